@@ -0,0 +1,11 @@
+DROP TRIGGER IF EXISTS set_updated_at ON books;
+DROP TRIGGER IF EXISTS set_updated_at ON games;
+DROP TRIGGER IF EXISTS set_updated_at ON projects;
+DROP TRIGGER IF EXISTS set_updated_at ON reviews;
+DROP TRIGGER IF EXISTS set_updated_at ON wplace;
+
+ALTER TABLE books DROP COLUMN created_at, DROP COLUMN updated_at;
+ALTER TABLE games DROP COLUMN created_at, DROP COLUMN updated_at;
+ALTER TABLE projects DROP COLUMN created_at, DROP COLUMN updated_at;
+ALTER TABLE reviews DROP COLUMN created_at, DROP COLUMN updated_at;
+ALTER TABLE wplace DROP COLUMN created_at, DROP COLUMN updated_at;
@@ -0,0 +1,5 @@
+ALTER TABLE books DROP COLUMN deleted_at;
+ALTER TABLE games DROP COLUMN deleted_at;
+ALTER TABLE projects DROP COLUMN deleted_at;
+ALTER TABLE reviews DROP COLUMN deleted_at;
+ALTER TABLE wplace DROP COLUMN deleted_at;
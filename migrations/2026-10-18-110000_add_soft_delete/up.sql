@@ -0,0 +1,11 @@
+ALTER TABLE books ADD COLUMN deleted_at TIMESTAMP;
+ALTER TABLE games ADD COLUMN deleted_at TIMESTAMP;
+ALTER TABLE projects ADD COLUMN deleted_at TIMESTAMP;
+ALTER TABLE reviews ADD COLUMN deleted_at TIMESTAMP;
+ALTER TABLE wplace ADD COLUMN deleted_at TIMESTAMP;
+
+CREATE INDEX books_deleted_at_idx ON books (deleted_at) WHERE deleted_at IS NOT NULL;
+CREATE INDEX games_deleted_at_idx ON games (deleted_at) WHERE deleted_at IS NOT NULL;
+CREATE INDEX projects_deleted_at_idx ON projects (deleted_at) WHERE deleted_at IS NOT NULL;
+CREATE INDEX reviews_deleted_at_idx ON reviews (deleted_at) WHERE deleted_at IS NOT NULL;
+CREATE INDEX wplace_deleted_at_idx ON wplace (deleted_at) WHERE deleted_at IS NOT NULL;
@@ -0,0 +1,7 @@
+DROP TRIGGER IF EXISTS record_revision ON books;
+DROP TRIGGER IF EXISTS record_revision ON games;
+DROP TRIGGER IF EXISTS record_revision ON projects;
+DROP TRIGGER IF EXISTS record_revision ON reviews;
+DROP TRIGGER IF EXISTS record_revision ON wplace;
+DROP FUNCTION IF EXISTS record_revision();
+DROP TABLE revisions;
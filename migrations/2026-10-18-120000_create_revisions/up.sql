@@ -0,0 +1,32 @@
+CREATE TABLE revisions (
+    id INTEGER GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+    item_type TEXT NOT NULL,
+    item_id INTEGER NOT NULL,
+    revision INTEGER NOT NULL,
+    data JSONB NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    UNIQUE (item_type, item_id, revision)
+);
+
+-- Stores the previous version of a row every time it changes, keyed by the
+-- table name and numbered per item.
+CREATE OR REPLACE FUNCTION record_revision() RETURNS trigger AS $$
+BEGIN
+    INSERT INTO revisions (item_type, item_id, revision, data)
+    SELECT TG_TABLE_NAME, OLD.id, COALESCE(MAX(revision), 0) + 1, to_jsonb(OLD)
+    FROM revisions
+    WHERE item_type = TG_TABLE_NAME AND item_id = OLD.id;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER record_revision AFTER UPDATE ON books
+    FOR EACH ROW WHEN (OLD.* IS DISTINCT FROM NEW.*) EXECUTE PROCEDURE record_revision();
+CREATE TRIGGER record_revision AFTER UPDATE ON games
+    FOR EACH ROW WHEN (OLD.* IS DISTINCT FROM NEW.*) EXECUTE PROCEDURE record_revision();
+CREATE TRIGGER record_revision AFTER UPDATE ON projects
+    FOR EACH ROW WHEN (OLD.* IS DISTINCT FROM NEW.*) EXECUTE PROCEDURE record_revision();
+CREATE TRIGGER record_revision AFTER UPDATE ON reviews
+    FOR EACH ROW WHEN (OLD.* IS DISTINCT FROM NEW.*) EXECUTE PROCEDURE record_revision();
+CREATE TRIGGER record_revision AFTER UPDATE ON wplace
+    FOR EACH ROW WHEN (OLD.* IS DISTINCT FROM NEW.*) EXECUTE PROCEDURE record_revision();
@@ -0,0 +1,4 @@
+DROP TABLE game_tags;
+DROP TABLE book_tags;
+DROP TABLE tags;
+DROP FUNCTION IF EXISTS normalize_tag(TEXT);
@@ -0,0 +1,109 @@
+-- Mirrors `normalize_tag` in src/handlers/tags.rs: "Sci-Fi" and "sci fi" both become "sci-fi".
+CREATE OR REPLACE FUNCTION normalize_tag(name TEXT) RETURNS TEXT AS $$
+    SELECT trim(BOTH '-' FROM regexp_replace(lower(name), '[^[:alnum:]]+', '-', 'g'));
+$$ LANGUAGE sql IMMUTABLE;
+
+CREATE TABLE tags (
+    id INTEGER GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+    name TEXT NOT NULL,
+    slug TEXT NOT NULL,
+    kind TEXT NOT NULL CHECK (kind IN ('genre', 'tag')),
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    UNIQUE (kind, slug)
+);
+
+CREATE TABLE book_tags (
+    book_id INTEGER NOT NULL REFERENCES books (id) ON DELETE CASCADE,
+    tag_id INTEGER NOT NULL REFERENCES tags (id) ON DELETE CASCADE,
+    PRIMARY KEY (book_id, tag_id)
+);
+
+CREATE TABLE game_tags (
+    game_id INTEGER NOT NULL REFERENCES games (id) ON DELETE CASCADE,
+    tag_id INTEGER NOT NULL REFERENCES tags (id) ON DELETE CASCADE,
+    PRIMARY KEY (game_id, tag_id)
+);
+
+CREATE INDEX book_tags_tag_id_idx ON book_tags (tag_id);
+CREATE INDEX game_tags_tag_id_idx ON game_tags (tag_id);
+
+-- Backfill the taxonomy from the existing free-text arrays, keeping the first
+-- spelling (alphabetically) of each normalized name.
+INSERT INTO tags (name, slug, kind)
+SELECT DISTINCT ON (slug, kind) name, slug, kind
+FROM (
+    SELECT trim(name) AS name, normalize_tag(name) AS slug, 'genre' AS kind
+    FROM books CROSS JOIN LATERAL unnest(books.genres) AS name
+    UNION ALL
+    SELECT trim(name), normalize_tag(name), 'genre'
+    FROM games CROSS JOIN LATERAL unnest(games.genres) AS name
+    UNION ALL
+    SELECT trim(name), normalize_tag(name), 'tag'
+    FROM books CROSS JOIN LATERAL unnest(books.tags) AS name
+    UNION ALL
+    SELECT trim(name), normalize_tag(name), 'tag'
+    FROM games CROSS JOIN LATERAL unnest(games.tags) AS name
+) AS names
+WHERE name IS NOT NULL AND slug <> ''
+ORDER BY slug, kind, name;
+
+INSERT INTO book_tags (book_id, tag_id)
+SELECT DISTINCT books.id, tag.id
+FROM books
+CROSS JOIN LATERAL unnest(books.genres) AS entry (name)
+JOIN tags AS tag ON tag.kind = 'genre' AND tag.slug = normalize_tag(entry.name)
+UNION
+SELECT DISTINCT books.id, tag.id
+FROM books
+CROSS JOIN LATERAL unnest(books.tags) AS entry (name)
+JOIN tags AS tag ON tag.kind = 'tag' AND tag.slug = normalize_tag(entry.name);
+
+INSERT INTO game_tags (game_id, tag_id)
+SELECT DISTINCT games.id, tag.id
+FROM games
+CROSS JOIN LATERAL unnest(games.genres) AS entry (name)
+JOIN tags AS tag ON tag.kind = 'genre' AND tag.slug = normalize_tag(entry.name)
+UNION
+SELECT DISTINCT games.id, tag.id
+FROM games
+CROSS JOIN LATERAL unnest(games.tags) AS entry (name)
+JOIN tags AS tag ON tag.kind = 'tag' AND tag.slug = normalize_tag(entry.name);
+
+-- Rewrite the arrays to the canonical spellings without flooding the revision history.
+ALTER TABLE books DISABLE TRIGGER record_revision;
+ALTER TABLE games DISABLE TRIGGER record_revision;
+
+UPDATE books SET
+    genres = ARRAY(
+        SELECT tag.name
+        FROM unnest(books.genres) WITH ORDINALITY AS entry (name, position)
+        JOIN tags AS tag ON tag.kind = 'genre' AND tag.slug = normalize_tag(entry.name)
+        GROUP BY tag.name
+        ORDER BY min(entry.position)
+    ),
+    tags = ARRAY(
+        SELECT tag.name
+        FROM unnest(books.tags) WITH ORDINALITY AS entry (name, position)
+        JOIN tags AS tag ON tag.kind = 'tag' AND tag.slug = normalize_tag(entry.name)
+        GROUP BY tag.name
+        ORDER BY min(entry.position)
+    );
+
+UPDATE games SET
+    genres = ARRAY(
+        SELECT tag.name
+        FROM unnest(games.genres) WITH ORDINALITY AS entry (name, position)
+        JOIN tags AS tag ON tag.kind = 'genre' AND tag.slug = normalize_tag(entry.name)
+        GROUP BY tag.name
+        ORDER BY min(entry.position)
+    ),
+    tags = ARRAY(
+        SELECT tag.name
+        FROM unnest(games.tags) WITH ORDINALITY AS entry (name, position)
+        JOIN tags AS tag ON tag.kind = 'tag' AND tag.slug = normalize_tag(entry.name)
+        GROUP BY tag.name
+        ORDER BY min(entry.position)
+    );
+
+ALTER TABLE books ENABLE TRIGGER record_revision;
+ALTER TABLE games ENABLE TRIGGER record_revision;
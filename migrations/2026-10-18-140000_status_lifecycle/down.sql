@@ -0,0 +1,19 @@
+DROP TRIGGER IF EXISTS record_status_change ON books;
+DROP TRIGGER IF EXISTS record_status_change ON games;
+DROP TRIGGER IF EXISTS check_status_transition ON books;
+DROP TRIGGER IF EXISTS check_status_transition ON games;
+DROP FUNCTION IF EXISTS record_status_change();
+DROP FUNCTION IF EXISTS check_status_transition();
+
+DROP TABLE status_changes;
+DROP TABLE status_transitions;
+
+ALTER TABLE books
+    DROP CONSTRAINT books_status_check,
+    DROP COLUMN started_at,
+    DROP COLUMN finished_at;
+
+ALTER TABLE games
+    DROP CONSTRAINT games_status_check,
+    DROP COLUMN started_at,
+    DROP COLUMN finished_at;
@@ -0,0 +1,199 @@
+-- How the free-text statuses used so far map onto the lifecycle. Blank ones
+-- become `planned`; anything else missing from here stops the migration.
+CREATE TEMPORARY TABLE legacy_statuses (
+    item_type TEXT NOT NULL,
+    legacy TEXT NOT NULL,
+    status TEXT NOT NULL,
+    PRIMARY KEY (item_type, legacy)
+);
+
+INSERT INTO legacy_statuses (item_type, legacy, status)
+SELECT item_type, legacy, status
+FROM (VALUES ('books'), ('games')) AS item_types (item_type)
+CROSS JOIN (VALUES
+    ('', 'planned'),
+    ('planned', 'planned'),
+    ('plan to read', 'planned'),
+    ('plan to play', 'planned'),
+    ('backlog', 'planned'),
+    ('wishlist', 'planned'),
+    ('in progress', 'in-progress'),
+    ('in-progress', 'in-progress'),
+    ('ongoing', 'in-progress'),
+    ('current', 'in-progress'),
+    ('paused', 'paused'),
+    ('on hold', 'paused'),
+    ('on-hold', 'paused'),
+    ('hiatus', 'paused'),
+    ('finished', 'finished'),
+    ('completed', 'finished'),
+    ('complete', 'finished'),
+    ('done', 'finished'),
+    ('dropped', 'dropped'),
+    ('abandoned', 'dropped')
+) AS common (legacy, status)
+UNION ALL
+VALUES
+    ('books', 'reading', 'in-progress'),
+    ('books', 'watching', 'in-progress'),
+    ('books', 'read', 'finished'),
+    ('books', 'watched', 'finished'),
+    ('games', 'playing', 'in-progress'),
+    ('games', 'beaten', 'finished'),
+    ('games', 'played', 'finished'),
+    ('games', '100%', 'finished');
+
+DO $$
+DECLARE
+    unknown TEXT;
+BEGIN
+    SELECT string_agg(DISTINCT format('%s: %L', item_type, status), ', ')
+    INTO unknown
+    FROM (
+        SELECT 'books' AS item_type, status FROM books
+        UNION ALL
+        SELECT 'games', status FROM games
+    ) AS existing
+    WHERE NOT EXISTS (
+        SELECT 1 FROM legacy_statuses AS legacy
+        WHERE legacy.item_type = existing.item_type
+          AND legacy.legacy = lower(trim(existing.status))
+    );
+
+    IF unknown IS NOT NULL THEN
+        RAISE EXCEPTION 'unrecognised statuses (%); change them to planned, in-progress, paused, finished or dropped and rerun the migration', unknown;
+    END IF;
+END;
+$$;
+
+ALTER TABLE books
+    ADD COLUMN started_at TIMESTAMP,
+    ADD COLUMN finished_at TIMESTAMP;
+
+ALTER TABLE games
+    ADD COLUMN started_at TIMESTAMP,
+    ADD COLUMN finished_at TIMESTAMP;
+
+-- Fold the existing statuses into the lifecycle and date them the way the
+-- trigger below would have: anything underway was started when it was added,
+-- and finished items were finished when they were last edited. The raw values
+-- are kept in `revisions` by the `record_revision` trigger.
+UPDATE books SET
+    status = legacy.status,
+    started_at = CASE WHEN legacy.status IN ('in-progress', 'paused', 'finished') THEN books.created_at END,
+    finished_at = CASE WHEN legacy.status = 'finished' THEN books.updated_at END
+FROM legacy_statuses AS legacy
+WHERE legacy.item_type = 'books' AND legacy.legacy = lower(trim(books.status));
+
+UPDATE games SET
+    status = legacy.status,
+    started_at = CASE WHEN legacy.status IN ('in-progress', 'paused', 'finished') THEN games.created_at END,
+    finished_at = CASE WHEN legacy.status = 'finished' THEN games.updated_at END
+FROM legacy_statuses AS legacy
+WHERE legacy.item_type = 'games' AND legacy.legacy = lower(trim(games.status));
+
+DROP TABLE legacy_statuses;
+
+ALTER TABLE books
+    ADD CONSTRAINT books_status_check
+        CHECK (status IN ('planned', 'in-progress', 'paused', 'finished', 'dropped'));
+
+ALTER TABLE games
+    ADD CONSTRAINT games_status_check
+        CHECK (status IN ('planned', 'in-progress', 'paused', 'finished', 'dropped'));
+
+CREATE TABLE status_transitions (
+    item_type TEXT NOT NULL,
+    from_status TEXT NOT NULL,
+    to_status TEXT NOT NULL,
+    PRIMARY KEY (item_type, from_status, to_status)
+);
+
+INSERT INTO status_transitions (item_type, from_status, to_status)
+SELECT item_type, from_status, to_status
+FROM (VALUES ('books'), ('games')) AS item_types (item_type)
+CROSS JOIN (VALUES
+    ('planned', 'in-progress'),
+    ('planned', 'dropped'),
+    ('in-progress', 'paused'),
+    ('in-progress', 'finished'),
+    ('in-progress', 'dropped'),
+    ('paused', 'in-progress'),
+    ('paused', 'dropped'),
+    ('finished', 'in-progress'),
+    ('dropped', 'planned'),
+    ('dropped', 'in-progress')
+) AS transitions (from_status, to_status);
+
+CREATE TABLE status_changes (
+    id INTEGER GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+    item_type TEXT NOT NULL,
+    item_id INTEGER NOT NULL,
+    from_status TEXT,
+    to_status TEXT NOT NULL,
+    changed_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX status_changes_item_idx ON status_changes (item_type, item_id);
+
+-- Rejects transitions missing from `status_transitions` and keeps the derived
+-- `started_at`/`finished_at` dates current.
+CREATE OR REPLACE FUNCTION check_status_transition() RETURNS trigger AS $$
+BEGIN
+    IF TG_OP = 'UPDATE' THEN
+        IF NEW.status IS NOT DISTINCT FROM OLD.status THEN
+            RETURN NEW;
+        END IF;
+
+        IF NOT EXISTS (
+            SELECT 1 FROM status_transitions
+            WHERE item_type = TG_TABLE_NAME
+              AND from_status = OLD.status
+              AND to_status = NEW.status
+        ) THEN
+            RAISE EXCEPTION 'invalid % status transition from % to %', TG_TABLE_NAME, OLD.status, NEW.status
+                USING ERRCODE = 'check_violation';
+        END IF;
+    END IF;
+
+    IF NEW.status = 'in-progress' AND NEW.started_at IS NULL THEN
+        NEW.started_at := current_timestamp;
+    END IF;
+
+    IF NEW.status = 'finished' THEN
+        NEW.finished_at := current_timestamp;
+    ELSE
+        NEW.finished_at := NULL;
+    END IF;
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION record_status_change() RETURNS trigger AS $$
+BEGIN
+    IF TG_OP = 'INSERT' THEN
+        INSERT INTO status_changes (item_type, item_id, from_status, to_status)
+        VALUES (TG_TABLE_NAME, NEW.id, NULL, NEW.status);
+    ELSIF NEW.status IS DISTINCT FROM OLD.status THEN
+        INSERT INTO status_changes (item_type, item_id, from_status, to_status)
+        VALUES (TG_TABLE_NAME, NEW.id, OLD.status, NEW.status);
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER check_status_transition BEFORE INSERT OR UPDATE OF status ON books
+    FOR EACH ROW EXECUTE PROCEDURE check_status_transition();
+CREATE TRIGGER check_status_transition BEFORE INSERT OR UPDATE OF status ON games
+    FOR EACH ROW EXECUTE PROCEDURE check_status_transition();
+CREATE TRIGGER record_status_change AFTER INSERT OR UPDATE OF status ON books
+    FOR EACH ROW EXECUTE PROCEDURE record_status_change();
+CREATE TRIGGER record_status_change AFTER INSERT OR UPDATE OF status ON games
+    FOR EACH ROW EXECUTE PROCEDURE record_status_change();
+
+-- Seed the history with each item's current status.
+INSERT INTO status_changes (item_type, item_id, from_status, to_status, changed_at)
+SELECT 'books', id, NULL, status, created_at FROM books
+UNION ALL
+SELECT 'games', id, NULL, status, created_at FROM games;
@@ -0,0 +1,10 @@
+DROP TABLE book_progress;
+
+ALTER TABLE books
+    DROP CONSTRAINT books_current_page_le_total_check,
+    DROP CONSTRAINT books_current_chapter_le_total_check,
+    DROP COLUMN percent,
+    DROP COLUMN current_page,
+    DROP COLUMN total_pages,
+    DROP COLUMN current_chapter,
+    DROP COLUMN total_chapters;
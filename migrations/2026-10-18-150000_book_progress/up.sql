@@ -0,0 +1,27 @@
+ALTER TABLE books
+    ADD COLUMN current_page INTEGER CHECK (current_page >= 0),
+    ADD COLUMN total_pages INTEGER CHECK (total_pages > 0),
+    ADD COLUMN current_chapter INTEGER CHECK (current_chapter >= 0),
+    ADD COLUMN total_chapters INTEGER CHECK (total_chapters > 0),
+    ADD COLUMN percent INTEGER GENERATED ALWAYS AS (
+        CASE
+            WHEN total_pages > 0 AND current_page IS NOT NULL
+                THEN LEAST(100, current_page * 100 / total_pages)
+            WHEN total_chapters > 0 AND current_chapter IS NOT NULL
+                THEN LEAST(100, current_chapter * 100 / total_chapters)
+        END
+    ) STORED,
+    ADD CONSTRAINT books_current_page_le_total_check CHECK (current_page <= total_pages),
+    ADD CONSTRAINT books_current_chapter_le_total_check CHECK (current_chapter <= total_chapters);
+
+CREATE TABLE book_progress (
+    id INTEGER GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+    book_id INTEGER NOT NULL REFERENCES books (id) ON DELETE CASCADE,
+    page INTEGER CHECK (page >= 0),
+    chapter INTEGER CHECK (chapter >= 0),
+    note TEXT,
+    logged_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    CHECK (page IS NOT NULL OR chapter IS NOT NULL)
+);
+
+CREATE INDEX book_progress_book_id_idx ON book_progress (book_id, logged_at);
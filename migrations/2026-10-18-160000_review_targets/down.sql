@@ -0,0 +1,4 @@
+ALTER TABLE reviews
+    DROP CONSTRAINT reviews_single_target_check,
+    DROP COLUMN book_id,
+    DROP COLUMN game_id;
@@ -0,0 +1,9 @@
+-- Existing reviews stay unattached (both columns NULL) until they are assigned
+-- through `POST /reviews/attach`; `GET /reviews/orphans` lists them.
+ALTER TABLE reviews
+    ADD COLUMN book_id INTEGER REFERENCES books (id) ON DELETE SET NULL,
+    ADD COLUMN game_id INTEGER REFERENCES games (id) ON DELETE SET NULL,
+    ADD CONSTRAINT reviews_single_target_check CHECK (num_nonnulls(book_id, game_id) <= 1);
+
+CREATE INDEX reviews_book_id_idx ON reviews (book_id, chapter);
+CREATE INDEX reviews_game_id_idx ON reviews (game_id, chapter);
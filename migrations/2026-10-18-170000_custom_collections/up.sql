@@ -0,0 +1,24 @@
+-- Collections defined at runtime. `fields` holds the field schema items are
+-- validated against; see `FieldDefinition` in src/handlers/collections.rs.
+CREATE TABLE collections (
+    id INTEGER GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE CHECK (name ~ '^[a-z0-9]+(-[a-z0-9]+)*$'),
+    description TEXT,
+    fields JSONB NOT NULL DEFAULT '[]' CHECK (jsonb_typeof(fields) = 'array'),
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE collection_items (
+    id INTEGER GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+    collection_id INTEGER NOT NULL REFERENCES collections (id) ON DELETE CASCADE,
+    data JSONB NOT NULL DEFAULT '{}' CHECK (jsonb_typeof(data) = 'object'),
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX collection_items_collection_id_idx ON collection_items (collection_id);
+CREATE INDEX collection_items_data_idx ON collection_items USING GIN (data jsonb_path_ops);
+
+SELECT diesel_manage_updated_at('collections');
+SELECT diesel_manage_updated_at('collection_items');
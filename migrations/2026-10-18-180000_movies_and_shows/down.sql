@@ -0,0 +1,7 @@
+DELETE FROM status_changes WHERE item_type IN ('movies', 'shows');
+DELETE FROM status_transitions WHERE item_type IN ('movies', 'shows');
+DELETE FROM revisions WHERE item_type IN ('movies', 'shows');
+
+DROP TABLE show_episodes;
+DROP TABLE shows;
+DROP TABLE movies;
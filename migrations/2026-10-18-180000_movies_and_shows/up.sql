@@ -0,0 +1,101 @@
+CREATE TABLE movies (
+    id INTEGER GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+    title TEXT NOT NULL,
+    director TEXT,
+    studio TEXT,
+    genres TEXT[] NOT NULL DEFAULT '{}',
+    tags TEXT[] NOT NULL DEFAULT '{}',
+    rating INTEGER NOT NULL,
+    status TEXT NOT NULL DEFAULT 'planned'
+        CONSTRAINT movies_status_check
+        CHECK (status IN ('planned', 'in-progress', 'paused', 'finished', 'dropped')),
+    description TEXT NOT NULL DEFAULT '',
+    my_thoughts TEXT NOT NULL DEFAULT '',
+    links JSONB,
+    cover_image TEXT NOT NULL DEFAULT '',
+    explicit BOOLEAN NOT NULL DEFAULT FALSE,
+    runtime_minutes INTEGER CHECK (runtime_minutes > 0),
+    release_year INTEGER,
+    first_watched_at TIMESTAMP,
+    last_watched_at TIMESTAMP,
+    rewatch_count INTEGER NOT NULL DEFAULT 0 CHECK (rewatch_count >= 0),
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    deleted_at TIMESTAMP,
+    started_at TIMESTAMP,
+    finished_at TIMESTAMP
+);
+
+CREATE TABLE shows (
+    id INTEGER GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+    title TEXT NOT NULL,
+    director TEXT,
+    studio TEXT,
+    genres TEXT[] NOT NULL DEFAULT '{}',
+    tags TEXT[] NOT NULL DEFAULT '{}',
+    rating INTEGER NOT NULL,
+    status TEXT NOT NULL DEFAULT 'planned'
+        CONSTRAINT shows_status_check
+        CHECK (status IN ('planned', 'in-progress', 'paused', 'finished', 'dropped')),
+    description TEXT NOT NULL DEFAULT '',
+    my_thoughts TEXT NOT NULL DEFAULT '',
+    links JSONB,
+    cover_image TEXT NOT NULL DEFAULT '',
+    explicit BOOLEAN NOT NULL DEFAULT FALSE,
+    -- Runtime of a single episode.
+    runtime_minutes INTEGER CHECK (runtime_minutes > 0),
+    total_seasons INTEGER CHECK (total_seasons > 0),
+    total_episodes INTEGER CHECK (total_episodes > 0),
+    current_season INTEGER CHECK (current_season > 0),
+    current_episode INTEGER CHECK (current_episode > 0),
+    episodes_watched INTEGER NOT NULL DEFAULT 0 CHECK (episodes_watched >= 0),
+    percent INTEGER GENERATED ALWAYS AS (
+        CASE WHEN total_episodes > 0
+            THEN LEAST(100, episodes_watched * 100 / total_episodes)
+        END
+    ) STORED,
+    first_watched_at TIMESTAMP,
+    last_watched_at TIMESTAMP,
+    rewatch_count INTEGER NOT NULL DEFAULT 0 CHECK (rewatch_count >= 0),
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    deleted_at TIMESTAMP,
+    started_at TIMESTAMP,
+    finished_at TIMESTAMP
+);
+
+CREATE TABLE show_episodes (
+    id INTEGER GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+    show_id INTEGER NOT NULL REFERENCES shows (id) ON DELETE CASCADE,
+    season INTEGER NOT NULL CHECK (season > 0),
+    episode INTEGER NOT NULL CHECK (episode > 0),
+    note TEXT,
+    watched_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX show_episodes_show_id_idx ON show_episodes (show_id, watched_at);
+
+SELECT diesel_manage_updated_at('movies');
+SELECT diesel_manage_updated_at('shows');
+
+CREATE TRIGGER record_revision AFTER UPDATE ON movies
+    FOR EACH ROW WHEN (OLD.* IS DISTINCT FROM NEW.*) EXECUTE PROCEDURE record_revision();
+CREATE TRIGGER record_revision AFTER UPDATE ON shows
+    FOR EACH ROW WHEN (OLD.* IS DISTINCT FROM NEW.*) EXECUTE PROCEDURE record_revision();
+
+CREATE TRIGGER check_status_transition BEFORE INSERT OR UPDATE OF status ON movies
+    FOR EACH ROW EXECUTE PROCEDURE check_status_transition();
+CREATE TRIGGER check_status_transition BEFORE INSERT OR UPDATE OF status ON shows
+    FOR EACH ROW EXECUTE PROCEDURE check_status_transition();
+CREATE TRIGGER record_status_change AFTER INSERT OR UPDATE OF status ON movies
+    FOR EACH ROW EXECUTE PROCEDURE record_status_change();
+CREATE TRIGGER record_status_change AFTER INSERT OR UPDATE OF status ON shows
+    FOR EACH ROW EXECUTE PROCEDURE record_status_change();
+
+-- Movies and shows start with the same lifecycle as books and games.
+INSERT INTO status_transitions (item_type, from_status, to_status)
+SELECT item_types.item_type, transitions.from_status, transitions.to_status
+FROM (VALUES ('movies'), ('shows')) AS item_types (item_type)
+CROSS JOIN (
+    SELECT DISTINCT from_status, to_status FROM status_transitions WHERE item_type = 'games'
+) AS transitions;
@@ -0,0 +1,4 @@
+DELETE FROM revisions WHERE item_type = 'albums';
+
+DROP TABLE album_tracks;
+DROP TABLE albums;
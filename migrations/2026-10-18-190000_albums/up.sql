@@ -0,0 +1,33 @@
+CREATE TABLE albums (
+    id INTEGER GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+    title TEXT NOT NULL,
+    artist TEXT NOT NULL,
+    release_year INTEGER,
+    genres TEXT[] NOT NULL DEFAULT '{}',
+    tags TEXT[] NOT NULL DEFAULT '{}',
+    -- Overall rating. When NULL the album is scored from its track ratings.
+    rating INTEGER,
+    description TEXT NOT NULL DEFAULT '',
+    my_thoughts TEXT NOT NULL DEFAULT '',
+    cover_image TEXT NOT NULL DEFAULT '',
+    explicit BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    deleted_at TIMESTAMP
+);
+
+CREATE TABLE album_tracks (
+    id INTEGER GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+    album_id INTEGER NOT NULL REFERENCES albums (id) ON DELETE CASCADE,
+    position INTEGER NOT NULL CHECK (position > 0),
+    title TEXT NOT NULL,
+    duration_seconds INTEGER CHECK (duration_seconds > 0),
+    rating INTEGER,
+    favorite BOOLEAN NOT NULL DEFAULT FALSE,
+    UNIQUE (album_id, position)
+);
+
+SELECT diesel_manage_updated_at('albums');
+
+CREATE TRIGGER record_revision AFTER UPDATE ON albums
+    FOR EACH ROW WHEN (OLD.* IS DISTINCT FROM NEW.*) EXECUTE PROCEDURE record_revision();
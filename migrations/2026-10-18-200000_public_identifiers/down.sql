@@ -0,0 +1,37 @@
+DROP TRIGGER record_slug_change ON albums;
+DROP TRIGGER assign_slug ON albums;
+ALTER TABLE albums DROP COLUMN slug, DROP COLUMN uuid;
+
+DROP TRIGGER record_slug_change ON books;
+DROP TRIGGER assign_slug ON books;
+ALTER TABLE books DROP COLUMN slug, DROP COLUMN uuid;
+
+DROP TRIGGER record_slug_change ON games;
+DROP TRIGGER assign_slug ON games;
+ALTER TABLE games DROP COLUMN slug, DROP COLUMN uuid;
+
+DROP TRIGGER record_slug_change ON movies;
+DROP TRIGGER assign_slug ON movies;
+ALTER TABLE movies DROP COLUMN slug, DROP COLUMN uuid;
+
+DROP TRIGGER record_slug_change ON projects;
+DROP TRIGGER assign_slug ON projects;
+ALTER TABLE projects DROP COLUMN slug, DROP COLUMN uuid;
+
+DROP TRIGGER record_slug_change ON reviews;
+DROP TRIGGER assign_slug ON reviews;
+ALTER TABLE reviews DROP COLUMN slug, DROP COLUMN uuid;
+
+DROP TRIGGER record_slug_change ON shows;
+DROP TRIGGER assign_slug ON shows;
+ALTER TABLE shows DROP COLUMN slug, DROP COLUMN uuid;
+
+DROP TRIGGER record_slug_change ON wplace;
+DROP TRIGGER assign_slug ON wplace;
+ALTER TABLE wplace DROP COLUMN slug, DROP COLUMN uuid;
+
+DROP TABLE slug_history;
+DROP FUNCTION record_slug_change();
+DROP FUNCTION assign_slug();
+DROP TABLE reserved_slugs;
+DROP FUNCTION slugify(TEXT);
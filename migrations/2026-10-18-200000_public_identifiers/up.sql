@@ -0,0 +1,209 @@
+-- Public identifiers: a random UUID that never changes and an editable,
+-- human-readable slug. Old slugs are kept in `slug_history` so links to them
+-- can be redirected.
+CREATE OR REPLACE FUNCTION slugify(value TEXT) RETURNS TEXT AS $$
+    SELECT trim(BOTH '-' FROM regexp_replace(lower(COALESCE(value, '')), '[^[:alnum:]]+', '-', 'g'));
+$$ LANGUAGE sql IMMUTABLE;
+
+-- Words taken by static routes next to `/<ref>` under each table's mount
+-- point, e.g. `/read-watch/search`. A slug equal to one of them could never
+-- be fetched, so new static routes need a row here too.
+CREATE TABLE reserved_slugs (
+    item_type TEXT NOT NULL,
+    slug TEXT NOT NULL,
+    PRIMARY KEY (item_type, slug)
+);
+
+INSERT INTO reserved_slugs (item_type, slug) VALUES
+    ('albums', 'bulk'),
+    ('albums', 'search'),
+    ('books', 'bulk'),
+    ('books', 'search'),
+    ('games', 'bulk'),
+    ('games', 'search'),
+    ('movies', 'bulk'),
+    ('movies', 'search'),
+    ('reviews', 'attach'),
+    ('reviews', 'orphans'),
+    ('reviews', 'search'),
+    ('shows', 'bulk'),
+    ('shows', 'search');
+
+-- Normalizes a given slug, or derives a unique one from the column named by
+-- the first trigger argument. Empty, purely numeric and reserved slugs are
+-- prefixed with the second argument so they can't be mistaken for ids or
+-- routes.
+CREATE OR REPLACE FUNCTION assign_slug() RETURNS trigger AS $$
+DECLARE
+    requested BOOLEAN := NEW.slug IS NOT NULL AND NEW.slug <> '';
+    base TEXT;
+    candidate TEXT;
+    suffix INTEGER := 1;
+    taken BOOLEAN;
+BEGIN
+    IF TG_OP = 'UPDATE' AND requested AND NEW.slug = OLD.slug THEN
+        RETURN NEW;
+    END IF;
+
+    IF requested THEN
+        base := slugify(NEW.slug);
+    ELSE
+        base := slugify(to_jsonb(NEW) ->> TG_ARGV[0]);
+    END IF;
+
+    IF base = ''
+        OR base ~ '^[0-9]+$'
+        OR EXISTS (SELECT 1 FROM reserved_slugs WHERE item_type = TG_TABLE_NAME AND slug = base)
+    THEN
+        base := trim(BOTH '-' FROM TG_ARGV[1] || '-' || base);
+    END IF;
+
+    -- A slug picked by hand must be free; the unique constraint rejects it otherwise.
+    IF requested THEN
+        NEW.slug := base;
+        RETURN NEW;
+    END IF;
+
+    candidate := base;
+    LOOP
+        EXECUTE format('SELECT EXISTS (SELECT 1 FROM %I WHERE slug = $1 AND id <> $2)', TG_TABLE_NAME)
+            INTO taken USING candidate, NEW.id;
+        EXIT WHEN NOT taken;
+        suffix := suffix + 1;
+        candidate := base || '-' || suffix;
+    END LOOP;
+
+    NEW.slug := candidate;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TABLE slug_history (
+    item_type TEXT NOT NULL,
+    slug TEXT NOT NULL,
+    item_id INTEGER NOT NULL,
+    changed_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (item_type, slug)
+);
+
+CREATE OR REPLACE FUNCTION record_slug_change() RETURNS trigger AS $$
+BEGIN
+    IF OLD.slug IS NOT NULL AND OLD.slug IS DISTINCT FROM NEW.slug THEN
+        INSERT INTO slug_history (item_type, slug, item_id)
+        VALUES (TG_TABLE_NAME, OLD.slug, NEW.id)
+        ON CONFLICT (item_type, slug) DO UPDATE
+            SET item_id = EXCLUDED.item_id, changed_at = NOW();
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+ALTER TABLE albums
+    ADD COLUMN uuid UUID NOT NULL DEFAULT gen_random_uuid() UNIQUE,
+    ADD COLUMN slug TEXT;
+
+CREATE TRIGGER assign_slug BEFORE INSERT OR UPDATE OF slug ON albums
+    FOR EACH ROW EXECUTE PROCEDURE assign_slug('title', 'album');
+CREATE TRIGGER record_slug_change AFTER UPDATE OF slug ON albums
+    FOR EACH ROW EXECUTE PROCEDURE record_slug_change();
+
+ALTER TABLE books
+    ADD COLUMN uuid UUID NOT NULL DEFAULT gen_random_uuid() UNIQUE,
+    ADD COLUMN slug TEXT;
+
+CREATE TRIGGER assign_slug BEFORE INSERT OR UPDATE OF slug ON books
+    FOR EACH ROW EXECUTE PROCEDURE assign_slug('title', 'book');
+CREATE TRIGGER record_slug_change AFTER UPDATE OF slug ON books
+    FOR EACH ROW EXECUTE PROCEDURE record_slug_change();
+
+ALTER TABLE games
+    ADD COLUMN uuid UUID NOT NULL DEFAULT gen_random_uuid() UNIQUE,
+    ADD COLUMN slug TEXT;
+
+CREATE TRIGGER assign_slug BEFORE INSERT OR UPDATE OF slug ON games
+    FOR EACH ROW EXECUTE PROCEDURE assign_slug('title', 'game');
+CREATE TRIGGER record_slug_change AFTER UPDATE OF slug ON games
+    FOR EACH ROW EXECUTE PROCEDURE record_slug_change();
+
+ALTER TABLE movies
+    ADD COLUMN uuid UUID NOT NULL DEFAULT gen_random_uuid() UNIQUE,
+    ADD COLUMN slug TEXT;
+
+CREATE TRIGGER assign_slug BEFORE INSERT OR UPDATE OF slug ON movies
+    FOR EACH ROW EXECUTE PROCEDURE assign_slug('title', 'movie');
+CREATE TRIGGER record_slug_change AFTER UPDATE OF slug ON movies
+    FOR EACH ROW EXECUTE PROCEDURE record_slug_change();
+
+ALTER TABLE projects
+    ADD COLUMN uuid UUID NOT NULL DEFAULT gen_random_uuid() UNIQUE,
+    ADD COLUMN slug TEXT;
+
+CREATE TRIGGER assign_slug BEFORE INSERT OR UPDATE OF slug ON projects
+    FOR EACH ROW EXECUTE PROCEDURE assign_slug('name', 'project');
+CREATE TRIGGER record_slug_change AFTER UPDATE OF slug ON projects
+    FOR EACH ROW EXECUTE PROCEDURE record_slug_change();
+
+ALTER TABLE reviews
+    ADD COLUMN uuid UUID NOT NULL DEFAULT gen_random_uuid() UNIQUE,
+    ADD COLUMN slug TEXT;
+
+CREATE TRIGGER assign_slug BEFORE INSERT OR UPDATE OF slug ON reviews
+    FOR EACH ROW EXECUTE PROCEDURE assign_slug('chapter', 'chapter');
+CREATE TRIGGER record_slug_change AFTER UPDATE OF slug ON reviews
+    FOR EACH ROW EXECUTE PROCEDURE record_slug_change();
+
+ALTER TABLE shows
+    ADD COLUMN uuid UUID NOT NULL DEFAULT gen_random_uuid() UNIQUE,
+    ADD COLUMN slug TEXT;
+
+CREATE TRIGGER assign_slug BEFORE INSERT OR UPDATE OF slug ON shows
+    FOR EACH ROW EXECUTE PROCEDURE assign_slug('title', 'show');
+CREATE TRIGGER record_slug_change AFTER UPDATE OF slug ON shows
+    FOR EACH ROW EXECUTE PROCEDURE record_slug_change();
+
+ALTER TABLE wplace
+    ADD COLUMN uuid UUID NOT NULL DEFAULT gen_random_uuid() UNIQUE,
+    ADD COLUMN slug TEXT;
+
+CREATE TRIGGER assign_slug BEFORE INSERT OR UPDATE OF slug ON wplace
+    FOR EACH ROW EXECUTE PROCEDURE assign_slug('alt', 'wplace');
+CREATE TRIGGER record_slug_change AFTER UPDATE OF slug ON wplace
+    FOR EACH ROW EXECUTE PROCEDURE record_slug_change();
+
+-- Backfill slugs for existing rows without touching `updated_at` or the
+-- revision history.
+ALTER TABLE albums DISABLE TRIGGER record_revision, DISABLE TRIGGER set_updated_at;
+ALTER TABLE books DISABLE TRIGGER record_revision, DISABLE TRIGGER set_updated_at;
+ALTER TABLE games DISABLE TRIGGER record_revision, DISABLE TRIGGER set_updated_at;
+ALTER TABLE movies DISABLE TRIGGER record_revision, DISABLE TRIGGER set_updated_at;
+ALTER TABLE projects DISABLE TRIGGER record_revision, DISABLE TRIGGER set_updated_at;
+ALTER TABLE reviews DISABLE TRIGGER record_revision, DISABLE TRIGGER set_updated_at;
+ALTER TABLE shows DISABLE TRIGGER record_revision, DISABLE TRIGGER set_updated_at;
+ALTER TABLE wplace DISABLE TRIGGER record_revision, DISABLE TRIGGER set_updated_at;
+
+UPDATE albums SET slug = NULL;
+UPDATE books SET slug = NULL;
+UPDATE games SET slug = NULL;
+UPDATE movies SET slug = NULL;
+UPDATE projects SET slug = NULL;
+UPDATE reviews SET slug = NULL;
+UPDATE shows SET slug = NULL;
+UPDATE wplace SET slug = NULL;
+
+ALTER TABLE albums ENABLE TRIGGER record_revision, ENABLE TRIGGER set_updated_at;
+ALTER TABLE books ENABLE TRIGGER record_revision, ENABLE TRIGGER set_updated_at;
+ALTER TABLE games ENABLE TRIGGER record_revision, ENABLE TRIGGER set_updated_at;
+ALTER TABLE movies ENABLE TRIGGER record_revision, ENABLE TRIGGER set_updated_at;
+ALTER TABLE projects ENABLE TRIGGER record_revision, ENABLE TRIGGER set_updated_at;
+ALTER TABLE reviews ENABLE TRIGGER record_revision, ENABLE TRIGGER set_updated_at;
+ALTER TABLE shows ENABLE TRIGGER record_revision, ENABLE TRIGGER set_updated_at;
+ALTER TABLE wplace ENABLE TRIGGER record_revision, ENABLE TRIGGER set_updated_at;
+
+ALTER TABLE albums ALTER COLUMN slug SET NOT NULL, ADD UNIQUE (slug);
+ALTER TABLE books ALTER COLUMN slug SET NOT NULL, ADD UNIQUE (slug);
+ALTER TABLE games ALTER COLUMN slug SET NOT NULL, ADD UNIQUE (slug);
+ALTER TABLE movies ALTER COLUMN slug SET NOT NULL, ADD UNIQUE (slug);
+ALTER TABLE projects ALTER COLUMN slug SET NOT NULL, ADD UNIQUE (slug);
+ALTER TABLE reviews ALTER COLUMN slug SET NOT NULL, ADD UNIQUE (slug);
+ALTER TABLE shows ALTER COLUMN slug SET NOT NULL, ADD UNIQUE (slug);
+ALTER TABLE wplace ALTER COLUMN slug SET NOT NULL, ADD UNIQUE (slug);
@@ -0,0 +1,11 @@
+DELETE FROM reserved_slugs WHERE item_type = 'books' AND slug = 'series';
+
+DROP INDEX books_series_id_idx;
+
+ALTER TABLE books
+    DROP CONSTRAINT books_series_volume_key,
+    DROP CONSTRAINT books_series_volume_check,
+    DROP COLUMN series_volume,
+    DROP COLUMN series_id;
+
+DROP TABLE series;
@@ -0,0 +1,25 @@
+CREATE TABLE series (
+    id INTEGER GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    description TEXT NOT NULL DEFAULT '',
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+SELECT diesel_manage_updated_at('series');
+
+-- A book belongs to at most one series. Volume numbers are optional (for
+-- companion books and the like) but unique within a series when given.
+ALTER TABLE books
+    ADD COLUMN series_id INTEGER REFERENCES series (id),
+    ADD COLUMN series_volume INTEGER,
+    ADD CONSTRAINT books_series_volume_check
+        CHECK (series_volume IS NULL OR (series_id IS NOT NULL AND series_volume > 0)),
+    ADD CONSTRAINT books_series_volume_key UNIQUE (series_id, series_volume);
+
+CREATE INDEX books_series_id_idx ON books (series_id);
+
+-- `/read-watch/series` now sits next to `/read-watch/<ref>`; re-slug any book
+-- that already took the word.
+INSERT INTO reserved_slugs (item_type, slug) VALUES ('books', 'series');
+UPDATE books SET slug = NULL WHERE slug = 'series';
@@ -0,0 +1,19 @@
+DROP INDEX games_links_idx;
+DROP INDEX books_links_idx;
+
+CREATE FUNCTION links_to_array(links JSONB) RETURNS JSONB[] AS $$
+    SELECT ARRAY(SELECT jsonb_array_elements(links))
+$$ LANGUAGE sql IMMUTABLE;
+
+ALTER TABLE books
+    DROP CONSTRAINT books_links_check,
+    ALTER COLUMN links DROP NOT NULL,
+    ALTER COLUMN links DROP DEFAULT,
+    ALTER COLUMN links TYPE JSONB[] USING links_to_array(links);
+
+ALTER TABLE games
+    DROP CONSTRAINT games_links_check,
+    ALTER COLUMN links DROP NOT NULL,
+    ALTER COLUMN links DROP DEFAULT;
+
+DROP FUNCTION links_to_array(JSONB);
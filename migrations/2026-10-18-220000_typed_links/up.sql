@@ -0,0 +1,102 @@
+-- Books stored links as JSONB[] and games as a single free-form JSONB value.
+-- Both become a JSONB array of `{"kind", "label", "url"}` objects; see `Link`
+-- in src/models.rs.
+
+-- Turns one legacy entry (a URL string or an object with a URL under `url`,
+-- `href` or `link`) into a typed link, or NULL when there is no usable URL.
+-- `fallback_label` is the key the entry was stored under, if any.
+CREATE FUNCTION normalize_link(entry JSONB, fallback_label TEXT) RETURNS JSONB AS $$
+DECLARE
+    link_url TEXT;
+    link_kind TEXT;
+    link_label TEXT;
+BEGIN
+    CASE jsonb_typeof(entry)
+        WHEN 'string' THEN link_url := entry #>> '{}';
+        WHEN 'object' THEN link_url := COALESCE(entry ->> 'url', entry ->> 'href', entry ->> 'link');
+        ELSE RETURN NULL;
+    END CASE;
+
+    link_url := btrim(COALESCE(link_url, ''));
+    IF link_url !~* '^https?://' THEN
+        -- Bare domains such as `example.com/page` were common; anything else
+        -- is not a link.
+        IF link_url ~ '^[^\s/]+\.[^\s]+$' THEN
+            link_url := 'https://' || link_url;
+        ELSE
+            RETURN NULL;
+        END IF;
+    END IF;
+
+    link_kind := lower(COALESCE(entry ->> 'kind', entry ->> 'type', fallback_label, ''));
+    IF link_kind NOT IN ('store', 'wiki', 'official', 'review') THEN
+        link_kind := 'other';
+    END IF;
+
+    link_label := NULLIF(btrim(COALESCE(
+        entry ->> 'label', entry ->> 'name', entry ->> 'title', fallback_label, ''
+    )), '');
+
+    RETURN jsonb_build_object('kind', link_kind, 'label', COALESCE(link_label, link_url), 'url', link_url);
+END;
+$$ LANGUAGE plpgsql IMMUTABLE;
+
+-- Accepts an array of entries, an object mapping labels to entries, a single
+-- entry, or NULL.
+CREATE FUNCTION normalize_links(links JSONB) RETURNS JSONB AS $$
+DECLARE
+    result JSONB := '[]';
+    entry RECORD;
+    link JSONB;
+BEGIN
+    IF links IS NULL THEN
+        RETURN result;
+    ELSIF jsonb_typeof(links) = 'array' THEN
+        FOR entry IN SELECT value FROM jsonb_array_elements(links) LOOP
+            link := normalize_link(entry.value, NULL);
+            IF link IS NOT NULL THEN
+                result := result || jsonb_build_array(link);
+            END IF;
+        END LOOP;
+    ELSIF jsonb_typeof(links) = 'object' AND NOT (links ?| ARRAY['url', 'href', 'link']) THEN
+        FOR entry IN SELECT key, value FROM jsonb_each(links) LOOP
+            link := normalize_link(entry.value, entry.key);
+            IF link IS NOT NULL THEN
+                result := result || jsonb_build_array(link);
+            END IF;
+        END LOOP;
+    ELSE
+        link := normalize_link(links, NULL);
+        IF link IS NOT NULL THEN
+            result := result || jsonb_build_array(link);
+        END IF;
+    END IF;
+
+    RETURN result;
+END;
+$$ LANGUAGE plpgsql IMMUTABLE;
+
+-- Changing the column type rewrites the rows without firing update triggers,
+-- so no revisions are recorded for the conversion itself.
+ALTER TABLE books
+    ALTER COLUMN links TYPE JSONB USING normalize_links(to_jsonb(links)),
+    ALTER COLUMN links SET DEFAULT '[]',
+    ALTER COLUMN links SET NOT NULL,
+    ADD CONSTRAINT books_links_check CHECK (jsonb_typeof(links) = 'array');
+
+ALTER TABLE games
+    ALTER COLUMN links TYPE JSONB USING normalize_links(links),
+    ALTER COLUMN links SET DEFAULT '[]',
+    ALTER COLUMN links SET NOT NULL,
+    ADD CONSTRAINT games_links_check CHECK (jsonb_typeof(links) = 'array');
+
+-- Keep older revisions restorable.
+UPDATE revisions
+SET data = jsonb_set(data, '{links}', normalize_links(data -> 'links'))
+WHERE item_type IN ('books', 'games') AND data ? 'links';
+
+CREATE INDEX books_links_idx ON books USING GIN (links jsonb_path_ops);
+CREATE INDEX games_links_idx ON games USING GIN (links jsonb_path_ops);
+
+DROP FUNCTION normalize_links(JSONB);
+DROP FUNCTION normalize_link(JSONB, TEXT);
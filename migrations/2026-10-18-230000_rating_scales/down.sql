@@ -0,0 +1,18 @@
+DROP TRIGGER check_rating ON shows;
+DROP TRIGGER check_rating ON reviews;
+DROP TRIGGER check_rating ON movies;
+DROP TRIGGER check_rating ON games;
+DROP TRIGGER check_rating ON books;
+DROP TRIGGER check_rating ON album_tracks;
+DROP TRIGGER check_rating ON albums;
+DROP FUNCTION check_rating();
+
+ALTER TABLE shows ALTER COLUMN rating TYPE INTEGER USING round(rating);
+ALTER TABLE reviews ALTER COLUMN rating TYPE INTEGER USING round(rating);
+ALTER TABLE movies ALTER COLUMN rating TYPE INTEGER USING round(rating);
+ALTER TABLE games ALTER COLUMN rating TYPE INTEGER USING round(rating);
+ALTER TABLE books ALTER COLUMN rating TYPE INTEGER USING round(rating);
+ALTER TABLE album_tracks ALTER COLUMN rating TYPE INTEGER USING round(rating);
+ALTER TABLE albums ALTER COLUMN rating TYPE INTEGER USING round(rating);
+
+DROP TABLE rating_scales;
@@ -0,0 +1,68 @@
+-- Ratings for each resource run from 0 to `max_rating` in increments of
+-- `step`. Album tracks are rated on the albums scale.
+CREATE TABLE rating_scales (
+    item_type TEXT PRIMARY KEY
+        CHECK (item_type IN ('albums', 'books', 'games', 'movies', 'reviews', 'shows')),
+    max_rating NUMERIC(6, 2) NOT NULL CHECK (max_rating > 0 AND max_rating <= 1000),
+    step NUMERIC(6, 2) NOT NULL CHECK (step > 0 AND step <= max_rating),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+SELECT diesel_manage_updated_at('rating_scales');
+
+INSERT INTO rating_scales (item_type, max_rating, step)
+VALUES ('albums', 10, 0.5),
+       ('books', 10, 0.5),
+       ('games', 10, 0.5),
+       ('movies', 10, 0.5),
+       ('reviews', 10, 0.5),
+       ('shows', 10, 0.5);
+
+-- Existing values are kept as they are; `rescale-ratings` brings ratings
+-- recorded on another scale into range.
+ALTER TABLE albums ALTER COLUMN rating TYPE NUMERIC(6, 2);
+ALTER TABLE album_tracks ALTER COLUMN rating TYPE NUMERIC(6, 2);
+ALTER TABLE books ALTER COLUMN rating TYPE NUMERIC(6, 2);
+ALTER TABLE games ALTER COLUMN rating TYPE NUMERIC(6, 2);
+ALTER TABLE movies ALTER COLUMN rating TYPE NUMERIC(6, 2);
+ALTER TABLE reviews ALTER COLUMN rating TYPE NUMERIC(6, 2);
+ALTER TABLE shows ALTER COLUMN rating TYPE NUMERIC(6, 2);
+
+-- Rejects ratings outside the scale named by the trigger argument.
+CREATE OR REPLACE FUNCTION check_rating() RETURNS trigger AS $$
+DECLARE
+    scale rating_scales%ROWTYPE;
+BEGIN
+    IF NEW.rating IS NULL THEN
+        RETURN NEW;
+    END IF;
+
+    SELECT * INTO scale FROM rating_scales WHERE item_type = TG_ARGV[0];
+    IF NOT FOUND THEN
+        RETURN NEW;
+    END IF;
+
+    IF NEW.rating < 0 OR NEW.rating > scale.max_rating OR mod(NEW.rating, scale.step) <> 0 THEN
+        RAISE EXCEPTION '% rating % is not between 0 and % in steps of %',
+            TG_TABLE_NAME, NEW.rating, scale.max_rating, scale.step
+            USING ERRCODE = 'check_violation';
+    END IF;
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER check_rating BEFORE INSERT OR UPDATE OF rating ON albums
+    FOR EACH ROW EXECUTE PROCEDURE check_rating('albums');
+CREATE TRIGGER check_rating BEFORE INSERT OR UPDATE OF rating ON album_tracks
+    FOR EACH ROW EXECUTE PROCEDURE check_rating('albums');
+CREATE TRIGGER check_rating BEFORE INSERT OR UPDATE OF rating ON books
+    FOR EACH ROW EXECUTE PROCEDURE check_rating('books');
+CREATE TRIGGER check_rating BEFORE INSERT OR UPDATE OF rating ON games
+    FOR EACH ROW EXECUTE PROCEDURE check_rating('games');
+CREATE TRIGGER check_rating BEFORE INSERT OR UPDATE OF rating ON movies
+    FOR EACH ROW EXECUTE PROCEDURE check_rating('movies');
+CREATE TRIGGER check_rating BEFORE INSERT OR UPDATE OF rating ON reviews
+    FOR EACH ROW EXECUTE PROCEDURE check_rating('reviews');
+CREATE TRIGGER check_rating BEFORE INSERT OR UPDATE OF rating ON shows
+    FOR EACH ROW EXECUTE PROCEDURE check_rating('shows');
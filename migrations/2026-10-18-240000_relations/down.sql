@@ -0,0 +1,11 @@
+DROP TRIGGER delete_relations ON wplace;
+DROP TRIGGER delete_relations ON shows;
+DROP TRIGGER delete_relations ON reviews;
+DROP TRIGGER delete_relations ON projects;
+DROP TRIGGER delete_relations ON movies;
+DROP TRIGGER delete_relations ON games;
+DROP TRIGGER delete_relations ON books;
+DROP TRIGGER delete_relations ON albums;
+DROP FUNCTION delete_relations();
+
+DROP TABLE relations;
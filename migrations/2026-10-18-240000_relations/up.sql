@@ -0,0 +1,49 @@
+-- A typed link between two items, read as "source <kind> target", e.g. a game
+-- that is an `adaptation` of a book. Inverse kinds (`prequel`,
+-- `adapted-into`) are stored flipped onto their counterparts, and symmetric
+-- kinds with the lower endpoint as the source, so each relation has one row.
+CREATE TABLE relations (
+    id INTEGER GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+    source_type TEXT NOT NULL
+        CHECK (source_type IN ('albums', 'books', 'games', 'movies', 'projects', 'reviews', 'shows', 'wplace')),
+    source_id INTEGER NOT NULL,
+    kind TEXT NOT NULL CHECK (kind IN ('adaptation', 'sequel', 'similar', 'same-universe')),
+    target_type TEXT NOT NULL
+        CHECK (target_type IN ('albums', 'books', 'games', 'movies', 'projects', 'reviews', 'shows', 'wplace')),
+    target_id INTEGER NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    CHECK ((source_type, source_id) <> (target_type, target_id)),
+    CHECK (kind NOT IN ('similar', 'same-universe') OR (source_type, source_id) < (target_type, target_id)),
+    UNIQUE (source_type, source_id, kind, target_type, target_id)
+);
+
+CREATE INDEX relations_source_idx ON relations (source_type, source_id);
+CREATE INDEX relations_target_idx ON relations (target_type, target_id);
+
+-- Items can't be referenced by foreign key from here, so drop their relations
+-- when they are purged.
+CREATE OR REPLACE FUNCTION delete_relations() RETURNS trigger AS $$
+BEGIN
+    DELETE FROM relations
+    WHERE (source_type = TG_TABLE_NAME AND source_id = OLD.id)
+       OR (target_type = TG_TABLE_NAME AND target_id = OLD.id);
+    RETURN OLD;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER delete_relations AFTER DELETE ON albums
+    FOR EACH ROW EXECUTE PROCEDURE delete_relations();
+CREATE TRIGGER delete_relations AFTER DELETE ON books
+    FOR EACH ROW EXECUTE PROCEDURE delete_relations();
+CREATE TRIGGER delete_relations AFTER DELETE ON games
+    FOR EACH ROW EXECUTE PROCEDURE delete_relations();
+CREATE TRIGGER delete_relations AFTER DELETE ON movies
+    FOR EACH ROW EXECUTE PROCEDURE delete_relations();
+CREATE TRIGGER delete_relations AFTER DELETE ON projects
+    FOR EACH ROW EXECUTE PROCEDURE delete_relations();
+CREATE TRIGGER delete_relations AFTER DELETE ON reviews
+    FOR EACH ROW EXECUTE PROCEDURE delete_relations();
+CREATE TRIGGER delete_relations AFTER DELETE ON shows
+    FOR EACH ROW EXECUTE PROCEDURE delete_relations();
+CREATE TRIGGER delete_relations AFTER DELETE ON wplace
+    FOR EACH ROW EXECUTE PROCEDURE delete_relations();
@@ -0,0 +1,19 @@
+DELETE FROM reserved_slugs WHERE item_type = 'wplace' AND slug IN ('albums', 'artworks');
+
+DROP INDEX wplace_canvas_idx;
+DROP INDEX wplace_album_id_idx;
+DROP INDEX wplace_artwork_idx;
+
+ALTER TABLE wplace
+    DROP CONSTRAINT wplace_position_check,
+    DROP COLUMN album_id,
+    DROP COLUMN artwork,
+    DROP COLUMN captured_at,
+    DROP COLUMN height,
+    DROP COLUMN width,
+    DROP COLUMN pixel_y,
+    DROP COLUMN pixel_x,
+    DROP COLUMN tile_y,
+    DROP COLUMN tile_x;
+
+DROP TABLE wplace_albums;
@@ -0,0 +1,49 @@
+CREATE TABLE wplace_albums (
+    id INTEGER GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    description TEXT NOT NULL DEFAULT '',
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+SELECT diesel_manage_updated_at('wplace_albums');
+
+-- The canvas is a 2048x2048 grid of 1000x1000 pixel tiles. A screenshot's
+-- position is the tile and pixel of its top-left corner, and its bounding box
+-- extends `width` by `height` pixels from there, possibly across tiles. The
+-- position is all-or-nothing so older screenshots can stay unplaced.
+ALTER TABLE wplace
+    ADD COLUMN tile_x INTEGER,
+    ADD COLUMN tile_y INTEGER,
+    ADD COLUMN pixel_x INTEGER,
+    ADD COLUMN pixel_y INTEGER,
+    ADD COLUMN width INTEGER,
+    ADD COLUMN height INTEGER,
+    ADD COLUMN captured_at TIMESTAMP,
+    ADD COLUMN artwork TEXT,
+    ADD COLUMN album_id INTEGER REFERENCES wplace_albums (id) ON DELETE SET NULL,
+    ADD CONSTRAINT wplace_position_check CHECK (
+        (tile_x IS NULL AND tile_y IS NULL AND pixel_x IS NULL AND pixel_y IS NULL
+            AND width IS NULL AND height IS NULL)
+        OR (tile_x BETWEEN 0 AND 2047 AND tile_y BETWEEN 0 AND 2047
+            AND pixel_x BETWEEN 0 AND 999 AND pixel_y BETWEEN 0 AND 999
+            AND width > 0 AND height > 0)
+    );
+
+ALTER TABLE wplace DISABLE TRIGGER record_revision, DISABLE TRIGGER set_updated_at;
+UPDATE wplace SET captured_at = created_at;
+ALTER TABLE wplace ENABLE TRIGGER record_revision, ENABLE TRIGGER set_updated_at;
+
+ALTER TABLE wplace
+    ALTER COLUMN captured_at SET DEFAULT NOW(),
+    ALTER COLUMN captured_at SET NOT NULL;
+
+CREATE INDEX wplace_artwork_idx ON wplace (artwork, captured_at) WHERE artwork IS NOT NULL;
+CREATE INDEX wplace_album_id_idx ON wplace (album_id);
+CREATE INDEX wplace_canvas_idx ON wplace ((tile_x * 1000 + pixel_x), (tile_y * 1000 + pixel_y))
+    WHERE tile_x IS NOT NULL;
+
+-- `/wplace/albums` and `/wplace/artworks` now sit next to `/wplace/<ref>`;
+-- re-slug any screenshot that already took one of the words.
+INSERT INTO reserved_slugs (item_type, slug) VALUES ('wplace', 'albums'), ('wplace', 'artworks');
+UPDATE wplace SET slug = NULL WHERE slug IN ('albums', 'artworks');
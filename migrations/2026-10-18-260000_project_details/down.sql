@@ -0,0 +1,28 @@
+DELETE FROM reserved_slugs WHERE item_type = 'projects' AND slug = 'order';
+
+DROP INDEX projects_position_idx;
+DROP TRIGGER assign_project_position ON projects;
+DROP FUNCTION assign_project_position();
+
+ALTER TABLE projects ADD COLUMN install_command TEXT;
+
+ALTER TABLE projects DISABLE TRIGGER record_revision, DISABLE TRIGGER set_updated_at;
+
+UPDATE projects SET install_command = install_commands ->> 'any';
+
+UPDATE revisions
+SET data = (data - 'install_commands')
+    || jsonb_build_object('install_command', data -> 'install_commands' ->> 'any')
+WHERE item_type = 'projects';
+
+ALTER TABLE projects ENABLE TRIGGER record_revision, ENABLE TRIGGER set_updated_at;
+
+ALTER TABLE projects
+    DROP CONSTRAINT projects_position_check,
+    DROP COLUMN position,
+    DROP COLUMN featured,
+    DROP COLUMN install_commands,
+    DROP COLUMN links,
+    DROP COLUMN status,
+    DROP COLUMN secondary_languages,
+    DROP COLUMN primary_language;
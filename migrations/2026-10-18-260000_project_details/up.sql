@@ -0,0 +1,64 @@
+-- Install commands become a JSONB object keyed by platform, e.g.
+-- `{"linux": "cargo install foo", "windows": "scoop install foo"}`. An
+-- existing single command applies to every platform and is kept under `any`.
+ALTER TABLE projects
+    ADD COLUMN primary_language TEXT,
+    ADD COLUMN secondary_languages TEXT[] NOT NULL DEFAULT '{}',
+    ADD COLUMN status TEXT NOT NULL DEFAULT 'active'
+        CONSTRAINT projects_status_check CHECK (status IN ('active', 'maintained', 'archived')),
+    ADD COLUMN links JSONB NOT NULL DEFAULT '[]'
+        CONSTRAINT projects_links_check CHECK (jsonb_typeof(links) = 'array'),
+    ADD COLUMN install_commands JSONB NOT NULL DEFAULT '{}'
+        CONSTRAINT projects_install_commands_check CHECK (jsonb_typeof(install_commands) = 'object'),
+    ADD COLUMN featured BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN position INTEGER;
+
+ALTER TABLE projects DISABLE TRIGGER record_revision, DISABLE TRIGGER set_updated_at;
+
+UPDATE projects
+SET install_commands = jsonb_build_object('any', install_command)
+WHERE install_command IS NOT NULL AND install_command <> '';
+
+-- Start from the order projects used to come back in.
+UPDATE projects
+SET position = ordered.position
+FROM (SELECT id, ROW_NUMBER() OVER (ORDER BY created_at, id) AS position FROM projects) AS ordered
+WHERE projects.id = ordered.id;
+
+UPDATE revisions
+SET data = (data - 'install_command')
+    || jsonb_build_object(
+        'install_commands',
+        CASE
+            WHEN COALESCE(data ->> 'install_command', '') = '' THEN '{}'::jsonb
+            ELSE jsonb_build_object('any', data ->> 'install_command')
+        END
+    )
+WHERE item_type = 'projects';
+
+ALTER TABLE projects ENABLE TRIGGER record_revision, ENABLE TRIGGER set_updated_at;
+
+ALTER TABLE projects
+    DROP COLUMN install_command,
+    ALTER COLUMN position SET NOT NULL,
+    ADD CONSTRAINT projects_position_check CHECK (position > 0);
+
+-- New projects go to the end of the list unless given a position.
+CREATE FUNCTION assign_project_position() RETURNS trigger AS $$
+BEGIN
+    IF NEW.position IS NULL THEN
+        SELECT COALESCE(MAX(position), 0) + 1 INTO NEW.position FROM projects;
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER assign_project_position BEFORE INSERT ON projects
+    FOR EACH ROW EXECUTE PROCEDURE assign_project_position();
+
+CREATE INDEX projects_position_idx ON projects (featured DESC, position);
+
+-- `/projects/order` now sits next to `/projects/<ref>`; re-slug any project
+-- that already took the word.
+INSERT INTO reserved_slugs (item_type, slug) VALUES ('projects', 'order');
+UPDATE projects SET slug = NULL WHERE slug = 'order';
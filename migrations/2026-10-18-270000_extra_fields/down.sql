@@ -0,0 +1,9 @@
+DROP TABLE extra_schemas;
+
+DROP INDEX projects_extra_idx;
+DROP INDEX games_extra_idx;
+DROP INDEX books_extra_idx;
+
+ALTER TABLE projects DROP COLUMN extra;
+ALTER TABLE games DROP COLUMN extra;
+ALTER TABLE books DROP COLUMN extra;
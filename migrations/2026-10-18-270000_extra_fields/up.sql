@@ -0,0 +1,25 @@
+-- Free-form fields that don't warrant a column of their own, e.g.
+-- `{"isbn": "9780441013593", "narrator": "Scott Brick"}`.
+ALTER TABLE books
+    ADD COLUMN extra JSONB NOT NULL DEFAULT '{}'
+        CONSTRAINT books_extra_check CHECK (jsonb_typeof(extra) = 'object');
+ALTER TABLE games
+    ADD COLUMN extra JSONB NOT NULL DEFAULT '{}'
+        CONSTRAINT games_extra_check CHECK (jsonb_typeof(extra) = 'object');
+ALTER TABLE projects
+    ADD COLUMN extra JSONB NOT NULL DEFAULT '{}'
+        CONSTRAINT projects_extra_check CHECK (jsonb_typeof(extra) = 'object');
+
+CREATE INDEX books_extra_idx ON books USING GIN (extra);
+CREATE INDEX games_extra_idx ON games USING GIN (extra);
+CREATE INDEX projects_extra_idx ON projects USING GIN (extra);
+
+-- An optional JSON Schema each resource's `extra` must satisfy. Resources
+-- without one accept any object.
+CREATE TABLE extra_schemas (
+    item_type TEXT PRIMARY KEY CHECK (item_type IN ('books', 'games', 'projects')),
+    schema JSONB NOT NULL CHECK (jsonb_typeof(schema) IN ('object', 'boolean')),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+SELECT diesel_manage_updated_at('extra_schemas');
@@ -0,0 +1,16 @@
+DROP INDEX wplace_owner_id_idx;
+DROP INDEX projects_owner_id_idx;
+DROP INDEX reviews_owner_id_idx;
+DROP INDEX games_owner_id_idx;
+DROP INDEX books_owner_id_idx;
+
+ALTER TABLE wplace DROP COLUMN owner_id;
+ALTER TABLE projects DROP COLUMN owner_id;
+ALTER TABLE reviews DROP COLUMN owner_id;
+ALTER TABLE games DROP COLUMN owner_id;
+ALTER TABLE books DROP COLUMN owner_id;
+
+DROP INDEX api_keys_account_id_idx;
+ALTER TABLE api_keys DROP COLUMN account_id;
+
+DROP TABLE accounts;
@@ -0,0 +1,56 @@
+-- Accounts own API keys and a library of books, games, reviews, projects and
+-- wplace screenshots. Handles appear in URLs, e.g. `/users/<handle>/read-watch`.
+CREATE TABLE accounts (
+    id INTEGER GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+    handle TEXT NOT NULL UNIQUE CHECK (handle ~ '^[a-z0-9][a-z0-9_-]{1,31}$'),
+    display_name TEXT NOT NULL DEFAULT '',
+    bio TEXT NOT NULL DEFAULT '',
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+SELECT diesel_manage_updated_at('accounts');
+
+-- Until now the database was one person's library; it all moves to `owner`.
+INSERT INTO accounts (handle) VALUES ('owner');
+
+ALTER TABLE api_keys ADD COLUMN account_id INTEGER REFERENCES accounts (id) ON DELETE CASCADE;
+UPDATE api_keys SET account_id = (SELECT id FROM accounts WHERE handle = 'owner');
+ALTER TABLE api_keys ALTER COLUMN account_id SET NOT NULL;
+CREATE INDEX api_keys_account_id_idx ON api_keys (account_id);
+
+ALTER TABLE books ADD COLUMN owner_id INTEGER REFERENCES accounts (id);
+ALTER TABLE games ADD COLUMN owner_id INTEGER REFERENCES accounts (id);
+ALTER TABLE reviews ADD COLUMN owner_id INTEGER REFERENCES accounts (id);
+ALTER TABLE projects ADD COLUMN owner_id INTEGER REFERENCES accounts (id);
+ALTER TABLE wplace ADD COLUMN owner_id INTEGER REFERENCES accounts (id);
+
+ALTER TABLE books DISABLE TRIGGER record_revision, DISABLE TRIGGER set_updated_at;
+ALTER TABLE games DISABLE TRIGGER record_revision, DISABLE TRIGGER set_updated_at;
+ALTER TABLE reviews DISABLE TRIGGER record_revision, DISABLE TRIGGER set_updated_at;
+ALTER TABLE projects DISABLE TRIGGER record_revision, DISABLE TRIGGER set_updated_at;
+ALTER TABLE wplace DISABLE TRIGGER record_revision, DISABLE TRIGGER set_updated_at;
+
+UPDATE books SET owner_id = (SELECT id FROM accounts WHERE handle = 'owner');
+UPDATE games SET owner_id = (SELECT id FROM accounts WHERE handle = 'owner');
+UPDATE reviews SET owner_id = (SELECT id FROM accounts WHERE handle = 'owner');
+UPDATE projects SET owner_id = (SELECT id FROM accounts WHERE handle = 'owner');
+UPDATE wplace SET owner_id = (SELECT id FROM accounts WHERE handle = 'owner');
+
+ALTER TABLE books ENABLE TRIGGER record_revision, ENABLE TRIGGER set_updated_at;
+ALTER TABLE games ENABLE TRIGGER record_revision, ENABLE TRIGGER set_updated_at;
+ALTER TABLE reviews ENABLE TRIGGER record_revision, ENABLE TRIGGER set_updated_at;
+ALTER TABLE projects ENABLE TRIGGER record_revision, ENABLE TRIGGER set_updated_at;
+ALTER TABLE wplace ENABLE TRIGGER record_revision, ENABLE TRIGGER set_updated_at;
+
+ALTER TABLE books ALTER COLUMN owner_id SET NOT NULL;
+ALTER TABLE games ALTER COLUMN owner_id SET NOT NULL;
+ALTER TABLE reviews ALTER COLUMN owner_id SET NOT NULL;
+ALTER TABLE projects ALTER COLUMN owner_id SET NOT NULL;
+ALTER TABLE wplace ALTER COLUMN owner_id SET NOT NULL;
+
+CREATE INDEX books_owner_id_idx ON books (owner_id);
+CREATE INDEX games_owner_id_idx ON games (owner_id);
+CREATE INDEX reviews_owner_id_idx ON reviews (owner_id);
+CREATE INDEX projects_owner_id_idx ON projects (owner_id);
+CREATE INDEX wplace_owner_id_idx ON wplace (owner_id);
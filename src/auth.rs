@@ -8,8 +8,8 @@ use std::time::{Duration, Instant};
 
 use crate::db::connect_db;
 use crate::errors::AuthError;
-use crate::models::{ApiKey, NewApiKey};
-use crate::schema::api_keys;
+use crate::models::{Account, ApiKey, NewAccount, NewApiKey};
+use crate::schema::{accounts, api_keys};
 
 #[derive(Clone, Debug)]
 struct CacheEntry {
@@ -73,6 +73,16 @@ impl User {
         self.api_key.id
     }
 
+    /// The account whose library this key reads and writes.
+    pub fn account_id(&self) -> i32 {
+        self.api_key.account_id
+    }
+
+    /// Admins can change anything; everyone else only what their account owns.
+    pub fn can_edit(&self, owner_id: i32) -> bool {
+        self.is_admin() || self.account_id() == owner_id
+    }
+
     pub fn is_admin(&self) -> bool {
         self.api_key.is_admin
     }
@@ -133,11 +143,12 @@ impl AuthService {
             return Ok(cached_key);
         }
 
-        let mut conn = connect_db();
+        let mut conn = connect_db()?;
         let api_key = api_keys::table
             .filter(api_keys::key_hash.eq(&key_hash))
             .first::<ApiKey>(&mut conn)
-            .map_err(|_| AuthError::InvalidKey)?;
+            .optional()?
+            .ok_or(AuthError::InvalidKey)?;
 
         self.cache.insert(key_hash, api_key.clone());
 
@@ -146,7 +157,7 @@ impl AuthService {
 
     pub async fn update_last_used(&self, key_id: i32) {
         tokio::task::spawn_blocking(move || {
-            if let Ok(mut conn) = std::panic::catch_unwind(connect_db) {
+            if let Ok(mut conn) = connect_db() {
                 let _ = diesel::update(api_keys::table.find(key_id))
                     .set(api_keys::last_used_at.eq(diesel::dsl::now))
                     .execute(&mut conn);
@@ -162,7 +173,7 @@ impl AuthService {
     }
 
     pub fn ensure_admin_exists(&self) -> Result<(), AuthError> {
-        let mut conn = connect_db();
+        let mut conn = connect_db()?;
 
         let admin_count = api_keys::table
             .filter(api_keys::is_admin.eq(true))
@@ -172,7 +183,8 @@ impl AuthService {
         if admin_count == 0 {
             if let Ok(admin_key) = std::env::var("BOOTSTRAP_ADMIN_KEY") {
                 println!("🔧 Creating bootstrap admin key...");
-                match self.create_api_key(&admin_key, true) {
+                let account = self.default_account()?;
+                match self.create_api_key(&admin_key, true, account.id) {
                     Ok(api_key) => {
                         println!("✅ Bootstrap admin created with ID: {}", api_key.id);
                         println!("⚠️  Remove BOOTSTRAP_ADMIN_KEY from environment after startup!");
@@ -191,13 +203,51 @@ impl AuthService {
         Ok(())
     }
 
-    pub fn create_api_key(&self, key: &str, is_admin: bool) -> Result<ApiKey, AuthError> {
-        let mut conn = connect_db();
+    /// The oldest account, which owned everything before there were accounts.
+    /// Creates an `owner` account if there are none yet.
+    pub fn default_account(&self) -> Result<Account, AuthError> {
+        let mut conn = connect_db()?;
+
+        if let Some(account) = accounts::table
+            .order(accounts::id.asc())
+            .first::<Account>(&mut conn)
+            .optional()?
+        {
+            return Ok(account);
+        }
+
+        Ok(diesel::insert_into(accounts::table)
+            .values(&NewAccount {
+                handle: "owner".to_string(),
+                display_name: String::new(),
+                bio: String::new(),
+            })
+            .get_result::<Account>(&mut conn)?)
+    }
+
+    pub fn find_account(&self, handle: &str) -> Result<Account, AuthError> {
+        let mut conn = connect_db()?;
+
+        accounts::table
+            .filter(accounts::handle.eq(handle))
+            .first::<Account>(&mut conn)
+            .optional()?
+            .ok_or(AuthError::UnknownAccount)
+    }
+
+    pub fn create_api_key(
+        &self,
+        key: &str,
+        is_admin: bool,
+        account_id: i32,
+    ) -> Result<ApiKey, AuthError> {
+        let mut conn = connect_db()?;
         let key_hash = Self::hash_api_key(key);
 
         let new_key = NewApiKey {
             key_hash: key_hash.clone(),
             is_admin,
+            account_id,
         };
 
         let api_key: ApiKey = diesel::insert_into(api_keys::table)
@@ -210,7 +260,7 @@ impl AuthService {
     }
 
     pub fn revoke_api_key(&self, key: &str) -> Result<(), AuthError> {
-        let mut conn = connect_db();
+        let mut conn = connect_db()?;
         let key_hash = Self::hash_api_key(key);
 
         diesel::delete(api_keys::table.filter(api_keys::key_hash.eq(&key_hash)))
@@ -222,7 +272,7 @@ impl AuthService {
     }
 
     pub fn list_api_keys(&self) -> Result<Vec<ApiKey>, AuthError> {
-        let mut conn = connect_db();
+        let mut conn = connect_db()?;
         Ok(api_keys::table
             .select(ApiKey::as_select())
             .load(&mut conn)?)
@@ -281,7 +331,7 @@ impl<'r> FromRequest<'r> for User {
                     api_key: key_record,
                 })
             }
-            Err(e) => Outcome::Error((rocket::http::Status::Unauthorized, e)),
+            Err(e) => Outcome::Error((e.status(), e)),
         }
     }
 }
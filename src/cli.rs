@@ -1,5 +1,9 @@
 use clap::{Arg, Command};
 use crate::auth::AuthService;
+use crate::db::connect_db;
+use crate::handlers::{ItemKind, ratings::rescale_ratings};
+use crate::models::Rating;
+use rocket::request::FromParam;
 
 pub fn cli() -> Command {
     Command::new("your-app")
@@ -12,6 +16,53 @@ pub fn cli() -> Command {
                         .help("Custom key (optional, will generate if not provided)")
                         .value_name("KEY")
                 )
+                .arg(
+                    Arg::new("handle")
+                        .long("handle")
+                        .help("Account to own the key (optional, defaults to the first account)")
+                        .value_name("HANDLE")
+                )
+        )
+        .subcommand(
+            Command::new("rescale-ratings")
+                .about("Move a resource onto a new rating scale, converting its existing ratings")
+                .arg(
+                    Arg::new("kind")
+                        .long("kind")
+                        .help("Resource to rescale, e.g. books or games")
+                        .value_name("KIND")
+                        .required(true)
+                )
+                .arg(
+                    Arg::new("from")
+                        .long("from")
+                        .help("Maximum of the scale the existing ratings were recorded on")
+                        .value_name("MAX")
+                        .value_parser(clap::value_parser!(f64))
+                        .required(true)
+                )
+                .arg(
+                    Arg::new("to")
+                        .long("to")
+                        .help("Maximum of the new scale")
+                        .value_name("MAX")
+                        .value_parser(clap::value_parser!(f64))
+                        .required(true)
+                )
+                .arg(
+                    Arg::new("step")
+                        .long("step")
+                        .help("Step of the new scale (optional, keeps the current step if not provided)")
+                        .value_name("STEP")
+                        .value_parser(clap::value_parser!(f64))
+                )
+                .arg(
+                    Arg::new("above")
+                        .long("above")
+                        .help("Only convert ratings greater than this (optional)")
+                        .value_name("RATING")
+                        .value_parser(clap::value_parser!(f64))
+                )
         )
 }
 
@@ -25,7 +76,12 @@ pub fn handle_cli(auth_service: AuthService) -> Result<(), Box<dyn std::error::E
                 None => AuthService::generate_api_key(),
             };
 
-            match auth_service.create_api_key(&key, true) {
+            let account = match sub_matches.get_one::<String>("handle") {
+                Some(handle) => auth_service.find_account(handle)?,
+                None => auth_service.default_account()?,
+            };
+
+            match auth_service.create_api_key(&key, true, account.id) {
                 Ok(api_key) => {
                     println!("api key created successfully!");
                     println!("Key: {}", key);
@@ -38,6 +94,27 @@ pub fn handle_cli(auth_service: AuthService) -> Result<(), Box<dyn std::error::E
                 }
             }
         }
+        Some(("rescale-ratings", sub_matches)) => {
+            let kind_name = sub_matches.get_one::<String>("kind").expect("kind is required");
+            let kind = ItemKind::from_param(kind_name)
+                .map_err(|_| format!("unknown resource: {}", kind_name))?;
+            let rating = |name: &str| sub_matches.get_one::<f64>(name).copied().map(Rating);
+
+            match rescale_ratings(
+                &mut connect_db()?,
+                kind,
+                rating("from").expect("from is required"),
+                rating("to").expect("to is required"),
+                rating("step"),
+                rating("above"),
+            ) {
+                Ok(count) => println!("rescaled {} {} ratings", count, kind_name),
+                Err(e) => {
+                    eprintln!("failed to rescale ratings: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
         _ => {
             cli().print_help()?;
         }
@@ -1,10 +1,12 @@
 use std::env;
 
+use diesel::result::{ConnectionError, ConnectionResult};
 use diesel::{Connection, PgConnection};
 use dotenvy::*;
 
-pub fn connect_db() -> PgConnection {
+pub fn connect_db() -> ConnectionResult<PgConnection> {
     dotenv().ok();
-    let db_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-    PgConnection::establish(&db_url).unwrap_or_else(|_| panic!("Error connecting to {db_url}"))
+    let db_url = env::var("DATABASE_URL")
+        .map_err(|_| ConnectionError::InvalidConnectionUrl("DATABASE_URL must be set".into()))?;
+    PgConnection::establish(&db_url)
 }
\ No newline at end of file
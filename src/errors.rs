@@ -1,8 +1,13 @@
-use rocket::http::Status;
-use rocket::response::{self, Responder, Response};
+use diesel::result::{ConnectionError, DatabaseErrorKind};
 use rocket::Request;
-use serde_json::json;
+use rocket::fairing::AdHoc;
+use rocket::http::{ContentType, Status};
+use rocket::response::{self, Responder, Response};
+use serde_json::{Value, json};
+use std::io::Cursor;
 use thiserror::Error;
+use uuid::Uuid;
+use validator::{ValidationError, ValidationErrors};
 
 #[derive(Error, Debug)]
 pub enum AuthError {
@@ -14,32 +19,310 @@ pub enum AuthError {
     InvalidKey,
     #[error("Insufficient permissions")]
     InsufficientPermissions,
+    #[error("Unknown account")]
+    UnknownAccount,
     #[error("Database error: {0}")]
     Database(#[from] diesel::result::Error),
+    #[error("Database unavailable: {0}")]
+    Connection(#[from] ConnectionError),
+}
+
+impl AuthError {
+    pub fn status(&self) -> Status {
+        match self {
+            AuthError::MissingHeader | AuthError::InvalidFormat | AuthError::InvalidKey => {
+                Status::Unauthorized
+            }
+            AuthError::InsufficientPermissions => Status::Forbidden,
+            AuthError::UnknownAccount => Status::NotFound,
+            AuthError::Database(_) => Status::InternalServerError,
+            AuthError::Connection(_) => Status::ServiceUnavailable,
+        }
+    }
 }
 
 impl<'r> Responder<'r, 'static> for AuthError {
-    fn respond_to(self, _: &'r Request<'_>) -> response::Result<'static> {
-        let (status, message) = match self {
-            AuthError::MissingHeader => (Status::Unauthorized, "Missing Authorization header"),
-            AuthError::InvalidFormat => {
-                (Status::Unauthorized, "Invalid Authorization header format")
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        ApiError::from(self).respond_to(request)
+    }
+}
+
+/// Everything a handler can fail with, rendered as an RFC 7807
+/// `application/problem+json` body.
+#[derive(Error, Debug)]
+pub enum ApiError {
+    #[error("{0}")]
+    BadRequest(String),
+    #[error("Insufficient permissions")]
+    Forbidden,
+    #[error("Resource not found")]
+    NotFound,
+    #[error("{0}")]
+    Conflict(String),
+    #[error("{0}")]
+    Validation(String),
+    /// Likely duplicates of an item being created, as `duplicates::Candidate`s.
+    #[error(
+        "This looks like something already in the library; retry with ?allowDuplicate=true to create it anyway"
+    )]
+    Duplicate(Value),
+    #[error("The item has changed since it was read; fetch it again and retry")]
+    PreconditionFailed,
+    #[error("Some fields are invalid")]
+    Invalid(#[from] ValidationErrors),
+    #[error("Database unavailable")]
+    Unavailable,
+    #[error("Internal server error")]
+    Internal,
+    #[error(transparent)]
+    Auth(#[from] AuthError),
+    #[error("Database error: {0}")]
+    Database(#[from] diesel::result::Error),
+}
+
+impl ApiError {
+    pub fn bad_request(detail: impl Into<String>) -> Self {
+        ApiError::BadRequest(detail.into())
+    }
+
+    pub fn conflict(detail: impl Into<String>) -> Self {
+        ApiError::Conflict(detail.into())
+    }
+
+    pub fn validation(detail: impl Into<String>) -> Self {
+        ApiError::Validation(detail.into())
+    }
+
+    pub fn status(&self) -> Status {
+        match self {
+            ApiError::BadRequest(_) => Status::BadRequest,
+            ApiError::Forbidden => Status::Forbidden,
+            ApiError::NotFound => Status::NotFound,
+            ApiError::Conflict(_) | ApiError::Duplicate(_) => Status::Conflict,
+            ApiError::PreconditionFailed => Status::PreconditionFailed,
+            ApiError::Validation(_) | ApiError::Invalid(_) => Status::UnprocessableEntity,
+            ApiError::Unavailable => Status::ServiceUnavailable,
+            ApiError::Internal => Status::InternalServerError,
+            ApiError::Auth(error) => error.status(),
+            // Constraint violations are the client's fault: a taken slug is a
+            // conflict, a disallowed status transition or missing series is 422.
+            ApiError::Database(error) => match error {
+                diesel::result::Error::NotFound => Status::NotFound,
+                diesel::result::Error::DatabaseError(kind, _) => match kind {
+                    DatabaseErrorKind::UniqueViolation => Status::Conflict,
+                    DatabaseErrorKind::CheckViolation
+                    | DatabaseErrorKind::ForeignKeyViolation
+                    | DatabaseErrorKind::NotNullViolation => Status::UnprocessableEntity,
+                    DatabaseErrorKind::ClosedConnection => Status::ServiceUnavailable,
+                    _ => Status::InternalServerError,
+                },
+                _ => Status::InternalServerError,
+            },
+        }
+    }
+
+    /// The human-readable explanation. Server-side failures don't leak their
+    /// cause; constraint violations name the constraint that was broken.
+    pub fn detail(&self) -> String {
+        match self {
+            ApiError::Auth(AuthError::Database(_)) => ApiError::Internal.to_string(),
+            ApiError::Auth(AuthError::Connection(_)) => ApiError::Unavailable.to_string(),
+            ApiError::Database(diesel::result::Error::NotFound) => ApiError::NotFound.to_string(),
+            ApiError::Database(diesel::result::Error::DatabaseError(_, info))
+                if self.status().code < 500 =>
+            {
+                info.message().to_string()
+            }
+            ApiError::Database(_) if self.status() == Status::ServiceUnavailable => {
+                ApiError::Unavailable.to_string()
             }
-            AuthError::InvalidKey => (Status::Unauthorized, "Invalid API key"),
-            AuthError::InsufficientPermissions => (Status::Forbidden, "Insufficient permissions"),
-            AuthError::Database(_) => (Status::InternalServerError, "Internal server error"),
-        };
-
-        let body = json!({
-            "error": message,
-            "status": status.code
+            ApiError::Database(_) => ApiError::Internal.to_string(),
+            error => error.to_string(),
+        }
+    }
+}
+
+impl From<ConnectionError> for ApiError {
+    fn from(error: ConnectionError) -> Self {
+        eprintln!("❌ Failed to connect to the database: {}", error);
+        ApiError::Unavailable
+    }
+}
+
+/// Lets helpers that still fail with a bare status be used with `?`.
+impl From<Status> for ApiError {
+    fn from(status: Status) -> Self {
+        let reason = status.reason_lossy().to_string();
+        match status.code {
+            400 => ApiError::BadRequest(reason),
+            403 => ApiError::Forbidden,
+            404 => ApiError::NotFound,
+            409 => ApiError::Conflict(reason),
+            422 => ApiError::Validation(reason),
+            503 => ApiError::Unavailable,
+            _ => ApiError::Internal,
+        }
+    }
+}
+
+impl<'r> Responder<'r, 'static> for ApiError {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        let status = self.status();
+        if status.code >= 500 {
+            eprintln!(
+                "❌ [{}] {} {}: {}",
+                RequestId::of(request),
+                request.method(),
+                request.uri(),
+                self
+            );
+        }
+
+        let mut body = problem(request, status, self.detail());
+        match &self {
+            ApiError::Invalid(errors) => body["errors"] = field_errors(errors),
+            ApiError::Duplicate(candidates) => body["candidates"] = candidates.clone(),
+            _ => {}
+        }
+
+        send_problem(status, body)
+    }
+}
+
+/// Every failing field with the reason it failed, e.g.
+/// `{"field": "percent", "reason": "must be between 0 and 100"}`.
+fn field_errors(errors: &ValidationErrors) -> Value {
+    let mut fields: Vec<(String, String)> = errors
+        .field_errors()
+        .into_iter()
+        .flat_map(|(field, errors)| {
+            errors
+                .iter()
+                .map(move |error| (field.to_string(), reason(error)))
         })
-        .to_string();
+        .collect();
+    fields.sort();
+
+    fields
+        .into_iter()
+        .map(|(field, reason)| json!({ "field": field, "reason": reason }))
+        .collect()
+}
+
+fn reason(error: &ValidationError) -> String {
+    if let Some(message) = &error.message {
+        return message.to_string();
+    }
+
+    let min = error.params.get("min");
+    let max = error.params.get("max");
+    match (error.code.as_ref(), min, max) {
+        ("length", Some(min), Some(max)) => format!("must be {min} to {max} characters long"),
+        ("length", Some(min), None) => format!("must be at least {min} characters long"),
+        ("length", None, Some(max)) => format!("must be at most {max} characters long"),
+        ("range", Some(min), Some(max)) => format!("must be between {min} and {max}"),
+        ("range", Some(min), None) => format!("must be at least {min}"),
+        ("range", None, Some(max)) => format!("must be at most {max}"),
+        ("url", ..) => "must be a URL".to_string(),
+        (code, ..) => format!("failed the {code} check"),
+    }
+}
+
+/// Identifies a request in error bodies and logs. Assigned the first time it's
+/// asked for and echoed back in the `X-Request-Id` header.
+pub struct RequestId(String);
+
+impl RequestId {
+    pub fn of<'r>(request: &'r Request<'_>) -> &'r str {
+        &request
+            .local_cache(|| RequestId(Uuid::new_v4().simple().to_string()))
+            .0
+    }
+
+    pub fn fairing() -> AdHoc {
+        AdHoc::on_response("Request id", |request, response| {
+            Box::pin(async move {
+                response.set_raw_header("X-Request-Id", RequestId::of(request).to_string());
+            })
+        })
+    }
+}
+
+fn problem(request: &Request<'_>, status: Status, detail: String) -> Value {
+    json!({
+        "type": "about:blank",
+        "title": status.reason_lossy(),
+        "status": status.code,
+        "detail": detail,
+        "instance": request.uri().path().to_string(),
+        "request_id": RequestId::of(request),
+    })
+}
+
+fn send_problem(status: Status, body: Value) -> response::Result<'static> {
+    let body = body.to_string();
+
+    Response::build()
+        .status(status)
+        .header(ContentType::new("application", "problem+json"))
+        .sized_body(body.len(), Cursor::new(body))
+        .ok()
+}
+
+/// Whether the client would rather read the error than parse it: browsers and
+/// anyone preferring `text/plain`. Everything else, including `*/*`, gets JSON.
+fn wants_text(request: &Request<'_>) -> bool {
+    request.accept().is_some_and(|accept| {
+        let preferred = accept.preferred().media_type();
+        preferred.is_html() || preferred.is_plain()
+    })
+}
+
+/// What the catchers send back: problem+json for API clients, and a plain-text
+/// page, with ASCII art when there is some, for people.
+pub struct ErrorPage {
+    status: Status,
+    detail: &'static str,
+    art: Option<&'static str>,
+}
+
+impl ErrorPage {
+    pub fn new(status: Status, detail: &'static str) -> Self {
+        Self {
+            status,
+            detail,
+            art: None,
+        }
+    }
+
+    pub fn with_art(self, art: &'static str) -> Self {
+        Self {
+            art: Some(art),
+            ..self
+        }
+    }
+}
+
+impl<'r> Responder<'r, 'static> for ErrorPage {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        if !wants_text(request) {
+            let body = problem(request, self.status, self.detail.to_string());
+            return send_problem(self.status, body);
+        }
+
+        let body = format!(
+            "{}\n\n{} {}\n{}\nrequest id: {}\n",
+            self.art.unwrap_or_default(),
+            self.status.code,
+            self.status.reason_lossy(),
+            self.detail,
+            RequestId::of(request),
+        );
 
         Response::build()
-            .status(status)
-            .header(rocket::http::ContentType::JSON)
-            .sized_body(body.len(), std::io::Cursor::new(body))
+            .status(self.status)
+            .header(ContentType::Plain)
+            .sized_body(body.len(), Cursor::new(body))
             .ok()
     }
 }
@@ -0,0 +1,344 @@
+use crate::auth::{AdminUser, User};
+use crate::db::connect_db;
+use crate::errors::ApiError;
+use crate::handlers::ItemKind;
+use crate::handlers::books::{BookQuery, BookResponse, get_book_by_id, get_books};
+use crate::handlers::etags::Created;
+use crate::handlers::games::{GameQuery, GameResponse, get_game_by_id, get_games};
+use crate::handlers::identifiers::{ItemRef, Located, Resolved, resolve_ref};
+use crate::handlers::projects::{ProjectQuery, get_project, get_projects};
+use crate::handlers::ratings::Rated;
+use crate::handlers::reviews::{ReviewQuery, get_review_by_id, search_reviews};
+use crate::handlers::wplace::{ScreenshotQuery, get_screenshot, get_screenshots};
+use crate::models::{Account, NewAccount, Project, Review, UpdateAccount, WplaceScreenshot};
+use crate::schema::{accounts, books, games, projects, reviews, wplace};
+use diesel::prelude::*;
+use rocket::serde::json::Json;
+use rocket::{Route, delete, get, patch, post, routes};
+use serde::Serialize;
+use validator::Validate;
+
+/// How many live items of each kind an account owns.
+#[derive(Serialize)]
+pub struct LibraryCounts {
+    books: i64,
+    games: i64,
+    reviews: i64,
+    projects: i64,
+    wplace: i64,
+}
+
+/// An account's public profile.
+#[derive(Serialize)]
+pub struct Profile {
+    #[serde(flatten)]
+    account: Account,
+    library: LibraryCounts,
+}
+
+/// The account owning an item, or `None` if the item doesn't exist or its kind
+/// isn't owned by accounts.
+pub fn item_owner(
+    conn: &mut PgConnection,
+    kind: ItemKind,
+    item_id: i32,
+) -> QueryResult<Option<i32>> {
+    match kind {
+        ItemKind::Books => books::table
+            .find(item_id)
+            .select(books::owner_id)
+            .first(conn)
+            .optional(),
+        ItemKind::Games => games::table
+            .find(item_id)
+            .select(games::owner_id)
+            .first(conn)
+            .optional(),
+        ItemKind::Reviews => reviews::table
+            .find(item_id)
+            .select(reviews::owner_id)
+            .first(conn)
+            .optional(),
+        ItemKind::Projects => projects::table
+            .find(item_id)
+            .select(projects::owner_id)
+            .first(conn)
+            .optional(),
+        ItemKind::Wplace => wplace::table
+            .find(item_id)
+            .select(wplace::owner_id)
+            .first(conn)
+            .optional(),
+        _ => Ok(None),
+    }
+}
+
+/// Lets `user` change an item if their account owns it or they're an admin.
+pub fn require_owner(
+    conn: &mut PgConnection,
+    user: &User,
+    kind: ItemKind,
+    item_id: i32,
+) -> Result<(), ApiError> {
+    let owner_id = item_owner(conn, kind, item_id)?.ok_or(ApiError::NotFound)?;
+
+    if user.can_edit(owner_id) {
+        Ok(())
+    } else {
+        Err(ApiError::Forbidden)
+    }
+}
+
+fn find_account(conn: &mut PgConnection, handle: &str) -> Result<Account, ApiError> {
+    accounts::table
+        .filter(accounts::handle.eq(handle))
+        .first::<Account>(conn)
+        .optional()?
+        .ok_or(ApiError::NotFound)
+}
+
+fn library_counts(conn: &mut PgConnection, account_id: i32) -> QueryResult<LibraryCounts> {
+    Ok(LibraryCounts {
+        books: books::table
+            .filter(books::owner_id.eq(account_id))
+            .filter(books::deleted_at.is_null())
+            .count()
+            .get_result(conn)?,
+        games: games::table
+            .filter(games::owner_id.eq(account_id))
+            .filter(games::deleted_at.is_null())
+            .count()
+            .get_result(conn)?,
+        reviews: reviews::table
+            .filter(reviews::owner_id.eq(account_id))
+            .filter(reviews::deleted_at.is_null())
+            .count()
+            .get_result(conn)?,
+        projects: projects::table
+            .filter(projects::owner_id.eq(account_id))
+            .filter(projects::deleted_at.is_null())
+            .count()
+            .get_result(conn)?,
+        wplace: wplace::table
+            .filter(wplace::owner_id.eq(account_id))
+            .filter(wplace::deleted_at.is_null())
+            .count()
+            .get_result(conn)?,
+    })
+}
+
+/// Resolves `handle` and checks `item_ref` belongs to it, so an item can't be
+/// read through another account's URL. Moved slugs are left for the item's own
+/// handler to redirect.
+fn check_owned(handle: &str, kind: ItemKind, item_ref: &ItemRef) -> Result<(), ApiError> {
+    let mut conn = connect_db()?;
+    let account = find_account(&mut conn, handle)?;
+
+    if let Resolved::Found(item_id) = resolve_ref(&mut conn, kind, item_ref)? {
+        let owner_id = item_owner(&mut conn, kind, item_id)?.ok_or(ApiError::NotFound)?;
+
+        if owner_id != account.id {
+            return Err(ApiError::NotFound);
+        }
+    }
+
+    Ok(())
+}
+
+fn account_id(handle: &str) -> Result<i32, ApiError> {
+    let mut conn = connect_db()?;
+    Ok(find_account(&mut conn, handle)?.id)
+}
+
+#[get("/")]
+pub fn get_accounts() -> Result<Json<Vec<Account>>, ApiError> {
+    let mut conn = connect_db()?;
+
+    accounts::table
+        .order(accounts::handle.asc())
+        .load::<Account>(&mut conn)
+        .map(Json)
+        .map_err(ApiError::from)
+}
+
+#[get("/<handle>")]
+pub fn get_account(handle: &str) -> Result<Json<Profile>, ApiError> {
+    let mut conn = connect_db()?;
+
+    let account = find_account(&mut conn, handle)?;
+    let library = library_counts(&mut conn, account.id)?;
+
+    Ok(Json(Profile { account, library }))
+}
+
+#[post("/", format = "json", data = "<new_account>")]
+pub fn create_account(
+    _admin: AdminUser,
+    new_account: Json<NewAccount>,
+) -> Result<Created<Account>, ApiError> {
+    new_account.validate()?;
+
+    let mut conn = connect_db()?;
+
+    let account = diesel::insert_into(accounts::table)
+        .values(&new_account.into_inner())
+        .get_result::<Account>(&mut conn)?;
+
+    Ok(Created::new(format!("/users/{}", account.handle), account))
+}
+
+/// Updates a profile. Accounts can edit their own; admins can edit any.
+#[patch("/<handle>", format = "json", data = "<changes>")]
+pub fn update_account(
+    user: User,
+    handle: &str,
+    changes: Json<UpdateAccount>,
+) -> Result<Json<Account>, ApiError> {
+    changes.validate()?;
+
+    let mut conn = connect_db()?;
+    let account = find_account(&mut conn, handle)?;
+
+    if !user.can_edit(account.id) {
+        return Err(ApiError::Forbidden);
+    }
+
+    diesel::update(accounts::table.find(account.id))
+        .set(&changes.into_inner())
+        .get_result::<Account>(&mut conn)
+        .map(Json)
+        .map_err(ApiError::from)
+}
+
+/// Deletes an account along with its API keys. Accounts that still own items
+/// can't be deleted (409); their library has to be emptied first.
+#[delete("/<handle>")]
+pub fn delete_account(admin: AdminUser, handle: &str) -> Result<Json<Account>, ApiError> {
+    let mut conn = connect_db()?;
+    let account = find_account(&mut conn, handle)?;
+
+    if account.id == admin.account_id() {
+        return Err(ApiError::conflict("you can't delete your own account"));
+    }
+
+    diesel::delete(accounts::table.find(account.id))
+        .get_result::<Account>(&mut conn)
+        .map(Json)
+        .map_err(|e| match e {
+            diesel::result::Error::DatabaseError(
+                diesel::result::DatabaseErrorKind::ForeignKeyViolation,
+                _,
+            ) => ApiError::conflict("the account still owns items"),
+            e => ApiError::from(e),
+        })
+}
+
+#[get("/<handle>/read-watch/search?<query..>")]
+pub fn get_account_books(
+    handle: &str,
+    mut query: BookQuery,
+) -> Result<Json<Vec<BookResponse>>, ApiError> {
+    query.owner_id = Some(account_id(handle)?);
+    get_books(query)
+}
+
+#[get("/<handle>/read-watch/<book_ref>")]
+pub fn get_account_book(
+    handle: &str,
+    book_ref: ItemRef,
+) -> Result<Located<BookResponse>, ApiError> {
+    check_owned(handle, ItemKind::Books, &book_ref)?;
+    get_book_by_id(book_ref)
+}
+
+#[get("/<handle>/games/search?<query..>")]
+pub fn get_account_games(
+    handle: &str,
+    mut query: GameQuery,
+) -> Result<Json<Vec<GameResponse>>, ApiError> {
+    query.owner_id = Some(account_id(handle)?);
+    get_games(query)
+}
+
+#[get("/<handle>/games/<game_ref>")]
+pub fn get_account_game(
+    handle: &str,
+    game_ref: ItemRef,
+) -> Result<Located<GameResponse>, ApiError> {
+    check_owned(handle, ItemKind::Games, &game_ref)?;
+    get_game_by_id(game_ref)
+}
+
+#[get("/<handle>/reviews/search?<query..>")]
+pub fn get_account_reviews(
+    handle: &str,
+    mut query: ReviewQuery,
+) -> Result<Json<Vec<Rated<Review>>>, ApiError> {
+    query.owner_id = Some(account_id(handle)?);
+    search_reviews(query)
+}
+
+#[get("/<handle>/reviews/<review_ref>")]
+pub fn get_account_review(
+    handle: &str,
+    review_ref: ItemRef,
+) -> Result<Located<Rated<Review>>, ApiError> {
+    check_owned(handle, ItemKind::Reviews, &review_ref)?;
+    get_review_by_id(review_ref)
+}
+
+#[get("/<handle>/projects?<query..>")]
+pub fn get_account_projects(
+    handle: &str,
+    mut query: ProjectQuery,
+) -> Result<Json<Vec<Project>>, ApiError> {
+    query.owner_id = Some(account_id(handle)?);
+    get_projects(query)
+}
+
+#[get("/<handle>/projects/<project_ref>")]
+pub fn get_account_project(
+    handle: &str,
+    project_ref: ItemRef,
+) -> Result<Located<Project>, ApiError> {
+    check_owned(handle, ItemKind::Projects, &project_ref)?;
+    get_project(project_ref)
+}
+
+#[get("/<handle>/wplace?<query..>")]
+pub fn get_account_screenshots(
+    handle: &str,
+    mut query: ScreenshotQuery,
+) -> Result<Json<Vec<WplaceScreenshot>>, ApiError> {
+    query.owner_id = Some(account_id(handle)?);
+    get_screenshots(query)
+}
+
+#[get("/<handle>/wplace/<screenshot_ref>")]
+pub fn get_account_screenshot(
+    handle: &str,
+    screenshot_ref: ItemRef,
+) -> Result<Located<WplaceScreenshot>, ApiError> {
+    check_owned(handle, ItemKind::Wplace, &screenshot_ref)?;
+    get_screenshot(screenshot_ref)
+}
+
+pub fn accounts_routes() -> Vec<Route> {
+    routes![
+        get_accounts,
+        get_account,
+        create_account,
+        update_account,
+        delete_account,
+        get_account_books,
+        get_account_book,
+        get_account_games,
+        get_account_game,
+        get_account_reviews,
+        get_account_review,
+        get_account_projects,
+        get_account_project,
+        get_account_screenshots,
+        get_account_screenshot,
+    ]
+}
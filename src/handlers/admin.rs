@@ -9,6 +9,8 @@ use serde_json::{Value, json};
 #[derive(Deserialize)]
 pub struct CreateKeyRequest {
     pub is_admin: Option<bool>,
+    /// The account to own the key; defaults to the caller's own account.
+    pub handle: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -20,6 +22,7 @@ pub struct RevokeKeyRequest {
 pub struct ApiKeyResponse {
     pub id: i32,
     pub is_admin: bool,
+    pub account_id: i32,
     pub created_at: chrono::NaiveDateTime,
     pub last_used_at: Option<chrono::NaiveDateTime>,
 }
@@ -29,6 +32,7 @@ impl From<ApiKey> for ApiKeyResponse {
         Self {
             id: api_key.id,
             is_admin: api_key.is_admin,
+            account_id: api_key.account_id,
             created_at: api_key.created_at,
             last_used_at: api_key.last_used_at,
         }
@@ -37,15 +41,19 @@ impl From<ApiKey> for ApiKeyResponse {
 
 #[post("/create-key", data = "<request>")]
 pub fn create_api_key(
-    _admin: AdminUser,
+    admin: AdminUser,
     request: Option<Json<CreateKeyRequest>>,
     auth_service: &State<AuthService>,
 ) -> Result<Json<Value>, AuthError> {
     let is_admin = request.as_ref().and_then(|r| r.is_admin).unwrap_or(false);
+    let account_id = match request.as_ref().and_then(|r| r.handle.as_deref()) {
+        Some(handle) => auth_service.find_account(handle)?.id,
+        None => admin.account_id(),
+    };
 
     let new_key = AuthService::generate_api_key();
 
-    match auth_service.create_api_key(&new_key, is_admin) {
+    match auth_service.create_api_key(&new_key, is_admin, account_id) {
         Ok(api_key) => Ok(Json(json!({
             "message": "API key created successfully",
             "api_key": new_key,
@@ -82,6 +90,7 @@ pub fn list_api_keys(
 pub fn get_profile(user: User) -> Json<Value> {
     Json(json!({
         "id": user.id(),
+        "account_id": user.account_id(),
         "is_admin": user.is_admin(),
         "created_at": user.created_at(),
         "last_used_at": user.last_used_at()
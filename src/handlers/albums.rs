@@ -0,0 +1,583 @@
+use crate::auth::{AdminUser, User};
+use crate::db::connect_db;
+use crate::errors::ApiError;
+use crate::handlers::etags::{Created, IfMatch, Tagged};
+use crate::handlers::identifiers::{ItemRef, Located, Resolved, resolve_ref};
+use crate::handlers::ratings::rating_scale;
+use crate::handlers::{ItemKind, parse_timestamp};
+use crate::models::{
+    Album, AlbumTrack, NewAlbum, NewAlbumTrack, Rating, UpdateAlbum, UpdateAlbumTrack,
+};
+use crate::schema::{album_tracks, albums};
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel::result::{DatabaseErrorKind, Error};
+use rocket::Either;
+use rocket::form::FromForm;
+use rocket::serde::{Deserialize, Serialize, json::Json};
+use rocket::{delete, get, patch, post, put, routes};
+use std::collections::HashMap;
+use validator::Validate;
+
+#[derive(FromForm, Debug)]
+pub struct AlbumQuery {
+    title: Option<String>,
+    artist: Option<String>,
+    genre: Option<String>,
+    tag: Option<String>,
+    explicit: Option<String>,
+    #[field(name = "releaseYear")]
+    release_year: Option<i32>,
+    /// Compared against the album's score, which falls back to its track ratings.
+    #[field(name = "minRating")]
+    min_rating: Option<f64>,
+    #[field(name = "maxRating")]
+    max_rating: Option<f64>,
+    #[field(name = "createdAfter")]
+    created_after: Option<String>,
+    #[field(name = "updatedSince")]
+    updated_since: Option<String>,
+    sort: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct BulkDeleteFilter {
+    artist: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct BulkUpdatePayload {
+    filter: HashMap<String, String>,
+    update: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct NewTrackPayload {
+    #[validate(custom(function = "crate::validation::not_blank"), length(max = 200))]
+    title: String,
+    /// Defaults to the end of the track list.
+    #[validate(range(min = 1))]
+    position: Option<i32>,
+    #[validate(range(min = 0))]
+    duration_seconds: Option<i32>,
+    #[validate(custom(function = "crate::validation::non_negative"))]
+    rating: Option<Rating>,
+    #[serde(default)]
+    favorite: bool,
+}
+
+#[derive(Serialize)]
+pub struct ApiResponse {
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    deleted: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    updated: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    count: Option<usize>,
+}
+
+/// An album with its track list and derived score.
+#[derive(Serialize)]
+pub struct AlbumResponse {
+    #[serde(flatten)]
+    album: Album,
+    tracks: Vec<AlbumTrack>,
+    /// The overall rating, or the average track rating when none is set.
+    score: Option<f64>,
+    /// `score` mapped onto 0–1.
+    normalized_rating: Option<f64>,
+}
+
+fn album_score(album: &Album, tracks: &[AlbumTrack]) -> Option<f64> {
+    if let Some(rating) = album.rating {
+        return Some(f64::from(rating));
+    }
+
+    let ratings: Vec<f64> = tracks
+        .iter()
+        .filter_map(|track| track.rating.map(f64::from))
+        .collect();
+    (!ratings.is_empty()).then(|| ratings.iter().sum::<f64>() / ratings.len() as f64)
+}
+
+fn album_responses(conn: &mut PgConnection, loaded: Vec<Album>) -> QueryResult<Vec<AlbumResponse>> {
+    let ids: Vec<i32> = loaded.iter().map(|album| album.id).collect();
+
+    let mut track_lists: HashMap<i32, Vec<AlbumTrack>> = HashMap::new();
+    for track in album_tracks::table
+        .filter(album_tracks::album_id.eq_any(&ids))
+        .order(album_tracks::position.asc())
+        .load::<AlbumTrack>(conn)?
+    {
+        track_lists.entry(track.album_id).or_default().push(track);
+    }
+
+    let scale = rating_scale(conn, ItemKind::Albums)?;
+
+    Ok(loaded
+        .into_iter()
+        .map(|album| {
+            let tracks = track_lists.remove(&album.id).unwrap_or_default();
+            let score = album_score(&album, &tracks);
+            AlbumResponse {
+                normalized_rating: score.map(|score| scale.normalize(Rating(score))),
+                score,
+                album,
+                tracks,
+            }
+        })
+        .collect())
+}
+
+fn album_response(conn: &mut PgConnection, album: Album) -> QueryResult<AlbumResponse> {
+    Ok(album_responses(conn, vec![album])?.remove(0))
+}
+
+fn find_live_album(conn: &mut PgConnection, album_id: i32) -> Result<Album, ApiError> {
+    albums::table
+        .find(album_id)
+        .filter(albums::deleted_at.is_null())
+        .first::<Album>(conn)
+        .map_err(ApiError::from)
+}
+
+/// A live album as `GET /albums/<id>` returns it.
+fn current_album(conn: &mut PgConnection, album_id: i32) -> Result<AlbumResponse, ApiError> {
+    let album = find_live_album(conn, album_id)?;
+    Ok(album_response(conn, album)?)
+}
+
+fn map_track_error(error: Error) -> ApiError {
+    match error {
+        Error::DatabaseError(DatabaseErrorKind::UniqueViolation, _) => {
+            ApiError::conflict("the album already has a track at that position")
+        }
+        error => ApiError::from(error),
+    }
+}
+
+#[get("/search?<query..>")]
+pub fn get_albums(query: AlbumQuery) -> Result<Json<Vec<AlbumResponse>>, ApiError> {
+    use crate::schema::albums::dsl::*;
+
+    let mut conn = connect_db()?;
+    let mut album_query = albums.filter(deleted_at.is_null()).into_boxed();
+
+    if let Some(title_filter) = &query.title {
+        album_query = album_query.filter(title.ilike(format!("%{}%", title_filter)));
+    }
+
+    if let Some(artist_filter) = &query.artist {
+        album_query = album_query.filter(artist.ilike(format!("%{}%", artist_filter)));
+    }
+
+    if let Some(explicit_filter) = &query.explicit {
+        match explicit_filter.as_str() {
+            "true" => album_query = album_query.filter(explicit.eq(true)),
+            "false" => album_query = album_query.filter(explicit.eq(false)),
+            _ => {}
+        }
+    }
+
+    if let Some(release_year_filter) = query.release_year {
+        album_query = album_query.filter(release_year.eq(release_year_filter));
+    }
+
+    if let Some(created_after_filter) = &query.created_after {
+        let timestamp = parse_timestamp(created_after_filter)
+            .ok_or_else(|| ApiError::bad_request("invalid createdAfter timestamp"))?;
+        album_query = album_query.filter(created_at.gt(timestamp));
+    }
+
+    if let Some(updated_since_filter) = &query.updated_since {
+        let timestamp = parse_timestamp(updated_since_filter)
+            .ok_or_else(|| ApiError::bad_request("invalid updatedSince timestamp"))?;
+        album_query = album_query.filter(updated_at.ge(timestamp));
+    }
+
+    if let Some(sort_by) = &query.sort {
+        match sort_by.as_str() {
+            "title" => album_query = album_query.order(title.asc()),
+            "artist" => album_query = album_query.order((artist.asc(), release_year.asc())),
+            "release" => album_query = album_query.order(release_year.desc().nulls_last()),
+            "created" => album_query = album_query.order(created_at.desc()),
+            "updated" => album_query = album_query.order(updated_at.desc()),
+            _ => {}
+        }
+    }
+
+    let mut results = album_query.load::<Album>(&mut conn)?;
+
+    if let Some(genre_filter) = &query.genre {
+        results.retain(|album| {
+            album
+                .genres
+                .iter()
+                .flatten()
+                .any(|genre| genre.to_lowercase().contains(&genre_filter.to_lowercase()))
+        });
+    }
+
+    if let Some(tag_filter) = &query.tag {
+        results.retain(|album| {
+            album
+                .tags
+                .iter()
+                .flatten()
+                .any(|tag| tag.to_lowercase().contains(&tag_filter.to_lowercase()))
+        });
+    }
+
+    let mut responses = album_responses(&mut conn, results)?;
+
+    if let Some(min_rating_filter) = query.min_rating {
+        responses.retain(|response| {
+            response
+                .score
+                .is_some_and(|score| score >= min_rating_filter)
+        });
+    }
+
+    if let Some(max_rating_filter) = query.max_rating {
+        responses.retain(|response| {
+            response
+                .score
+                .is_some_and(|score| score <= max_rating_filter)
+        });
+    }
+
+    if query.sort.as_deref() == Some("rating") {
+        responses.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+    }
+
+    Ok(Json(responses))
+}
+
+#[get("/<album_ref>")]
+pub fn get_album_by_id(album_ref: ItemRef) -> Result<Located<AlbumResponse>, ApiError> {
+    let mut conn = connect_db()?;
+
+    let album_id = match resolve_ref(&mut conn, ItemKind::Albums, &album_ref)? {
+        Resolved::Found(found) => found,
+        Resolved::Moved(redirect) => return Ok(Either::Right(*redirect)),
+    };
+
+    let response = current_album(&mut conn, album_id)?;
+
+    Ok(Either::Left(Tagged::new(response)))
+}
+
+#[post("/", format = "json", data = "<new_album>")]
+pub fn post_albums(
+    _user: User,
+    new_album: Json<NewAlbum>,
+) -> Result<Created<AlbumResponse>, ApiError> {
+    _user.require_admin()?;
+
+    new_album.validate()?;
+
+    let mut conn = connect_db()?;
+
+    let album = diesel::insert_into(albums::table)
+        .values(&new_album.into_inner())
+        .get_result::<Album>(&mut conn)?;
+    let id = album.id;
+    let response = album_response(&mut conn, album)?;
+
+    Ok(Created::new(ItemKind::Albums.location(id), response))
+}
+
+#[put("/<album_id>", format = "json", data = "<updated_album>")]
+pub fn update_album(
+    _user: User,
+    if_match: IfMatch,
+    album_id: i32,
+    updated_album: Json<UpdateAlbum>,
+) -> Result<Tagged<AlbumResponse>, ApiError> {
+    _user.require_admin()?;
+
+    updated_album.validate()?;
+
+    let mut conn = connect_db()?;
+
+    let response = conn.transaction::<_, ApiError, _>(|conn| {
+        if_match.check(conn, ItemKind::Albums, album_id, current_album)?;
+        let album = diesel::update(
+            albums::table
+                .find(album_id)
+                .filter(albums::deleted_at.is_null()),
+        )
+        .set(&updated_album.into_inner())
+        .get_result::<Album>(conn)?;
+        Ok(album_response(conn, album)?)
+    })?;
+
+    Ok(Tagged::new(response))
+}
+
+#[patch("/<album_id>", format = "json", data = "<patch_data>")]
+pub fn patch_album(
+    _user: User,
+    if_match: IfMatch,
+    album_id: i32,
+    patch_data: Json<UpdateAlbum>,
+) -> Result<Tagged<AlbumResponse>, ApiError> {
+    _user.require_admin()?;
+
+    patch_data.validate()?;
+
+    let mut conn = connect_db()?;
+
+    let response = conn.transaction::<_, ApiError, _>(|conn| {
+        if_match.check(conn, ItemKind::Albums, album_id, current_album)?;
+        let album = diesel::update(
+            albums::table
+                .find(album_id)
+                .filter(albums::deleted_at.is_null()),
+        )
+        .set(&patch_data.into_inner())
+        .get_result::<Album>(conn)?;
+        Ok(album_response(conn, album)?)
+    })?;
+
+    Ok(Tagged::new(response))
+}
+
+#[delete("/<album_id>")]
+pub fn delete_album(
+    _user: User,
+    if_match: IfMatch,
+    album_id: i32,
+) -> Result<Json<ApiResponse>, ApiError> {
+    _user.require_admin()?;
+
+    let mut conn = connect_db()?;
+
+    let rows_deleted = conn.transaction::<_, ApiError, _>(|conn| {
+        if_match.check(conn, ItemKind::Albums, album_id, current_album)?;
+        Ok(diesel::update(
+            albums::table
+                .find(album_id)
+                .filter(albums::deleted_at.is_null()),
+        )
+        .set(albums::deleted_at.eq(diesel::dsl::now.nullable()))
+        .execute(conn)?)
+    })?;
+
+    if rows_deleted > 0 {
+        Ok(Json(ApiResponse {
+            message: "album moved to trash".to_string(),
+            deleted: None,
+            updated: None,
+            count: None,
+        }))
+    } else {
+        Err(ApiError::NotFound)
+    }
+}
+
+#[post("/<album_id>/restore")]
+pub fn restore_album(_admin: AdminUser, album_id: i32) -> Result<Json<Album>, ApiError> {
+    let mut conn = connect_db()?;
+
+    let album = diesel::update(
+        albums::table
+            .find(album_id)
+            .filter(albums::deleted_at.is_not_null()),
+    )
+    .set(albums::deleted_at.eq(None::<NaiveDateTime>))
+    .get_result::<Album>(&mut conn)?;
+
+    Ok(Json(album))
+}
+
+#[get("/<album_id>/tracks")]
+pub fn get_album_tracks(album_id: i32) -> Result<Json<Vec<AlbumTrack>>, ApiError> {
+    let mut conn = connect_db()?;
+    find_live_album(&mut conn, album_id)?;
+
+    let tracks = album_tracks::table
+        .filter(album_tracks::album_id.eq(album_id))
+        .order(album_tracks::position.asc())
+        .load::<AlbumTrack>(&mut conn)?;
+
+    Ok(Json(tracks))
+}
+
+#[post("/<album_id>/tracks", format = "json", data = "<track>")]
+pub fn add_album_track(
+    _admin: AdminUser,
+    album_id: i32,
+    track: Json<NewTrackPayload>,
+) -> Result<Json<AlbumTrack>, ApiError> {
+    let track = track.into_inner();
+    track.validate()?;
+
+    let mut conn = connect_db()?;
+    find_live_album(&mut conn, album_id)?;
+
+    let position = match track.position {
+        Some(position) => position,
+        None => {
+            album_tracks::table
+                .filter(album_tracks::album_id.eq(album_id))
+                .select(diesel::dsl::max(album_tracks::position))
+                .first::<Option<i32>>(&mut conn)?
+                .unwrap_or(0)
+                + 1
+        }
+    };
+
+    let created = diesel::insert_into(album_tracks::table)
+        .values(&NewAlbumTrack {
+            album_id,
+            position,
+            title: track.title,
+            duration_seconds: track.duration_seconds,
+            rating: track.rating,
+            favorite: track.favorite,
+        })
+        .get_result::<AlbumTrack>(&mut conn)
+        .map_err(map_track_error)?;
+
+    Ok(Json(created))
+}
+
+#[patch("/<album_id>/tracks/<track_id>", format = "json", data = "<changes>")]
+pub fn update_album_track(
+    _admin: AdminUser,
+    album_id: i32,
+    track_id: i32,
+    changes: Json<UpdateAlbumTrack>,
+) -> Result<Json<AlbumTrack>, ApiError> {
+    changes.validate()?;
+
+    let mut conn = connect_db()?;
+    find_live_album(&mut conn, album_id)?;
+
+    let track = diesel::update(
+        album_tracks::table
+            .find(track_id)
+            .filter(album_tracks::album_id.eq(album_id)),
+    )
+    .set(&changes.into_inner())
+    .get_result::<AlbumTrack>(&mut conn)
+    .map_err(map_track_error)?;
+
+    Ok(Json(track))
+}
+
+#[delete("/<album_id>/tracks/<track_id>")]
+pub fn delete_album_track(
+    _admin: AdminUser,
+    album_id: i32,
+    track_id: i32,
+) -> Result<Json<AlbumTrack>, ApiError> {
+    let mut conn = connect_db()?;
+    find_live_album(&mut conn, album_id)?;
+
+    let track = diesel::delete(
+        album_tracks::table
+            .find(track_id)
+            .filter(album_tracks::album_id.eq(album_id)),
+    )
+    .get_result::<AlbumTrack>(&mut conn)
+    .map_err(map_track_error)?;
+
+    Ok(Json(track))
+}
+
+#[delete("/bulk", format = "json", data = "<filter>")]
+pub fn bulk_delete_albums(
+    _user: User,
+    filter: Json<BulkDeleteFilter>,
+) -> Result<Json<ApiResponse>, ApiError> {
+    _user.require_admin()?;
+    use crate::schema::albums::dsl::*;
+
+    let mut conn = connect_db()?;
+    let filter = filter.into_inner();
+
+    let mut delete_query = diesel::update(albums.filter(deleted_at.is_null())).into_boxed();
+
+    if let Some(artist_filter) = &filter.artist {
+        delete_query = delete_query.filter(artist.eq(artist_filter));
+    }
+
+    let deleted_count = delete_query
+        .set(deleted_at.eq(diesel::dsl::now.nullable()))
+        .execute(&mut conn)? as i32;
+
+    Ok(Json(ApiResponse {
+        message: "bulk delete complete".to_string(),
+        deleted: Some(deleted_count),
+        updated: None,
+        count: None,
+    }))
+}
+
+#[patch("/bulk", format = "json", data = "<payload>")]
+pub fn bulk_update_albums(
+    _user: User,
+    payload: Json<BulkUpdatePayload>,
+) -> Result<Json<ApiResponse>, ApiError> {
+    _user.require_admin()?;
+    use crate::schema::albums::dsl::*;
+
+    let mut conn = connect_db()?;
+    let payload = payload.into_inner();
+
+    let mut update_query = diesel::update(albums.filter(deleted_at.is_null())).into_boxed();
+
+    if let Some(artist_filter) = payload.filter.get("artist") {
+        update_query = update_query.filter(artist.eq(artist_filter));
+    }
+
+    let mut update_album = UpdateAlbum::default();
+
+    if let Some(new_rating) = payload.update.get("rating")
+        && let Some(rating_num) = new_rating.as_f64()
+    {
+        update_album.rating = Some(Rating(rating_num));
+    }
+
+    if let Some(new_explicit) = payload.update.get("explicit")
+        && let Some(explicit_flag) = new_explicit.as_bool()
+    {
+        update_album.explicit = Some(explicit_flag);
+    }
+
+    update_album.validate()?;
+
+    let updated_count = update_query.set(&update_album).execute(&mut conn)? as i32;
+
+    Ok(Json(ApiResponse {
+        message: "bulk update complete".to_string(),
+        deleted: None,
+        updated: Some(updated_count),
+        count: None,
+    }))
+}
+
+pub fn albums_routes() -> Vec<rocket::Route> {
+    routes![
+        get_albums,
+        get_album_by_id,
+        post_albums,
+        update_album,
+        patch_album,
+        delete_album,
+        restore_album,
+        get_album_tracks,
+        add_album_track,
+        update_album_track,
+        delete_album_track,
+        bulk_delete_albums,
+        bulk_update_albums
+    ]
+}
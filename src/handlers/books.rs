@@ -1,21 +1,35 @@
 use {
     crate::{
-        auth::User,
+        auth::{AdminUser, User},
         db::connect_db,
-        models::{Book, NewBook, UpdateBook},
-        schema::books,
+        errors::ApiError,
+        handlers::{
+            ItemKind,
+            accounts::require_owner,
+            duplicates::{CreateOptions, MergeItems, check_duplicates, merge_items},
+            etags::{Created, IfMatch, Tagged},
+            extra::validate_extra,
+            identifiers::{ItemRef, Located, Resolved, resolve_ref},
+            parse_timestamp,
+            ratings::rating_scale,
+            reviews::{ReviewSummary, book_review_summaries},
+            series::{BookSeries, book_series},
+            tags::{TagKind, link_book_tags, resolve_tags, tag_names},
+        },
+        models::{Book, BookProgress, LinkKind, NewBook, NewBookProgress, Rating, UpdateBook},
+        schema::{book_progress, books},
     },
+    chrono::{NaiveDateTime, TimeDelta, Utc},
     diesel::prelude::*,
     nucleo::{Config, Nucleo},
     rocket::{
-        Route, delete,
+        Either, Route, delete,
         form::FromForm,
-        get,
-        http::Status,
-        patch, post, put, routes,
+        get, patch, post, put, routes,
         serde::{Deserialize, Serialize, json::Json},
     },
     std::collections::HashMap,
+    validator::Validate,
 };
 
 #[derive(FromForm, Debug)]
@@ -27,9 +41,29 @@ pub struct BookQuery {
     status: Option<String>,
     explicit: Option<String>,
     #[field(name = "minRating")]
-    min_rating: Option<i32>,
+    min_rating: Option<f64>,
     #[field(name = "maxRating")]
-    max_rating: Option<i32>,
+    max_rating: Option<f64>,
+    #[field(name = "minProgress")]
+    min_progress: Option<i32>,
+    #[field(name = "maxProgress")]
+    max_progress: Option<i32>,
+    #[field(name = "exactProgress")]
+    exact_progress: Option<i32>,
+    #[field(name = "createdAfter")]
+    created_after: Option<String>,
+    #[field(name = "updatedSince")]
+    updated_since: Option<String>,
+    #[field(name = "linkKind")]
+    link_kind: Option<String>,
+    #[field(name = "seriesId")]
+    series_id: Option<i32>,
+    /// Exact matches on extra fields, e.g. `extra.platform=switch`. Array
+    /// fields match any element.
+    extra: Option<HashMap<String, String>>,
+    /// Limits results to one account's library, as `/users/<handle>/...` does.
+    #[field(name = "ownerId")]
+    pub(crate) owner_id: Option<i32>,
     sort: Option<String>,
 }
 
@@ -56,12 +90,180 @@ pub struct ApiResponse {
     count: Option<usize>,
 }
 
+#[derive(Deserialize)]
+pub struct ProgressUpdate {
+    page: Option<i32>,
+    chapter: Option<i32>,
+    total_pages: Option<i32>,
+    total_chapters: Option<i32>,
+    note: Option<String>,
+    logged_at: Option<NaiveDateTime>,
+}
+
+#[derive(Serialize)]
+pub struct ReadingPace {
+    pages_per_day: Option<f64>,
+    chapters_per_day: Option<f64>,
+    estimated_finish: Option<NaiveDateTime>,
+}
+
+/// A book along with the figures derived from its related rows.
+#[derive(Serialize)]
+pub struct BookResponse {
+    #[serde(flatten)]
+    book: Book,
+    normalized_rating: f64,
+    pace: Option<ReadingPace>,
+    reviews: ReviewSummary,
+    series: Option<BookSeries>,
+}
+
+fn per_day(first: (NaiveDateTime, i32), last: (NaiveDateTime, i32)) -> Option<f64> {
+    let days = (last.0 - first.0).num_seconds() as f64 / 86_400.0;
+    (days > 0.0).then(|| f64::from(last.1 - first.1) / days)
+}
+
+fn remaining_days(rate: Option<f64>, current: Option<i32>, total: Option<i32>) -> Option<f64> {
+    match (rate, current, total) {
+        (Some(rate), Some(current), Some(total)) if rate > 0.0 && total > current => {
+            Some(f64::from(total - current) / rate)
+        }
+        _ => None,
+    }
+}
+
+/// Works out pages and chapters per day from the first and last progress log
+/// entries, and projects a finish date from whatever is left to read.
+fn reading_pace(book: &Book, log: &[BookProgress]) -> Option<ReadingPace> {
+    let pages: Vec<(NaiveDateTime, i32)> = log
+        .iter()
+        .filter_map(|entry| entry.page.map(|page| (entry.logged_at, page)))
+        .collect();
+    let chapters: Vec<(NaiveDateTime, i32)> = log
+        .iter()
+        .filter_map(|entry| entry.chapter.map(|chapter| (entry.logged_at, chapter)))
+        .collect();
+
+    let pages_per_day = match (pages.first(), pages.last()) {
+        (Some(first), Some(last)) => per_day(*first, *last),
+        _ => None,
+    };
+    let chapters_per_day = match (chapters.first(), chapters.last()) {
+        (Some(first), Some(last)) => per_day(*first, *last),
+        _ => None,
+    };
+
+    if pages_per_day.is_none() && chapters_per_day.is_none() {
+        return None;
+    }
+
+    let estimated_finish = remaining_days(pages_per_day, book.current_page, book.total_pages)
+        .or_else(|| remaining_days(chapters_per_day, book.current_chapter, book.total_chapters))
+        .map(|days| Utc::now().naive_utc() + TimeDelta::seconds((days * 86_400.0) as i64));
+
+    Some(ReadingPace {
+        pages_per_day,
+        chapters_per_day,
+        estimated_finish,
+    })
+}
+
+fn book_responses(conn: &mut PgConnection, loaded: Vec<Book>) -> QueryResult<Vec<BookResponse>> {
+    let ids: Vec<i32> = loaded.iter().map(|book| book.id).collect();
+
+    let mut logs: HashMap<i32, Vec<BookProgress>> = HashMap::new();
+    for entry in book_progress::table
+        .filter(book_progress::book_id.eq_any(&ids))
+        .order(book_progress::logged_at.asc())
+        .load::<BookProgress>(conn)?
+    {
+        logs.entry(entry.book_id).or_default().push(entry);
+    }
+
+    let summaries = book_review_summaries(conn, &ids)?;
+    let mut placements = book_series(conn, &loaded)?;
+    let scale = rating_scale(conn, ItemKind::Books)?;
+
+    Ok(loaded
+        .into_iter()
+        .map(|book| {
+            let log = logs.get(&book.id).map(Vec::as_slice).unwrap_or(&[]);
+            BookResponse {
+                normalized_rating: scale.normalize(book.rating),
+                pace: reading_pace(&book, log),
+                reviews: summaries.get(&book.id).copied().unwrap_or_default(),
+                series: placements.remove(&book.id),
+                book,
+            }
+        })
+        .collect())
+}
+
+fn book_response(conn: &mut PgConnection, book: Book) -> QueryResult<BookResponse> {
+    Ok(book_responses(conn, vec![book])?.remove(0))
+}
+
+/// A live book as `GET /read-watch/<id>` returns it.
+fn current_book(conn: &mut PgConnection, book_id: i32) -> Result<BookResponse, ApiError> {
+    let book = books::table
+        .find(book_id)
+        .filter(books::deleted_at.is_null())
+        .first::<Book>(conn)?;
+
+    Ok(book_response(conn, book)?)
+}
+
+/// Applies an update to a live book, canonicalizing any new genres and tags
+/// against the shared taxonomy.
+pub fn apply_book_update(
+    conn: &mut PgConnection,
+    book_id: i32,
+    mut changes: UpdateBook,
+) -> QueryResult<Book> {
+    use crate::schema::books::dsl::*;
+
+    conn.transaction(|conn| {
+        let genre_tags = match &changes.genres {
+            Some(names) => Some(resolve_tags(conn, TagKind::Genre, names)?),
+            None => None,
+        };
+        let tag_tags = match &changes.tags {
+            Some(names) => Some(resolve_tags(conn, TagKind::Tag, names)?),
+            None => None,
+        };
+
+        if let Some(resolved) = &genre_tags {
+            changes.genres = Some(tag_names(resolved));
+        }
+        if let Some(resolved) = &tag_tags {
+            changes.tags = Some(tag_names(resolved));
+        }
+
+        let book = diesel::update(books.filter(id.eq(book_id)).filter(deleted_at.is_null()))
+            .set(&changes)
+            .get_result::<Book>(conn)?;
+
+        if let Some(resolved) = &genre_tags {
+            link_book_tags(conn, book.id, TagKind::Genre, resolved)?;
+        }
+        if let Some(resolved) = &tag_tags {
+            link_book_tags(conn, book.id, TagKind::Tag, resolved)?;
+        }
+
+        Ok(book)
+    })
+}
+
 #[get("/search?<query..>")]
-pub fn get_books(query: BookQuery) -> Result<Json<Vec<Book>>, Status> {
+pub fn get_books(query: BookQuery) -> Result<Json<Vec<BookResponse>>, ApiError> {
     use crate::schema::books::dsl::*;
 
-    let mut conn = connect_db();
-    let mut book_query = books.into_boxed();
+    let mut conn = connect_db()?;
+    let mut book_query = books.filter(deleted_at.is_null()).into_boxed();
+
+    if let Some(owner_filter) = query.owner_id {
+        book_query = book_query.filter(owner_id.eq(owner_filter));
+    }
 
     if let Some(title_filter) = &query.title {
         book_query = book_query.filter(title.ilike(format!("%{}%", title_filter)));
@@ -84,11 +286,57 @@ pub fn get_books(query: BookQuery) -> Result<Json<Vec<Book>>, Status> {
     }
 
     if let Some(min_rating_filter) = query.min_rating {
-        book_query = book_query.filter(rating.ge(min_rating_filter));
+        book_query = book_query.filter(rating.ge(Rating(min_rating_filter)));
     }
 
     if let Some(max_rating_filter) = query.max_rating {
-        book_query = book_query.filter(rating.le(max_rating_filter));
+        book_query = book_query.filter(rating.le(Rating(max_rating_filter)));
+    }
+
+    if let Some(min_progress_filter) = query.min_progress {
+        book_query = book_query.filter(percent.ge(min_progress_filter));
+    }
+
+    if let Some(max_progress_filter) = query.max_progress {
+        book_query = book_query.filter(percent.le(max_progress_filter));
+    }
+
+    if let Some(exact_progress_filter) = query.exact_progress {
+        book_query = book_query.filter(percent.eq(exact_progress_filter));
+    }
+
+    if let Some(created_after_filter) = &query.created_after {
+        let timestamp = parse_timestamp(created_after_filter)
+            .ok_or_else(|| ApiError::bad_request("invalid createdAfter timestamp"))?;
+        book_query = book_query.filter(created_at.gt(timestamp));
+    }
+
+    if let Some(updated_since_filter) = &query.updated_since {
+        let timestamp = parse_timestamp(updated_since_filter)
+            .ok_or_else(|| ApiError::bad_request("invalid updatedSince timestamp"))?;
+        book_query = book_query.filter(updated_at.ge(timestamp));
+    }
+
+    if let Some(link_kind_filter) = &query.link_kind {
+        let kind = link_kind_filter
+            .parse::<LinkKind>()
+            .map_err(|_| ApiError::bad_request(format!("unknown link kind: {link_kind_filter}")))?;
+        book_query = book_query.filter(links.contains(serde_json::json!([{ "kind": kind }])));
+    }
+
+    if let Some(series_filter) = query.series_id {
+        book_query = book_query.filter(series_id.eq(series_filter));
+    }
+
+    if let Some(extra_filters) = &query.extra {
+        for (key, value) in extra_filters {
+            book_query = book_query.filter(
+                extra
+                    .retrieve_as_text(key.clone())
+                    .eq(value.clone())
+                    .or(extra.contains(serde_json::json!({ key: [value] }))),
+            );
+        }
     }
 
     if let Some(sort_by) = &query.sort {
@@ -96,13 +344,15 @@ pub fn get_books(query: BookQuery) -> Result<Json<Vec<Book>>, Status> {
             "title" => book_query = book_query.order(title.asc()),
             "author" => book_query = book_query.order(author.asc()),
             "rating" => book_query = book_query.order(rating.desc()),
+            "progress" => book_query = book_query.order(percent.desc().nulls_last()),
+            "volume" => book_query = book_query.order(series_volume.asc().nulls_last()),
+            "created" => book_query = book_query.order(created_at.desc()),
+            "updated" => book_query = book_query.order(updated_at.desc()),
             _ => {}
         }
     }
 
-    let results = book_query
-        .load::<Book>(&mut conn)
-        .map_err(|_| Status::InternalServerError)?;
+    let results = book_query.load::<Book>(&mut conn)?;
 
     let mut filtered_results = results;
 
@@ -130,110 +380,250 @@ pub fn get_books(query: BookQuery) -> Result<Json<Vec<Book>>, Status> {
         });
     }
 
-    Ok(Json(filtered_results))
+    let responses = book_responses(&mut conn, filtered_results)?;
+
+    Ok(Json(responses))
 }
 
-#[get("/<book_id>")]
-pub fn get_book_by_id(book_id: i32) -> Result<Json<Book>, Status> {
-    use crate::schema::books::dsl::*;
+#[get("/<book_ref>")]
+pub fn get_book_by_id(book_ref: ItemRef) -> Result<Located<BookResponse>, ApiError> {
+    let mut conn = connect_db()?;
 
-    let mut conn = connect_db();
+    let book_id = match resolve_ref(&mut conn, ItemKind::Books, &book_ref)? {
+        Resolved::Found(found) => found,
+        Resolved::Moved(redirect) => return Ok(Either::Right(*redirect)),
+    };
 
-    let book = books
-        .filter(id.eq(&book_id))
-        .first::<Book>(&mut conn)
-        .map_err(|_| Status::NotFound)?;
+    let response = current_book(&mut conn, book_id)?;
 
-    Ok(Json(book))
+    Ok(Either::Left(Tagged::new(response)))
 }
 
-#[post("/", format = "json", data = "<new_book>")]
-pub fn post_books(_user: User, new_book: Json<NewBook>) -> Result<Json<Book>, Status> {
-    _user.require_admin().expect("User is not admin");
-    let mut conn = connect_db();
-    let new_book = new_book.into_inner();
-
-    let inserted_book = diesel::insert_into(books::table)
-        .values(&new_book)
-        .get_result::<Book>(&mut conn)
-        .map_err(|_| Status::InternalServerError)?;
-
-    Ok(Json(inserted_book))
+#[post("/?<options..>", format = "json", data = "<new_book>")]
+pub fn post_books(
+    user: User,
+    options: CreateOptions,
+    new_book: Json<NewBook>,
+) -> Result<Created<BookResponse>, ApiError> {
+    let mut conn = connect_db()?;
+    let mut new_book = new_book.into_inner();
+    new_book.validate()?;
+    if let Some(extra) = &new_book.extra {
+        validate_extra(&mut conn, ItemKind::Books, extra)?;
+    }
+    check_duplicates(
+        &mut conn,
+        ItemKind::Books,
+        user.account_id(),
+        &new_book.title,
+        &new_book.author,
+        &options,
+    )?;
+
+    let inserted_book = conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        let genre_tags = resolve_tags(conn, TagKind::Genre, &new_book.genres)?;
+        let tag_tags = resolve_tags(conn, TagKind::Tag, &new_book.tags)?;
+        new_book.genres = tag_names(&genre_tags);
+        new_book.tags = tag_names(&tag_tags);
+
+        let book = diesel::insert_into(books::table)
+            .values((&new_book, books::owner_id.eq(user.account_id())))
+            .get_result::<Book>(conn)?;
+
+        link_book_tags(conn, book.id, TagKind::Genre, &genre_tags)?;
+        link_book_tags(conn, book.id, TagKind::Tag, &tag_tags)?;
+
+        Ok(book)
+    })?;
+
+    let location = ItemKind::Books.location(inserted_book.id);
+    let response = book_response(&mut conn, inserted_book)?;
+
+    Ok(Created::new(location, response))
 }
 
 #[put("/<book_id>", format = "json", data = "<updated_book>")]
 pub fn update_book(
-    _user: User,
+    user: User,
+    if_match: IfMatch,
     book_id: i32,
     updated_book: Json<UpdateBook>,
-) -> Result<Json<Book>, Status> {
-    _user.require_admin().expect("User is not admin");
-    use crate::schema::books::dsl::*;
-
-    let mut conn = connect_db();
+) -> Result<Tagged<BookResponse>, ApiError> {
+    let mut conn = connect_db()?;
+    require_owner(&mut conn, &user, ItemKind::Books, book_id)?;
+
+    let changes = updated_book.into_inner();
+    changes.validate()?;
+    if let Some(extra) = &changes.extra {
+        validate_extra(&mut conn, ItemKind::Books, extra)?;
+    }
 
-    let book = diesel::update(books.filter(id.eq(&book_id)))
-        .set(&updated_book.into_inner())
-        .get_result::<Book>(&mut conn)
-        .map_err(|_| Status::NotFound)?;
+    let response = conn.transaction::<_, ApiError, _>(|conn| {
+        if_match.check(conn, ItemKind::Books, book_id, current_book)?;
+        let book = apply_book_update(conn, book_id, changes)?;
+        Ok(book_response(conn, book)?)
+    })?;
 
-    Ok(Json(book))
+    Ok(Tagged::new(response))
 }
 
 #[patch("/<book_id>", format = "json", data = "<patch_data>")]
 pub fn patch_book(
-    _user: User,
+    user: User,
+    if_match: IfMatch,
     book_id: i32,
     patch_data: Json<UpdateBook>,
-) -> Result<Json<Book>, Status> {
-    _user.require_admin().expect("User is not admin");
-    use crate::schema::books::dsl::*;
-
-    let mut conn = connect_db();
+) -> Result<Tagged<BookResponse>, ApiError> {
+    let mut conn = connect_db()?;
+    require_owner(&mut conn, &user, ItemKind::Books, book_id)?;
+
+    let changes = patch_data.into_inner();
+    changes.validate()?;
+    if let Some(extra) = &changes.extra {
+        validate_extra(&mut conn, ItemKind::Books, extra)?;
+    }
 
-    let book = diesel::update(books.filter(id.eq(&book_id)))
-        .set(&patch_data.into_inner())
-        .get_result::<Book>(&mut conn)
-        .map_err(|_| Status::NotFound)?;
+    let response = conn.transaction::<_, ApiError, _>(|conn| {
+        if_match.check(conn, ItemKind::Books, book_id, current_book)?;
+        let book = apply_book_update(conn, book_id, changes)?;
+        Ok(book_response(conn, book)?)
+    })?;
 
-    Ok(Json(book))
+    Ok(Tagged::new(response))
 }
 
 #[delete("/<book_id>")]
-pub fn delete_book(_user: User, book_id: i32) -> Result<Json<ApiResponse>, Status> {
-    _user.require_admin().expect("User is not admin");
+pub fn delete_book(
+    user: User,
+    if_match: IfMatch,
+    book_id: i32,
+) -> Result<Json<ApiResponse>, ApiError> {
     use crate::schema::books::dsl::*;
 
-    let mut conn = connect_db();
+    let mut conn = connect_db()?;
+    require_owner(&mut conn, &user, ItemKind::Books, book_id)?;
 
-    let rows_deleted = diesel::delete(books.filter(id.eq(&book_id)))
-        .execute(&mut conn)
-        .map_err(|_| Status::InternalServerError)?;
+    let rows_deleted = conn.transaction::<_, ApiError, _>(|conn| {
+        if_match.check(conn, ItemKind::Books, book_id, current_book)?;
+        Ok(
+            diesel::update(books.filter(id.eq(&book_id)).filter(deleted_at.is_null()))
+                .set(deleted_at.eq(diesel::dsl::now.nullable()))
+                .execute(conn)?,
+        )
+    })?;
 
     if rows_deleted > 0 {
         Ok(Json(ApiResponse {
-            message: "book deleted".to_string(),
+            message: "book moved to trash".to_string(),
             deleted: None,
             updated: None,
             count: None,
         }))
     } else {
-        Err(Status::NotFound)
+        Err(ApiError::NotFound)
     }
 }
 
+#[post("/<book_id>/restore")]
+pub fn restore_book(user: User, book_id: i32) -> Result<Json<Book>, ApiError> {
+    use crate::schema::books::dsl::*;
+
+    let mut conn = connect_db()?;
+    require_owner(&mut conn, &user, ItemKind::Books, book_id)?;
+
+    let book = diesel::update(
+        books
+            .filter(id.eq(&book_id))
+            .filter(deleted_at.is_not_null()),
+    )
+    .set(deleted_at.eq(None::<chrono::NaiveDateTime>))
+    .get_result::<Book>(&mut conn)?;
+
+    Ok(Json(book))
+}
+
+/// Folds a duplicate book into `merge.into`, which is returned.
+#[post("/<book_id>/merge", format = "json", data = "<merge>")]
+pub fn merge_book(
+    _admin: AdminUser,
+    book_id: i32,
+    merge: Json<MergeItems>,
+) -> Result<Tagged<BookResponse>, ApiError> {
+    let mut conn = connect_db()?;
+    merge_items(&mut conn, ItemKind::Books, book_id, merge.into)?;
+
+    Ok(Tagged::new(current_book(&mut conn, merge.into)?))
+}
+
+#[get("/<book_id>/progress")]
+pub fn get_book_progress(book_id: i32) -> Result<Json<Vec<BookProgress>>, ApiError> {
+    let mut conn = connect_db()?;
+
+    let log = book_progress::table
+        .filter(book_progress::book_id.eq(book_id))
+        .order(book_progress::logged_at.asc())
+        .load::<BookProgress>(&mut conn)?;
+
+    Ok(Json(log))
+}
+
+#[post("/<book_id>/progress", format = "json", data = "<progress>")]
+pub fn log_book_progress(
+    user: User,
+    book_id: i32,
+    progress: Json<ProgressUpdate>,
+) -> Result<Json<BookResponse>, ApiError> {
+    let progress = progress.into_inner();
+    if progress.page.is_none() && progress.chapter.is_none() {
+        return Err(ApiError::validation("progress needs a page or a chapter"));
+    }
+
+    let mut conn = connect_db()?;
+    require_owner(&mut conn, &user, ItemKind::Books, book_id)?;
+
+    let response = conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        let book = diesel::update(
+            books::table
+                .find(book_id)
+                .filter(books::deleted_at.is_null()),
+        )
+        .set(&UpdateBook {
+            current_page: progress.page,
+            total_pages: progress.total_pages,
+            current_chapter: progress.chapter,
+            total_chapters: progress.total_chapters,
+            ..Default::default()
+        })
+        .get_result::<Book>(conn)?;
+
+        diesel::insert_into(book_progress::table)
+            .values(&NewBookProgress {
+                book_id,
+                page: progress.page,
+                chapter: progress.chapter,
+                note: progress.note,
+                logged_at: progress.logged_at,
+            })
+            .execute(conn)?;
+
+        book_response(conn, book)
+    })?;
+
+    Ok(Json(response))
+}
+
 #[delete("/bulk", format = "json", data = "<filter>")]
 pub fn bulk_delete_books(
     _user: User,
     filter: Json<BulkDeleteFilter>,
-) -> Result<Json<ApiResponse>, Status> {
-    _user.require_admin().expect("User is not admin");
+) -> Result<Json<ApiResponse>, ApiError> {
+    _user.require_admin()?;
     use crate::schema::books::dsl::*;
 
-    let mut conn = connect_db();
+    let mut conn = connect_db()?;
     let filter = filter.into_inner();
 
-    let mut delete_query = diesel::delete(books).into_boxed();
+    let mut delete_query = diesel::update(books.filter(deleted_at.is_null())).into_boxed();
 
     if let Some(author_filter) = &filter.author {
         delete_query = delete_query.filter(author.eq(author_filter));
@@ -244,8 +634,8 @@ pub fn bulk_delete_books(
     }
 
     let deleted_count = delete_query
-        .execute(&mut conn)
-        .map_err(|_| Status::InternalServerError)? as i32;
+        .set(deleted_at.eq(diesel::dsl::now.nullable()))
+        .execute(&mut conn)? as i32;
 
     Ok(Json(ApiResponse {
         message: "bulk delete complete".to_string(),
@@ -259,14 +649,14 @@ pub fn bulk_delete_books(
 pub fn bulk_update_books(
     _user: User,
     payload: Json<BulkUpdatePayload>,
-) -> Result<Json<ApiResponse>, Status> {
-    _user.require_admin().expect("User is not admin");
+) -> Result<Json<ApiResponse>, ApiError> {
+    _user.require_admin()?;
     use crate::schema::books::dsl::*;
 
-    let mut conn = connect_db();
+    let mut conn = connect_db()?;
     let payload = payload.into_inner();
 
-    let mut update_query = diesel::update(books).into_boxed();
+    let mut update_query = diesel::update(books.filter(deleted_at.is_null())).into_boxed();
 
     if let Some(author_filter) = payload.filter.get("author") {
         update_query = update_query.filter(author.eq(author_filter));
@@ -276,20 +666,7 @@ pub fn bulk_update_books(
         update_query = update_query.filter(status.eq(status_filter));
     }
 
-    let mut update_book = UpdateBook {
-        title: None,
-        author: None,
-        genres: None,
-        tags: None,
-        rating: None,
-        status: None,
-        description: None,
-        my_thoughts: None,
-        links: None,
-        cover_image: None,
-        explicit: None,
-        color: None,
-    };
+    let mut update_book = UpdateBook::default();
 
     if let Some(new_status) = payload.update.get("status")
         && let Some(status_str) = new_status.as_str()
@@ -300,13 +677,12 @@ pub fn bulk_update_books(
     if let Some(new_rating) = payload.update.get("rating")
         && let Some(rating_num) = new_rating.as_f64()
     {
-        update_book.rating = Some(rating_num as i32);
+        update_book.rating = Some(Rating(rating_num));
     }
 
-    let updated_count = update_query
-        .set(&update_book)
-        .execute(&mut conn)
-        .map_err(|_| Status::InternalServerError)? as i32;
+    update_book.validate()?;
+
+    let updated_count = update_query.set(&update_book).execute(&mut conn)? as i32;
 
     Ok(Json(ApiResponse {
         message: "bulk update complete".to_string(),
@@ -324,6 +700,10 @@ pub fn read_watch_routes() -> Vec<Route> {
         update_book,
         patch_book,
         delete_book,
+        restore_book,
+        merge_book,
+        get_book_progress,
+        log_book_progress,
         bulk_delete_books,
         bulk_update_books
     ]
@@ -0,0 +1,635 @@
+use crate::auth::AdminUser;
+use crate::db::connect_db;
+use crate::errors::ApiError;
+use crate::handlers::etags::{Created, IfMatch, Tagged};
+use crate::handlers::parse_timestamp;
+use crate::models::{Collection, CollectionItem, NewCollection, NewCollectionItem};
+use crate::schema::{collection_items, collections};
+use diesel::prelude::*;
+use diesel::result::{DatabaseErrorKind, Error};
+use rocket::serde::json::Json;
+use rocket::{FromForm, delete, get, patch, post, put, routes};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+
+const DEFAULT_PER_PAGE: usize = 25;
+const MAX_PER_PAGE: usize = 100;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldType {
+    String,
+    Integer,
+    Number,
+    Boolean,
+    Timestamp,
+}
+
+/// One field of a collection's schema, e.g.
+/// `{"name": "status", "type": "string", "required": true, "enum": ["watched", "planned"]}`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FieldDefinition {
+    name: String,
+    #[serde(rename = "type")]
+    field_type: FieldType,
+    #[serde(default)]
+    required: bool,
+    /// Holds a list of values of `field_type` rather than a single one.
+    #[serde(default)]
+    array: bool,
+    /// Restricts a string field to a fixed set of values.
+    #[serde(default, rename = "enum", skip_serializing_if = "Option::is_none")]
+    values: Option<Vec<String>>,
+}
+
+impl FieldDefinition {
+    fn accepts_scalar(&self, value: &Value) -> bool {
+        match (self.field_type, value) {
+            (FieldType::String, Value::String(text)) => self
+                .values
+                .as_ref()
+                .is_none_or(|values| values.contains(text)),
+            (FieldType::Integer, Value::Number(number)) => number.is_i64() || number.is_u64(),
+            (FieldType::Number, Value::Number(_)) => true,
+            (FieldType::Boolean, Value::Bool(_)) => true,
+            (FieldType::Timestamp, Value::String(text)) => parse_timestamp(text).is_some(),
+            _ => false,
+        }
+    }
+
+    fn accepts(&self, value: &Value) -> bool {
+        match value {
+            Value::Null => !self.required,
+            Value::Array(items) if self.array => items.iter().all(|item| self.accepts_scalar(item)),
+            _ if self.array => false,
+            _ => self.accepts_scalar(value),
+        }
+    }
+
+    /// Parses a query string value into the JSON this field stores.
+    fn parse_query_value(&self, raw: &str) -> Option<Value> {
+        match self.field_type {
+            FieldType::String => Some(Value::String(raw.to_string())),
+            FieldType::Timestamp => parse_timestamp(raw).map(|_| Value::String(raw.to_string())),
+            FieldType::Integer => raw.parse::<i64>().ok().map(Value::from),
+            FieldType::Number => raw.parse::<f64>().ok().map(Value::from),
+            FieldType::Boolean => raw.parse::<bool>().ok().map(Value::Bool),
+        }
+    }
+
+    fn compare(&self, a: &Value, b: &Value) -> Ordering {
+        match self.field_type {
+            FieldType::Integer | FieldType::Number => a
+                .as_f64()
+                .partial_cmp(&b.as_f64())
+                .unwrap_or(Ordering::Equal),
+            FieldType::Timestamp => a
+                .as_str()
+                .and_then(parse_timestamp)
+                .cmp(&b.as_str().and_then(parse_timestamp)),
+            FieldType::String => a
+                .as_str()
+                .map(str::to_lowercase)
+                .cmp(&b.as_str().map(str::to_lowercase)),
+            FieldType::Boolean => a.as_bool().cmp(&b.as_bool()),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CollectionPayload {
+    name: String,
+    description: Option<String>,
+    fields: Vec<FieldDefinition>,
+}
+
+#[derive(Deserialize)]
+pub struct SchemaUpdate {
+    description: Option<String>,
+    fields: Vec<FieldDefinition>,
+}
+
+#[derive(FromForm, Debug)]
+pub struct CollectionQuery {
+    /// Case-insensitive search across every string field.
+    q: Option<String>,
+    /// A field name, `created` or `updated`; prefix with `-` to sort descending.
+    sort: Option<String>,
+    page: Option<usize>,
+    #[field(name = "perPage")]
+    per_page: Option<usize>,
+    /// Exact matches, e.g. `where.status=watched`. Array fields match any element.
+    #[field(name = "where")]
+    filters: Option<HashMap<String, String>>,
+    min: Option<HashMap<String, String>>,
+    max: Option<HashMap<String, String>>,
+}
+
+#[derive(Serialize)]
+pub struct CollectionPage {
+    items: Vec<CollectionItem>,
+    total: usize,
+    page: usize,
+    per_page: usize,
+}
+
+fn is_valid_field_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|first| first.is_ascii_lowercase() || first == '_')
+        && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+fn validate_schema(fields: &[FieldDefinition]) -> Result<(), ApiError> {
+    let mut seen = HashSet::new();
+
+    for field in fields {
+        let valid_enum = match &field.values {
+            Some(values) => field.field_type == FieldType::String && !values.is_empty(),
+            None => true,
+        };
+
+        if !is_valid_field_name(&field.name) {
+            return Err(ApiError::validation(format!(
+                "invalid field name: {}",
+                field.name
+            )));
+        }
+        if !seen.insert(field.name.as_str()) {
+            return Err(ApiError::validation(format!(
+                "duplicate field: {}",
+                field.name
+            )));
+        }
+        if !valid_enum {
+            return Err(ApiError::validation(format!(
+                "{}: only non-empty string fields can list values",
+                field.name
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks an item's data against the collection's schema. Unknown keys are
+/// rejected so typos don't silently become new fields.
+fn validate_item(fields: &[FieldDefinition], data: &Value) -> Result<(), ApiError> {
+    let object = data
+        .as_object()
+        .ok_or_else(|| ApiError::validation("item data must be an object"))?;
+
+    if let Some(key) = object
+        .keys()
+        .find(|key| !fields.iter().any(|field| &field.name == *key))
+    {
+        return Err(ApiError::validation(format!("unknown field: {key}")));
+    }
+
+    for field in fields {
+        if !field.accepts(object.get(&field.name).unwrap_or(&Value::Null)) {
+            return Err(ApiError::validation(format!(
+                "invalid value for field: {}",
+                field.name
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+fn schema_fields(collection: &Collection) -> Result<Vec<FieldDefinition>, ApiError> {
+    serde_json::from_value(collection.fields.clone()).map_err(|_| ApiError::Internal)
+}
+
+fn find_field<'a>(
+    fields: &'a [FieldDefinition],
+    name: &str,
+) -> Result<&'a FieldDefinition, ApiError> {
+    fields
+        .iter()
+        .find(|field| field.name == name)
+        .ok_or_else(|| ApiError::bad_request(format!("unknown field: {name}")))
+}
+
+fn find_collection(conn: &mut PgConnection, name: &str) -> Result<Collection, ApiError> {
+    collections::table
+        .filter(collections::name.eq(name))
+        .first::<Collection>(conn)
+        .map_err(ApiError::from)
+}
+
+/// Loads a collection to write one of its items, share-locking its row until
+/// the transaction ends so the schema can't change before the write commits.
+fn lock_collection(conn: &mut PgConnection, name: &str) -> QueryResult<Collection> {
+    collections::table
+        .filter(collections::name.eq(name))
+        .for_share()
+        .first::<Collection>(conn)
+}
+
+/// Locks one of a collection's items until the end of the current
+/// transaction.
+fn lock_collection_item(
+    conn: &mut PgConnection,
+    collection_id: i32,
+    item_id: i32,
+) -> QueryResult<CollectionItem> {
+    collection_items::table
+        .find(item_id)
+        .filter(collection_items::collection_id.eq(collection_id))
+        .for_update()
+        .first::<CollectionItem>(conn)
+}
+
+fn map_collection_error(error: Error) -> ApiError {
+    match error {
+        Error::DatabaseError(DatabaseErrorKind::UniqueViolation, _) => {
+            ApiError::conflict("a collection with that name already exists")
+        }
+        error => ApiError::from(error),
+    }
+}
+
+fn matches_search(data: &Value, fields: &[FieldDefinition], needle: &str) -> bool {
+    fields
+        .iter()
+        .filter(|field| field.field_type == FieldType::String)
+        .filter_map(|field| data.get(&field.name))
+        .flat_map(|value| match value {
+            Value::Array(items) => items.iter().collect(),
+            value => vec![value],
+        })
+        .filter_map(Value::as_str)
+        .any(|text| text.to_lowercase().contains(needle))
+}
+
+/// Keeps the items whose value for each bounded field compares to the bound
+/// as `keep` requires. Items missing the field are dropped.
+fn apply_bounds(
+    items: &mut Vec<CollectionItem>,
+    fields: &[FieldDefinition],
+    bounds: Option<&HashMap<String, String>>,
+    keep: fn(Ordering) -> bool,
+) -> Result<(), ApiError> {
+    for (field_name, raw) in bounds.into_iter().flatten() {
+        let field = find_field(fields, field_name)?;
+        if field.array {
+            return Err(ApiError::bad_request(format!(
+                "array field can't be bounded: {field_name}"
+            )));
+        }
+        let bound = field
+            .parse_query_value(raw)
+            .ok_or_else(|| ApiError::bad_request(format!("invalid bound for {field_name}")))?;
+
+        items.retain(|item| {
+            item.data
+                .get(field_name)
+                .filter(|value| !value.is_null())
+                .is_some_and(|value| keep(field.compare(value, &bound)))
+        });
+    }
+
+    Ok(())
+}
+
+#[get("/")]
+pub fn get_collections() -> Result<Json<Vec<Collection>>, ApiError> {
+    let mut conn = connect_db()?;
+
+    collections::table
+        .order(collections::name.asc())
+        .load::<Collection>(&mut conn)
+        .map(Json)
+        .map_err(ApiError::from)
+}
+
+#[post("/", format = "json", data = "<payload>")]
+pub fn create_collection(
+    _admin: AdminUser,
+    payload: Json<CollectionPayload>,
+) -> Result<Created<Collection>, ApiError> {
+    let payload = payload.into_inner();
+    validate_schema(&payload.fields)?;
+
+    let fields = serde_json::to_value(&payload.fields).map_err(|_| ApiError::Internal)?;
+    let mut conn = connect_db()?;
+
+    let collection = diesel::insert_into(collections::table)
+        .values(&NewCollection {
+            name: &payload.name,
+            description: payload.description.as_deref(),
+            fields,
+        })
+        .get_result::<Collection>(&mut conn)
+        .map_err(map_collection_error)?;
+
+    Ok(Created::new(
+        format!("/collections/{}/schema", collection.name),
+        collection,
+    ))
+}
+
+#[get("/<name>/schema")]
+pub fn get_collection_schema(name: &str) -> Result<Json<Collection>, ApiError> {
+    let mut conn = connect_db()?;
+    find_collection(&mut conn, name).map(Json)
+}
+
+/// Replaces a collection's schema. Every existing item must still validate
+/// against the new fields, otherwise nothing is changed.
+#[put("/<name>/schema", format = "json", data = "<update>")]
+pub fn update_collection_schema(
+    _admin: AdminUser,
+    name: &str,
+    update: Json<SchemaUpdate>,
+) -> Result<Json<Collection>, ApiError> {
+    let update = update.into_inner();
+    validate_schema(&update.fields)?;
+
+    let fields = serde_json::to_value(&update.fields).map_err(|_| ApiError::Internal)?;
+    let mut conn = connect_db()?;
+
+    // The collection stays locked from the check to the update, so items
+    // written in between wait for the new schema instead of slipping past it.
+    conn.transaction::<_, ApiError, _>(|conn| {
+        let collection = collections::table
+            .filter(collections::name.eq(name))
+            .for_update()
+            .first::<Collection>(conn)?;
+
+        let items = collection_items::table
+            .filter(collection_items::collection_id.eq(collection.id))
+            .load::<CollectionItem>(conn)?;
+
+        for item in &items {
+            validate_item(&update.fields, &item.data)?;
+        }
+
+        diesel::update(collections::table.find(collection.id))
+            .set((
+                collections::description.eq(update.description),
+                collections::fields.eq(fields),
+            ))
+            .get_result::<Collection>(conn)
+            .map_err(map_collection_error)
+    })
+    .map(Json)
+}
+
+/// Deletes a collection along with all of its items.
+#[delete("/<name>")]
+pub fn delete_collection(_admin: AdminUser, name: &str) -> Result<Json<Collection>, ApiError> {
+    let mut conn = connect_db()?;
+
+    diesel::delete(collections::table.filter(collections::name.eq(name)))
+        .get_result::<Collection>(&mut conn)
+        .map(Json)
+        .map_err(map_collection_error)
+}
+
+#[get("/<name>?<query..>")]
+pub fn get_collection_items(
+    name: &str,
+    query: CollectionQuery,
+) -> Result<Json<CollectionPage>, ApiError> {
+    let mut conn = connect_db()?;
+    let collection = find_collection(&mut conn, name)?;
+    let fields = schema_fields(&collection)?;
+
+    let mut item_query = collection_items::table
+        .filter(collection_items::collection_id.eq(collection.id))
+        .order(collection_items::id.asc())
+        .into_boxed();
+
+    if let Some(filters) = &query.filters {
+        let mut containment = Map::new();
+
+        for (field_name, raw) in filters {
+            let field = find_field(&fields, field_name)?;
+            let value = field
+                .parse_query_value(raw)
+                .ok_or_else(|| ApiError::bad_request(format!("invalid value for {field_name}")))?;
+            let value = if field.array {
+                Value::Array(vec![value])
+            } else {
+                value
+            };
+            containment.insert(field_name.clone(), value);
+        }
+
+        item_query = item_query.filter(collection_items::data.contains(Value::Object(containment)));
+    }
+
+    let mut items = item_query.load::<CollectionItem>(&mut conn)?;
+
+    apply_bounds(&mut items, &fields, query.min.as_ref(), Ordering::is_ge)?;
+    apply_bounds(&mut items, &fields, query.max.as_ref(), Ordering::is_le)?;
+
+    if let Some(search) = &query.q {
+        let needle = search.to_lowercase();
+        items.retain(|item| matches_search(&item.data, &fields, &needle));
+    }
+
+    if let Some(sort_by) = &query.sort {
+        let (descending, key) = match sort_by.strip_prefix('-') {
+            Some(key) => (true, key),
+            None => (false, sort_by.as_str()),
+        };
+
+        match key {
+            "created" => items.sort_by_key(|item| item.created_at),
+            "updated" => items.sort_by_key(|item| item.updated_at),
+            _ => {
+                let field = find_field(&fields, key)?;
+                if field.array {
+                    return Err(ApiError::bad_request(format!(
+                        "array field can't be sorted by: {key}"
+                    )));
+                }
+                items.sort_by(|a, b| {
+                    field.compare(
+                        a.data.get(key).unwrap_or(&Value::Null),
+                        b.data.get(key).unwrap_or(&Value::Null),
+                    )
+                });
+            }
+        }
+
+        if descending {
+            items.reverse();
+        }
+    }
+
+    let total = items.len();
+    let page = query.page.unwrap_or(1).max(1);
+    let per_page = query
+        .per_page
+        .unwrap_or(DEFAULT_PER_PAGE)
+        .clamp(1, MAX_PER_PAGE);
+
+    let items = items
+        .into_iter()
+        .skip((page - 1).saturating_mul(per_page))
+        .take(per_page)
+        .collect();
+
+    Ok(Json(CollectionPage {
+        items,
+        total,
+        page,
+        per_page,
+    }))
+}
+
+#[get("/<name>/<item_id>")]
+pub fn get_collection_item(name: &str, item_id: i32) -> Result<Tagged<CollectionItem>, ApiError> {
+    let mut conn = connect_db()?;
+    let collection = find_collection(&mut conn, name)?;
+
+    collection_items::table
+        .find(item_id)
+        .filter(collection_items::collection_id.eq(collection.id))
+        .first::<CollectionItem>(&mut conn)
+        .map(Tagged::new)
+        .map_err(ApiError::from)
+}
+
+#[post("/<name>", format = "json", data = "<data>")]
+pub fn create_collection_item(
+    _admin: AdminUser,
+    name: &str,
+    data: Json<Value>,
+) -> Result<Created<CollectionItem>, ApiError> {
+    let data = data.into_inner();
+    let mut conn = connect_db()?;
+
+    conn.transaction::<_, ApiError, _>(|conn| {
+        let collection = lock_collection(conn, name)?;
+        validate_item(&schema_fields(&collection)?, &data)?;
+
+        diesel::insert_into(collection_items::table)
+            .values(&NewCollectionItem {
+                collection_id: collection.id,
+                data,
+            })
+            .get_result::<CollectionItem>(conn)
+            .map_err(map_collection_error)
+    })
+    .map(|item| Created::new(format!("/collections/{name}/{}", item.id), item))
+}
+
+#[put("/<name>/<item_id>", format = "json", data = "<data>")]
+pub fn replace_collection_item(
+    _admin: AdminUser,
+    if_match: IfMatch,
+    name: &str,
+    item_id: i32,
+    data: Json<Value>,
+) -> Result<Tagged<CollectionItem>, ApiError> {
+    let data = data.into_inner();
+    let mut conn = connect_db()?;
+
+    conn.transaction::<_, ApiError, _>(|conn| {
+        let collection = lock_collection(conn, name)?;
+        if_match.check_with(conn, |conn| {
+            Ok(lock_collection_item(conn, collection.id, item_id)?)
+        })?;
+        validate_item(&schema_fields(&collection)?, &data)?;
+
+        diesel::update(
+            collection_items::table
+                .find(item_id)
+                .filter(collection_items::collection_id.eq(collection.id)),
+        )
+        .set(collection_items::data.eq(data))
+        .get_result::<CollectionItem>(conn)
+        .map_err(map_collection_error)
+    })
+    .map(Tagged::new)
+}
+
+/// Merges the given keys into an item. A `null` value removes the key.
+#[patch("/<name>/<item_id>", format = "json", data = "<changes>")]
+pub fn patch_collection_item(
+    _admin: AdminUser,
+    if_match: IfMatch,
+    name: &str,
+    item_id: i32,
+    changes: Json<Map<String, Value>>,
+) -> Result<Tagged<CollectionItem>, ApiError> {
+    let mut conn = connect_db()?;
+
+    conn.transaction::<_, ApiError, _>(|conn| {
+        let collection = lock_collection(conn, name)?;
+        let item = lock_collection_item(conn, collection.id, item_id)?;
+        if_match.check_with(conn, |_| Ok(&item))?;
+
+        let mut data = match item.data {
+            Value::Object(data) => data,
+            _ => Map::new(),
+        };
+        for (key, value) in changes.into_inner() {
+            if value.is_null() {
+                data.remove(&key);
+            } else {
+                data.insert(key, value);
+            }
+        }
+        let data = Value::Object(data);
+
+        validate_item(&schema_fields(&collection)?, &data)?;
+
+        diesel::update(collection_items::table.find(item.id))
+            .set(collection_items::data.eq(data))
+            .get_result::<CollectionItem>(conn)
+            .map_err(map_collection_error)
+    })
+    .map(Tagged::new)
+}
+
+#[delete("/<name>/<item_id>")]
+pub fn delete_collection_item(
+    _admin: AdminUser,
+    if_match: IfMatch,
+    name: &str,
+    item_id: i32,
+) -> Result<Json<CollectionItem>, ApiError> {
+    let mut conn = connect_db()?;
+
+    conn.transaction::<_, ApiError, _>(|conn| {
+        let collection = lock_collection(conn, name)?;
+        if_match.check_with(conn, |conn| {
+            Ok(lock_collection_item(conn, collection.id, item_id)?)
+        })?;
+
+        diesel::delete(
+            collection_items::table
+                .find(item_id)
+                .filter(collection_items::collection_id.eq(collection.id)),
+        )
+        .get_result::<CollectionItem>(conn)
+        .map_err(map_collection_error)
+    })
+    .map(Json)
+}
+
+pub fn collections_routes() -> Vec<rocket::Route> {
+    routes![
+        get_collections,
+        create_collection,
+        get_collection_schema,
+        update_collection_schema,
+        delete_collection,
+        get_collection_items,
+        get_collection_item,
+        create_collection_item,
+        replace_collection_item,
+        patch_collection_item,
+        delete_collection_item
+    ]
+}
@@ -0,0 +1,586 @@
+use crate::errors::ApiError;
+use crate::handlers::ItemKind;
+use crate::handlers::accounts::item_owner;
+use crate::handlers::relations::repoint_relations;
+use crate::handlers::tags::{TagKind, link_book_tags, link_game_tags, resolve_tags, tag_names};
+use crate::models::{Book, Game, Links, Project};
+use crate::schema::{
+    book_progress, books, games, projects, reviews, revisions, slug_history, status_changes,
+};
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel::sql_types::{Integer, Text};
+use rocket::FromForm;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// How alike two normalized titles have to be, from 0 to 1, to be reported as
+/// likely duplicates.
+const SIMILARITY_THRESHOLD: f64 = 0.8;
+
+/// Query options for `POST /read-watch`, `/games` and `/projects`.
+#[derive(FromForm, Debug)]
+pub struct CreateOptions {
+    /// Creates the item even if it looks like one already in the library.
+    #[field(name = "allowDuplicate")]
+    allow_duplicate: Option<bool>,
+}
+
+/// The body of `POST /<id>/merge`: the item to fold this one into.
+#[derive(Deserialize)]
+pub struct MergeItems {
+    pub into: i32,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchKind {
+    /// Same title and author, developer or source once normalized.
+    Exact,
+    /// A title close enough to be the same thing spelled differently.
+    Similar,
+}
+
+/// An existing item that a new one probably duplicates.
+#[derive(Serialize, Debug)]
+pub struct Candidate {
+    id: i32,
+    slug: String,
+    title: String,
+    #[serde(rename = "match")]
+    match_kind: MatchKind,
+    similarity: f64,
+}
+
+/// Lowercases `value` and keeps only its words, so "The Hobbit!" and
+/// "the  hobbit" compare equal.
+fn normalize(value: &str) -> String {
+    value
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The Dice coefficient of two strings' character bigrams: 1 for identical
+/// strings, 0 for ones with no pair of letters in common.
+fn similarity(a: &str, b: &str) -> f64 {
+    if a == b {
+        return 1.0;
+    }
+
+    let bigrams = |value: &str| -> Vec<(char, char)> {
+        let chars: Vec<char> = value.chars().collect();
+        chars.windows(2).map(|pair| (pair[0], pair[1])).collect()
+    };
+    let left = bigrams(a);
+    let mut right = bigrams(b);
+    let total = left.len() + right.len();
+    if total == 0 {
+        return 0.0;
+    }
+
+    let mut shared = 0;
+    for pair in left {
+        if let Some(index) = right.iter().position(|other| *other == pair) {
+            right.swap_remove(index);
+            shared += 1;
+        }
+    }
+
+    2.0 * shared as f64 / total as f64
+}
+
+/// How an existing item with title similarity `score` relates to a new one,
+/// if it's close enough to report.
+fn match_kind(score: f64, same_creator: bool) -> Option<MatchKind> {
+    if score == 1.0 && same_creator {
+        Some(MatchKind::Exact)
+    } else if score >= SIMILARITY_THRESHOLD {
+        Some(MatchKind::Similar)
+    } else {
+        None
+    }
+}
+
+/// Live items in `owner_id`'s library that a new item titled `title` by
+/// `creator` probably duplicates, exact matches first. The creator is a book's
+/// author, a game's developer or a project's source.
+pub fn find_duplicates(
+    conn: &mut PgConnection,
+    kind: ItemKind,
+    owner_id: i32,
+    title: &str,
+    creator: &str,
+) -> QueryResult<Vec<Candidate>> {
+    let existing: Vec<(i32, String, String, String)> = match kind {
+        ItemKind::Books => books::table
+            .filter(books::owner_id.eq(owner_id))
+            .filter(books::deleted_at.is_null())
+            .select((books::id, books::slug, books::title, books::author))
+            .load(conn)?,
+        ItemKind::Games => games::table
+            .filter(games::owner_id.eq(owner_id))
+            .filter(games::deleted_at.is_null())
+            .select((games::id, games::slug, games::title, games::developer))
+            .load(conn)?,
+        ItemKind::Projects => projects::table
+            .filter(projects::owner_id.eq(owner_id))
+            .filter(projects::deleted_at.is_null())
+            .select((
+                projects::id,
+                projects::slug,
+                projects::name,
+                projects::source,
+            ))
+            .load(conn)?,
+        _ => Vec::new(),
+    };
+
+    let title = normalize(title);
+    let creator = normalize(creator);
+
+    let mut candidates: Vec<Candidate> = existing
+        .into_iter()
+        .filter_map(|(id, slug, existing_title, existing_creator)| {
+            let score = similarity(&title, &normalize(&existing_title));
+            let match_kind = match_kind(score, creator == normalize(&existing_creator))?;
+
+            Some(Candidate {
+                id,
+                slug,
+                title: existing_title,
+                match_kind,
+                similarity: (score * 100.0).round() / 100.0,
+            })
+        })
+        .collect();
+
+    candidates.sort_by(|a, b| {
+        a.match_kind
+            .cmp(&b.match_kind)
+            .then(b.similarity.total_cmp(&a.similarity))
+            .then(a.id.cmp(&b.id))
+    });
+
+    Ok(candidates)
+}
+
+/// Fails with 409 and the likely duplicates when a new item looks like one
+/// already in the owner's library, unless `?allowDuplicate=true` was given.
+pub fn check_duplicates(
+    conn: &mut PgConnection,
+    kind: ItemKind,
+    owner_id: i32,
+    title: &str,
+    creator: &str,
+    options: &CreateOptions,
+) -> Result<(), ApiError> {
+    if options.allow_duplicate.unwrap_or(false) {
+        return Ok(());
+    }
+
+    let candidates = find_duplicates(conn, kind, owner_id, title, creator)?;
+    if candidates.is_empty() {
+        Ok(())
+    } else {
+        Err(ApiError::Duplicate(serde_json::json!(candidates)))
+    }
+}
+
+/// Folds item `from` into `into`: tags and links are combined, a book's series
+/// and any `extra` fields fill in what `into` lacks, reviews, reading
+/// progress, status changes, revisions, relations and old slugs move over, and
+/// `from` is deleted. Its last version is kept as a revision of
+/// `into` and its slug redirects there. Both items must belong to the same
+/// library.
+pub fn merge_items(
+    conn: &mut PgConnection,
+    kind: ItemKind,
+    from: i32,
+    into: i32,
+) -> Result<(), ApiError> {
+    if from == into {
+        return Err(ApiError::validation("an item can't be merged into itself"));
+    }
+
+    let from_owner = item_owner(conn, kind, from)?.ok_or(ApiError::NotFound)?;
+    let into_owner = item_owner(conn, kind, into)?.ok_or(ApiError::NotFound)?;
+    if from_owner != into_owner {
+        return Err(ApiError::validation(
+            "items in different users' libraries can't be merged",
+        ));
+    }
+
+    conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        move_history(conn, kind, from, into)?;
+        repoint_relations(conn, kind, from, into)?;
+
+        let slug = match kind {
+            ItemKind::Books => merge_books(conn, from, into)?,
+            ItemKind::Games => merge_games(conn, from, into)?,
+            ItemKind::Projects => merge_projects(conn, from, into)?,
+            _ => return Err(diesel::result::Error::NotFound),
+        };
+
+        diesel::insert_into(slug_history::table)
+            .values((
+                slug_history::item_type.eq(kind.table_name()),
+                slug_history::slug.eq(slug),
+                slug_history::item_id.eq(into),
+            ))
+            .on_conflict((slug_history::item_type, slug_history::slug))
+            .do_update()
+            .set((
+                slug_history::item_id.eq(into),
+                slug_history::changed_at.eq(diesel::dsl::now),
+            ))
+            .execute(conn)?;
+
+        Ok(())
+    })?;
+
+    Ok(())
+}
+
+/// Appends `from`'s revisions, then its current version, to `into`'s history
+/// and hands over its status changes and old slugs.
+fn move_history(conn: &mut PgConnection, kind: ItemKind, from: i32, into: i32) -> QueryResult<()> {
+    let item_type = kind.table_name();
+
+    let offset = revisions::table
+        .filter(revisions::item_type.eq(item_type))
+        .filter(revisions::item_id.eq(into))
+        .select(diesel::dsl::max(revisions::revision))
+        .first::<Option<i32>>(conn)?
+        .unwrap_or(0);
+
+    diesel::update(
+        revisions::table
+            .filter(revisions::item_type.eq(item_type))
+            .filter(revisions::item_id.eq(from)),
+    )
+    .set((
+        revisions::item_id.eq(into),
+        revisions::revision.eq(revisions::revision + offset),
+    ))
+    .execute(conn)?;
+
+    diesel::sql_query(format!(
+        "INSERT INTO revisions (item_type, item_id, revision, data) \
+         SELECT $1, $3, COALESCE(( \
+             SELECT MAX(revision) FROM revisions WHERE item_type = $1 AND item_id = $3 \
+         ), 0) + 1, to_jsonb(item) \
+         FROM {item_type} AS item WHERE item.id = $2"
+    ))
+    .bind::<Text, _>(item_type)
+    .bind::<Integer, _>(from)
+    .bind::<Integer, _>(into)
+    .execute(conn)?;
+
+    diesel::update(
+        status_changes::table
+            .filter(status_changes::item_type.eq(item_type))
+            .filter(status_changes::item_id.eq(from)),
+    )
+    .set(status_changes::item_id.eq(into))
+    .execute(conn)?;
+
+    diesel::update(
+        slug_history::table
+            .filter(slug_history::item_type.eq(item_type))
+            .filter(slug_history::item_id.eq(from)),
+    )
+    .set(slug_history::item_id.eq(into))
+    .execute(conn)?;
+
+    Ok(())
+}
+
+/// `into`'s links followed by those of `from` it doesn't already have.
+fn merged_links(into: &Links, from: &Links) -> Links {
+    let mut links = into.0.clone();
+    for link in &from.0 {
+        if !links.iter().any(|existing| existing.url == link.url) {
+            links.push(link.clone());
+        }
+    }
+
+    Links(links)
+}
+
+/// `into`'s extra fields, or `from`'s if `into` has none.
+fn merged_extra(into: &Value, from: &Value) -> Value {
+    match into.as_object() {
+        Some(fields) if !fields.is_empty() => into.clone(),
+        _ => from.clone(),
+    }
+}
+
+/// When a book was last read, or `None` if it has no progress log.
+fn last_read(conn: &mut PgConnection, book_id: i32) -> QueryResult<Option<NaiveDateTime>> {
+    book_progress::table
+        .filter(book_progress::book_id.eq(book_id))
+        .select(diesel::dsl::max(book_progress::logged_at))
+        .first(conn)
+}
+
+/// Merges two live books and deletes `from`, returning its slug. The merged
+/// book keeps the reading position of whichever of the two was read last, and
+/// takes `from`'s series if it isn't in one.
+fn merge_books(conn: &mut PgConnection, from: i32, into: i32) -> QueryResult<String> {
+    let source = books::table
+        .find(from)
+        .filter(books::deleted_at.is_null())
+        .first::<Book>(conn)?;
+    let target = books::table
+        .find(into)
+        .filter(books::deleted_at.is_null())
+        .first::<Book>(conn)?;
+
+    let genres: Vec<&String> = target
+        .genres
+        .iter()
+        .chain(&source.genres)
+        .flatten()
+        .collect();
+    let tags: Vec<&String> = target.tags.iter().chain(&source.tags).flatten().collect();
+    let genre_tags = resolve_tags(conn, TagKind::Genre, &genres)?;
+    let tag_tags = resolve_tags(conn, TagKind::Tag, &tags)?;
+
+    diesel::update(books::table.find(into))
+        .set((
+            books::genres.eq(tag_names(&genre_tags)),
+            books::tags.eq(tag_names(&tag_tags)),
+            books::links.eq(merged_links(&target.links, &source.links)),
+            books::extra.eq(merged_extra(&target.extra, &source.extra)),
+        ))
+        .execute(conn)?;
+
+    link_book_tags(conn, into, TagKind::Genre, &genre_tags)?;
+    link_book_tags(conn, into, TagKind::Tag, &tag_tags)?;
+
+    let source_is_ahead = match (last_read(conn, from)?, last_read(conn, into)?) {
+        (Some(source_read), Some(target_read)) => source_read > target_read,
+        (Some(_), None) => true,
+        (None, _) => target.current_page.is_none() && target.current_chapter.is_none(),
+    };
+    if source_is_ahead {
+        diesel::update(books::table.find(into))
+            .set((
+                books::current_page.eq(source.current_page),
+                books::total_pages.eq(source.total_pages),
+                books::current_chapter.eq(source.current_chapter),
+                books::total_chapters.eq(source.total_chapters),
+            ))
+            .execute(conn)?;
+    }
+
+    diesel::update(reviews::table.filter(reviews::book_id.eq(from)))
+        .set(reviews::book_id.eq(into))
+        .execute(conn)?;
+
+    diesel::update(book_progress::table.filter(book_progress::book_id.eq(from)))
+        .set(book_progress::book_id.eq(into))
+        .execute(conn)?;
+
+    diesel::delete(books::table.find(from)).execute(conn)?;
+
+    // Only once `from` is gone, as two books can't hold the same volume.
+    if target.series_id.is_none() && source.series_id.is_some() {
+        diesel::update(books::table.find(into))
+            .set((
+                books::series_id.eq(source.series_id),
+                books::series_volume.eq(source.series_volume),
+            ))
+            .execute(conn)?;
+    }
+
+    Ok(source.slug)
+}
+
+/// Merges two live games and deletes `from`, returning its slug.
+fn merge_games(conn: &mut PgConnection, from: i32, into: i32) -> QueryResult<String> {
+    let source = games::table
+        .find(from)
+        .filter(games::deleted_at.is_null())
+        .first::<Game>(conn)?;
+    let target = games::table
+        .find(into)
+        .filter(games::deleted_at.is_null())
+        .first::<Game>(conn)?;
+
+    let genres: Vec<&String> = target
+        .genres
+        .iter()
+        .chain(&source.genres)
+        .flatten()
+        .collect();
+    let tags: Vec<&String> = target.tags.iter().chain(&source.tags).flatten().collect();
+    let genre_tags = resolve_tags(conn, TagKind::Genre, &genres)?;
+    let tag_tags = resolve_tags(conn, TagKind::Tag, &tags)?;
+
+    diesel::update(games::table.find(into))
+        .set((
+            games::genres.eq(tag_names(&genre_tags)),
+            games::tags.eq(tag_names(&tag_tags)),
+            games::links.eq(merged_links(&target.links, &source.links)),
+            games::extra.eq(merged_extra(&target.extra, &source.extra)),
+        ))
+        .execute(conn)?;
+
+    link_game_tags(conn, into, TagKind::Genre, &genre_tags)?;
+    link_game_tags(conn, into, TagKind::Tag, &tag_tags)?;
+
+    diesel::update(reviews::table.filter(reviews::game_id.eq(from)))
+        .set(reviews::game_id.eq(into))
+        .execute(conn)?;
+
+    diesel::delete(games::table.find(from)).execute(conn)?;
+
+    Ok(source.slug)
+}
+
+/// Merges two live projects and deletes `from`, returning its slug. Project
+/// tags aren't part of the shared taxonomy, so they're deduplicated by their
+/// normalized spelling.
+fn merge_projects(conn: &mut PgConnection, from: i32, into: i32) -> QueryResult<String> {
+    let source = projects::table
+        .find(from)
+        .filter(projects::deleted_at.is_null())
+        .first::<Project>(conn)?;
+    let target = projects::table
+        .find(into)
+        .filter(projects::deleted_at.is_null())
+        .first::<Project>(conn)?;
+
+    let mut tags: Vec<String> = Vec::new();
+    for tag in target.tags.iter().chain(&source.tags).flatten().flatten() {
+        if !tags.iter().any(|kept| normalize(kept) == normalize(tag)) {
+            tags.push(tag.clone());
+        }
+    }
+
+    diesel::update(projects::table.find(into))
+        .set((
+            projects::tags.eq(Some(tags)),
+            projects::links.eq(merged_links(&target.links, &source.links)),
+            projects::extra.eq(merged_extra(&target.extra, &source.extra)),
+        ))
+        .execute(conn)?;
+
+    diesel::delete(projects::table.find(from)).execute(conn)?;
+
+    Ok(source.slug)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MatchKind, SIMILARITY_THRESHOLD, match_kind, merge_items, normalize, similarity};
+    use crate::db::connect_db;
+    use crate::handlers::ItemKind;
+    use crate::models::{Book, NewBook};
+    use crate::schema::{accounts, books, series};
+    use diesel::prelude::*;
+    use serde_json::json;
+
+    #[test]
+    fn normalize_folds_case_punctuation_and_whitespace() {
+        assert_eq!(normalize("The Hobbit!"), "the hobbit");
+        assert_eq!(normalize("  the   HOBBIT "), "the hobbit");
+        assert_eq!(normalize("Harry Potter: Book #1"), "harry potter book 1");
+        assert_eq!(normalize("Mass-Effect\t2"), "mass effect 2");
+        assert_eq!(normalize("Pokémon"), "pokémon");
+        assert_eq!(normalize("?!..."), "");
+    }
+
+    #[test]
+    fn similarity_of_identical_and_disjoint_titles() {
+        assert_eq!(similarity("the hobbit", "the hobbit"), 1.0);
+        assert_eq!(similarity("abc", "xyz"), 0.0);
+        assert_eq!(similarity("", ""), 1.0);
+    }
+
+    #[test]
+    fn similarity_counts_repeated_bigrams_once_each() {
+        // "aaa" has the bigram "aa" twice, "aa" only once.
+        assert_eq!(similarity("aaa", "aa"), 2.0 / 3.0);
+    }
+
+    #[test]
+    fn one_character_titles() {
+        // A single character has no bigrams, so only an identical title
+        // matches, through the early return.
+        assert_eq!(similarity("a", "a"), 1.0);
+        assert_eq!(similarity("a", "b"), 0.0);
+        assert_eq!(similarity("a", "ab"), 0.0);
+        assert_eq!(similarity("", "a"), 0.0);
+    }
+
+    #[test]
+    fn threshold_boundary() {
+        // Four of the five bigrams on each side are shared: exactly 0.8.
+        let at = similarity("abcdef", "abcdeg");
+        assert_eq!(at, SIMILARITY_THRESHOLD);
+        assert_eq!(match_kind(at, false), Some(MatchKind::Similar));
+
+        // Seven of nine bigrams shared on one side, of ten on the other.
+        let below = similarity("abcdefghij", "abcdefghxyz");
+        assert!(below < SIMILARITY_THRESHOLD);
+        assert_eq!(match_kind(below, false), None);
+        assert_eq!(match_kind(below, true), None);
+    }
+
+    #[test]
+    fn exact_matches_need_the_same_creator() {
+        assert_eq!(match_kind(1.0, true), Some(MatchKind::Exact));
+        assert_eq!(match_kind(1.0, false), Some(MatchKind::Similar));
+    }
+
+    #[test]
+    #[ignore = "needs a migrated database at DATABASE_URL"]
+    fn merging_books_fills_in_series_and_extra() {
+        connect_db()
+            .unwrap()
+            .test_transaction::<_, diesel::result::Error, _>(|conn| {
+                let owner = diesel::insert_into(accounts::table)
+                    .values(accounts::handle.eq("merge-test"))
+                    .returning(accounts::id)
+                    .get_result::<i32>(conn)?;
+                let series_id = diesel::insert_into(series::table)
+                    .values(series::name.eq("Merge Test Series"))
+                    .returning(series::id)
+                    .get_result::<i32>(conn)?;
+
+                let mut insert_book = |title: &str, extra: serde_json::Value| {
+                    let new_book = json!({
+                        "title": title, "author": "Nobody", "genres": [], "tags": [],
+                        "rating": 3, "status": "in-progress", "description": "",
+                        "my_thoughts": "", "cover_image": "https://example.com/cover.jpg",
+                        "explicit": false, "extra": extra,
+                    });
+                    diesel::insert_into(books::table)
+                        .values((
+                            &serde_json::from_value::<NewBook>(new_book).unwrap(),
+                            books::owner_id.eq(owner),
+                        ))
+                        .get_result::<Book>(conn)
+                };
+                let source = insert_book("Merge Test", json!({"edition": "first"}))?;
+                let target = insert_book("Merge Test (Copy)", json!({}))?;
+
+                diesel::update(books::table.find(source.id))
+                    .set((books::series_id.eq(series_id), books::series_volume.eq(2)))
+                    .execute(conn)?;
+
+                merge_items(conn, ItemKind::Books, source.id, target.id).unwrap();
+
+                let merged = books::table.find(target.id).first::<Book>(conn)?;
+                assert_eq!(merged.series_id, Some(series_id));
+                assert_eq!(merged.series_volume, Some(2));
+                assert_eq!(merged.extra, json!({"edition": "first"}));
+                Ok(())
+            });
+    }
+}
@@ -0,0 +1,163 @@
+use crate::errors::ApiError;
+use crate::handlers::ItemKind;
+use crate::handlers::identifiers::lock_item;
+use diesel::PgConnection;
+use rocket::fairing::AdHoc;
+use rocket::http::{ContentType, Method, Status};
+use rocket::request::{FromRequest, Outcome, Request};
+use rocket::response::{self, Responder, Response};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::convert::Infallible;
+use std::io::Cursor;
+
+/// The entity tag of a JSON body: a hash of the body itself, so anything a
+/// client would see change, like a new review or a different rating scale,
+/// changes the tag along with the item's own fields.
+fn etag(json: &[u8]) -> String {
+    format!("\"{}\"", hex::encode(&Sha256::digest(json)[..16]))
+}
+
+/// Whether an `If-Match` or `If-None-Match` value names `etag`. `*` names any
+/// version. Weak tags (`W/"..."`) only count under the weak comparison that
+/// `If-None-Match` uses.
+fn names(header: &str, etag: &str, weak: bool) -> bool {
+    header
+        .split(',')
+        .map(str::trim)
+        .any(|tag| tag == "*" || tag == etag || (weak && tag.strip_prefix("W/") == Some(etag)))
+}
+
+/// A JSON response carrying its `ETag`.
+pub struct Tagged<T>(T);
+
+impl<T> Tagged<T> {
+    pub fn new(body: T) -> Self {
+        Self(body)
+    }
+}
+
+impl<'r, T: Serialize> Responder<'r, 'static> for Tagged<T> {
+    fn respond_to(self, _request: &'r Request<'_>) -> response::Result<'static> {
+        let json = serde_json::to_vec(&self.0).map_err(|e| {
+            eprintln!("❌ Failed to serialize a response: {}", e);
+            Status::InternalServerError
+        })?;
+
+        Response::build()
+            .header(ContentType::JSON)
+            .raw_header("ETag", etag(&json))
+            .sized_body(json.len(), Cursor::new(json))
+            .ok()
+    }
+}
+
+/// A `201 Created` response carrying the inserted row, a `Location` header
+/// pointing at where it can be read back and its `ETag`.
+pub struct Created<T> {
+    location: String,
+    body: Tagged<T>,
+}
+
+impl<T> Created<T> {
+    pub fn new(location: impl Into<String>, body: T) -> Self {
+        Self {
+            location: location.into(),
+            body: Tagged::new(body),
+        }
+    }
+}
+
+impl<'r, T: Serialize> Responder<'r, 'static> for Created<T> {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        Response::build_from(self.body.respond_to(request)?)
+            .status(Status::Created)
+            .raw_header("Location", self.location)
+            .ok()
+    }
+}
+
+/// The `If-Match` header of a write. Without one the write goes ahead as
+/// before; with one it only does if the item hasn't changed since the client
+/// read it.
+pub struct IfMatch(Option<String>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for IfMatch {
+    type Error = Infallible;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(IfMatch(
+            request.headers().get_one("If-Match").map(str::to_string),
+        ))
+    }
+}
+
+impl IfMatch {
+    /// Fails with 412 if `If-Match` was sent and doesn't name the item as
+    /// `current` loads it, i.e. the body a `GET` would answer with right now.
+    ///
+    /// Call it inside the transaction that makes the write: the item's row
+    /// is locked before it's read, so a concurrent write holding the same
+    /// `ETag` waits for this one and then fails the check.
+    pub fn check<T: Serialize>(
+        &self,
+        conn: &mut PgConnection,
+        kind: ItemKind,
+        item_id: i32,
+        current: fn(&mut PgConnection, i32) -> Result<T, ApiError>,
+    ) -> Result<(), ApiError> {
+        self.check_with(conn, |conn| {
+            if !lock_item(conn, kind, item_id)? {
+                return Err(ApiError::NotFound);
+            }
+            current(conn, item_id)
+        })
+    }
+
+    /// `check` for rows that aren't items, like series or collection items.
+    /// `current` has to lock the row itself before loading it the way a
+    /// `GET` answers with it.
+    pub fn check_with<T: Serialize>(
+        &self,
+        conn: &mut PgConnection,
+        current: impl FnOnce(&mut PgConnection) -> Result<T, ApiError>,
+    ) -> Result<(), ApiError> {
+        let Some(header) = &self.0 else {
+            return Ok(());
+        };
+
+        let json = serde_json::to_vec(&current(conn)?).map_err(|_| ApiError::Internal)?;
+        if names(header, &etag(&json), false) {
+            Ok(())
+        } else {
+            Err(ApiError::PreconditionFailed)
+        }
+    }
+}
+
+/// Turns a successful `GET` into `304 Not Modified` when its `If-None-Match`
+/// names the `ETag` the handler answered with.
+pub fn conditional_get() -> AdHoc {
+    AdHoc::on_response("Conditional GET", |request, response| {
+        Box::pin(async move {
+            if request.method() != Method::Get || response.status() != Status::Ok {
+                return;
+            }
+
+            let Some(if_none_match) = request.headers().get_one("If-None-Match") else {
+                return;
+            };
+            let unchanged = response
+                .headers()
+                .get_one("ETag")
+                .is_some_and(|etag| names(if_none_match, etag, true));
+
+            if unchanged {
+                response.set_status(Status::NotModified);
+                response.set_sized_body(0, Cursor::new(""));
+                response.remove_header("Content-Type");
+            }
+        })
+    })
+}
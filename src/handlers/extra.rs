@@ -0,0 +1,148 @@
+use crate::auth::AdminUser;
+use crate::db::connect_db;
+use crate::errors::ApiError;
+use crate::handlers::ItemKind;
+use crate::models::{ExtraSchema, NewExtraSchema};
+use crate::schema::{books, extra_schemas, games, projects};
+use diesel::prelude::*;
+use rocket::serde::json::Json;
+use rocket::{Route, delete, get, put, routes};
+use serde_json::Value;
+
+/// Only books, games and projects carry `extra` fields.
+fn extra_kind(kind: ItemKind) -> Result<ItemKind, ApiError> {
+    match kind {
+        ItemKind::Books | ItemKind::Games | ItemKind::Projects => Ok(kind),
+        _ => Err(ApiError::NotFound),
+    }
+}
+
+fn load_schema(conn: &mut PgConnection, kind: ItemKind) -> QueryResult<Option<Value>> {
+    extra_schemas::table
+        .find(kind.table_name())
+        .select(extra_schemas::schema)
+        .first::<Value>(conn)
+        .optional()
+}
+
+/// Every stored `extra` object of a kind, trashed items included so they can
+/// still be restored once a schema is in place.
+fn stored_extras(conn: &mut PgConnection, kind: ItemKind) -> QueryResult<Vec<Value>> {
+    match kind {
+        ItemKind::Books => books::table.select(books::extra).load(conn),
+        ItemKind::Games => games::table.select(games::extra).load(conn),
+        ItemKind::Projects => projects::table.select(projects::extra).load(conn),
+        _ => Ok(Vec::new()),
+    }
+}
+
+/// Checks an item's `extra` object against its resource's schema, if one is
+/// set. Returns 422 when it doesn't conform.
+pub fn validate_extra(
+    conn: &mut PgConnection,
+    kind: ItemKind,
+    extra: &Value,
+) -> Result<(), ApiError> {
+    if !extra.is_object() {
+        return Err(ApiError::validation("extra must be an object"));
+    }
+
+    let Some(schema) = load_schema(conn, kind)? else {
+        return Ok(());
+    };
+    let validator = jsonschema::validator_for(&schema).map_err(|_| ApiError::Internal)?;
+
+    validator
+        .validate(extra)
+        .map_err(|e| ApiError::validation(format!("extra does not match the schema: {e}")))
+}
+
+#[get("/")]
+pub fn get_extra_schemas() -> Result<Json<Vec<ExtraSchema>>, ApiError> {
+    let mut conn = connect_db()?;
+
+    extra_schemas::table
+        .order(extra_schemas::item_type.asc())
+        .load::<ExtraSchema>(&mut conn)
+        .map(Json)
+        .map_err(ApiError::from)
+}
+
+#[get("/<kind>")]
+pub fn get_extra_schema(kind: ItemKind) -> Result<Json<ExtraSchema>, ApiError> {
+    let kind = extra_kind(kind)?;
+    let mut conn = connect_db()?;
+
+    extra_schemas::table
+        .find(kind.table_name())
+        .first::<ExtraSchema>(&mut conn)
+        .map(Json)
+        .map_err(ApiError::from)
+}
+
+/// Sets the JSON Schema for a resource's `extra` fields. The schema itself
+/// must be valid (422), and every item already stored must satisfy it (409).
+#[put("/<kind>", format = "json", data = "<schema>")]
+pub fn put_extra_schema(
+    _admin: AdminUser,
+    kind: ItemKind,
+    schema: Json<Value>,
+) -> Result<Json<ExtraSchema>, ApiError> {
+    let kind = extra_kind(kind)?;
+    let schema = schema.into_inner();
+    let validator = jsonschema::validator_for(&schema)
+        .map_err(|e| ApiError::validation(format!("invalid schema: {e}")))?;
+    let mut conn = connect_db()?;
+
+    conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        let saved = diesel::insert_into(extra_schemas::table)
+            .values(&NewExtraSchema {
+                item_type: kind.table_name(),
+                schema: &schema,
+            })
+            .on_conflict(extra_schemas::item_type)
+            .do_update()
+            .set(extra_schemas::schema.eq(&schema))
+            .get_result::<ExtraSchema>(conn)?;
+
+        if !stored_extras(conn, kind)?
+            .iter()
+            .all(|extra| validator.is_valid(extra))
+        {
+            return Err(diesel::result::Error::RollbackTransaction);
+        }
+
+        Ok(saved)
+    })
+    .map(Json)
+    .map_err(|e| match e {
+        diesel::result::Error::RollbackTransaction => {
+            ApiError::conflict("existing extra fields do not match the schema")
+        }
+        e => ApiError::from(e),
+    })
+}
+
+/// Removes a resource's schema, after which any object is accepted again.
+#[delete("/<kind>")]
+pub fn delete_extra_schema(
+    _admin: AdminUser,
+    kind: ItemKind,
+) -> Result<Json<ExtraSchema>, ApiError> {
+    let kind = extra_kind(kind)?;
+    let mut conn = connect_db()?;
+
+    diesel::delete(extra_schemas::table.find(kind.table_name()))
+        .get_result::<ExtraSchema>(&mut conn)
+        .map(Json)
+        .map_err(ApiError::from)
+}
+
+pub fn extra_routes() -> Vec<Route> {
+    routes![
+        get_extra_schemas,
+        get_extra_schema,
+        put_extra_schema,
+        delete_extra_schema,
+    ]
+}
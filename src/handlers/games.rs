@@ -1,11 +1,23 @@
-use crate::auth::User;
+use crate::auth::{AdminUser, User};
 use crate::db::connect_db;
-use crate::models::{Game, NewGame, UpdateGame};
+use crate::errors::ApiError;
+use crate::handlers::accounts::require_owner;
+use crate::handlers::duplicates::{CreateOptions, MergeItems, check_duplicates, merge_items};
+use crate::handlers::etags::{Created, IfMatch, Tagged};
+use crate::handlers::extra::validate_extra;
+use crate::handlers::identifiers::{ItemRef, Located, Resolved, resolve_ref};
+use crate::handlers::ratings::rating_scale;
+use crate::handlers::reviews::{ReviewSummary, game_review_summaries};
+use crate::handlers::tags::{TagKind, link_game_tags, resolve_tags, tag_names};
+use crate::handlers::{ItemKind, parse_timestamp};
+use crate::models::{Game, LinkKind, NewGame, Rating, UpdateGame};
 use diesel::prelude::*;
+use rocket::Either;
 use rocket::form::FromForm;
 use rocket::serde::{Deserialize, Serialize, json::Json};
-use rocket::{delete, get, http::Status, patch, post, put, routes};
+use rocket::{delete, get, patch, post, put, routes};
 use std::collections::HashMap;
+use validator::Validate;
 
 #[derive(FromForm, Debug)]
 pub struct GameQuery {
@@ -23,11 +35,23 @@ pub struct GameQuery {
     #[field(name = "exactProgress")]
     exact_progress: Option<i32>,
     #[field(name = "minRating")]
-    min_rating: Option<i32>,
+    min_rating: Option<f64>,
     #[field(name = "maxRating")]
-    max_rating: Option<i32>,
+    max_rating: Option<f64>,
     #[field(name = "exactRating")]
-    exact_rating: Option<i32>,
+    exact_rating: Option<f64>,
+    #[field(name = "createdAfter")]
+    created_after: Option<String>,
+    #[field(name = "updatedSince")]
+    updated_since: Option<String>,
+    #[field(name = "linkKind")]
+    link_kind: Option<String>,
+    /// Exact matches on extra fields, e.g. `extra.platform=switch`. Array
+    /// fields match any element.
+    extra: Option<HashMap<String, String>>,
+    /// Limits results to one account's library, as `/users/<handle>/...` does.
+    #[field(name = "ownerId")]
+    pub(crate) owner_id: Option<i32>,
     sort: Option<String>,
 }
 
@@ -54,12 +78,97 @@ pub struct ApiResponse {
     count: Option<usize>,
 }
 
+/// A game along with the figures derived from its related rows.
+#[derive(Serialize)]
+pub struct GameResponse {
+    #[serde(flatten)]
+    game: Game,
+    normalized_rating: f64,
+    reviews: ReviewSummary,
+}
+
+fn game_responses(conn: &mut PgConnection, loaded: Vec<Game>) -> QueryResult<Vec<GameResponse>> {
+    let ids: Vec<i32> = loaded.iter().map(|game| game.id).collect();
+    let summaries = game_review_summaries(conn, &ids)?;
+    let scale = rating_scale(conn, ItemKind::Games)?;
+
+    Ok(loaded
+        .into_iter()
+        .map(|game| GameResponse {
+            normalized_rating: scale.normalize(game.rating),
+            reviews: summaries.get(&game.id).copied().unwrap_or_default(),
+            game,
+        })
+        .collect())
+}
+
+fn game_response(conn: &mut PgConnection, game: Game) -> QueryResult<GameResponse> {
+    Ok(game_responses(conn, vec![game])?.remove(0))
+}
+
+/// A live game as `GET /games/<id>` returns it.
+fn current_game(conn: &mut PgConnection, game_id: i32) -> Result<GameResponse, ApiError> {
+    use crate::schema::games::dsl::*;
+
+    let game = games
+        .filter(id.eq(&game_id))
+        .filter(deleted_at.is_null())
+        .first::<Game>(conn)?;
+
+    Ok(game_response(conn, game)?)
+}
+
+/// Applies an update to a live game, canonicalizing any new genres and tags
+/// against the shared taxonomy.
+pub fn apply_game_update(
+    conn: &mut PgConnection,
+    game_id: i32,
+    mut changes: UpdateGame,
+) -> QueryResult<Game> {
+    use crate::schema::games::dsl::*;
+
+    conn.transaction(|conn| {
+        let genre_tags = match &changes.genres {
+            Some(names) => Some(resolve_tags(conn, TagKind::Genre, names)?),
+            None => None,
+        };
+        let tag_tags = match &changes.tags {
+            Some(names) => Some(resolve_tags(conn, TagKind::Tag, names)?),
+            None => None,
+        };
+
+        if let Some(resolved) = &genre_tags {
+            changes.genres = Some(tag_names(resolved));
+        }
+        if let Some(resolved) = &tag_tags {
+            changes.tags = Some(tag_names(resolved));
+        }
+
+        let game = diesel::update(games.filter(id.eq(game_id)).filter(deleted_at.is_null()))
+            .set(&changes)
+            .get_result::<Game>(conn)?;
+
+        if let Some(resolved) = &genre_tags {
+            link_game_tags(conn, game.id, TagKind::Genre, resolved)?;
+        }
+        if let Some(resolved) = &tag_tags {
+            link_game_tags(conn, game.id, TagKind::Tag, resolved)?;
+        }
+
+        Ok(game)
+    })
+}
+
 #[get("/search?<query..>")]
-pub fn get_games(query: GameQuery) -> Result<Json<Vec<Game>>, Status> {
+pub fn get_games(query: GameQuery) -> Result<Json<Vec<GameResponse>>, ApiError> {
     use crate::schema::games::dsl::*;
 
-    let mut conn = connect_db();
-    let mut game_query = games.into_boxed();
+    let mut conn = connect_db()?;
+    let mut game_query = games.filter(deleted_at.is_null()).into_boxed();
+
+    if let Some(owner_filter) = query.owner_id {
+        game_query = game_query.filter(owner_id.eq(owner_filter));
+    }
 
     if let Some(title_filter) = &query.title {
         game_query = game_query.filter(title.ilike(format!("%{}%", title_filter)));
@@ -90,15 +199,15 @@ pub fn get_games(query: GameQuery) -> Result<Json<Vec<Game>>, Status> {
     }
 
     if let Some(min_rating_filter) = query.min_rating {
-        game_query = game_query.filter(rating.ge(min_rating_filter));
+        game_query = game_query.filter(rating.ge(Rating(min_rating_filter)));
     }
 
     if let Some(max_rating_filter) = query.max_rating {
-        game_query = game_query.filter(rating.le(max_rating_filter));
+        game_query = game_query.filter(rating.le(Rating(max_rating_filter)));
     }
 
     if let Some(exact_rating_filter) = query.exact_rating {
-        game_query = game_query.filter(rating.eq(exact_rating_filter));
+        game_query = game_query.filter(rating.eq(Rating(exact_rating_filter)));
     }
 
     if let Some(min_progress_filter) = query.min_progress {
@@ -113,18 +222,48 @@ pub fn get_games(query: GameQuery) -> Result<Json<Vec<Game>>, Status> {
         game_query = game_query.filter(percent.eq(exact_progress_filter));
     }
 
+    if let Some(created_after_filter) = &query.created_after {
+        let timestamp = parse_timestamp(created_after_filter)
+            .ok_or_else(|| ApiError::bad_request("invalid createdAfter timestamp"))?;
+        game_query = game_query.filter(created_at.gt(timestamp));
+    }
+
+    if let Some(updated_since_filter) = &query.updated_since {
+        let timestamp = parse_timestamp(updated_since_filter)
+            .ok_or_else(|| ApiError::bad_request("invalid updatedSince timestamp"))?;
+        game_query = game_query.filter(updated_at.ge(timestamp));
+    }
+
+    if let Some(link_kind_filter) = &query.link_kind {
+        let kind = link_kind_filter
+            .parse::<LinkKind>()
+            .map_err(|_| ApiError::bad_request(format!("unknown link kind: {link_kind_filter}")))?;
+        game_query = game_query.filter(links.contains(serde_json::json!([{ "kind": kind }])));
+    }
+
+    if let Some(extra_filters) = &query.extra {
+        for (key, value) in extra_filters {
+            game_query = game_query.filter(
+                extra
+                    .retrieve_as_text(key.clone())
+                    .eq(value.clone())
+                    .or(extra.contains(serde_json::json!({ key: [value] }))),
+            );
+        }
+    }
+
     if let Some(sort_by) = &query.sort {
         match sort_by.as_str() {
             "title" => game_query = game_query.order(title.asc()),
             "author" => game_query = game_query.order(developer.asc()),
             "rating" => game_query = game_query.order(rating.desc()),
+            "created" => game_query = game_query.order(created_at.desc()),
+            "updated" => game_query = game_query.order(updated_at.desc()),
             _ => {}
         }
     }
 
-    let results = game_query
-        .load::<Game>(&mut conn)
-        .map_err(|_| Status::InternalServerError)?;
+    let results = game_query.load::<Game>(&mut conn)?;
 
     let mut filtered_results = results;
 
@@ -152,111 +291,202 @@ pub fn get_games(query: GameQuery) -> Result<Json<Vec<Game>>, Status> {
         });
     }
 
-    Ok(Json(filtered_results))
+    game_responses(&mut conn, filtered_results)
+        .map(Json)
+        .map_err(ApiError::from)
 }
 
-#[get("/<game_id>")]
-pub fn get_game_by_id(game_id: i32) -> Result<Json<Game>, Status> {
-    use crate::schema::games::dsl::*;
+#[get("/<game_ref>")]
+pub fn get_game_by_id(game_ref: ItemRef) -> Result<Located<GameResponse>, ApiError> {
+    let mut conn = connect_db()?;
 
-    let mut conn = connect_db();
+    let game_id = match resolve_ref(&mut conn, ItemKind::Games, &game_ref)? {
+        Resolved::Found(found) => found,
+        Resolved::Moved(redirect) => return Ok(Either::Right(*redirect)),
+    };
 
-    let game = games
-        .filter(id.eq(&game_id))
-        .first::<Game>(&mut conn)
-        .map_err(|_| Status::NotFound)?;
+    let response = current_game(&mut conn, game_id)?;
 
-    Ok(Json(game))
+    Ok(Either::Left(Tagged::new(response)))
 }
 
-#[post("/", format = "json", data = "<new_game>")]
-pub fn post_games(_user: User, new_game: Json<NewGame>) -> Result<Json<Game>, Status> {
-    _user.require_admin().expect("User is not admin");
+#[post("/?<options..>", format = "json", data = "<new_game>")]
+pub fn post_games(
+    user: User,
+    options: CreateOptions,
+    new_game: Json<NewGame>,
+) -> Result<Created<GameResponse>, ApiError> {
     use crate::schema::games;
 
-    let mut conn = connect_db();
-
-    let created_game = diesel::insert_into(games::table)
-        .values(&new_game.into_inner())
-        .get_result::<Game>(&mut conn)
-        .map_err(|_| Status::InternalServerError)?;
-
-    Ok(Json(created_game))
+    let mut conn = connect_db()?;
+    let new_game = new_game.into_inner();
+    new_game.validate()?;
+    if let Some(extra) = &new_game.extra {
+        validate_extra(&mut conn, ItemKind::Games, extra)?;
+    }
+    check_duplicates(
+        &mut conn,
+        ItemKind::Games,
+        user.account_id(),
+        new_game.title,
+        new_game.developer,
+        &options,
+    )?;
+
+    let created_game = conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        let genre_tags = resolve_tags(conn, TagKind::Genre, &new_game.genres)?;
+        let tag_tags = resolve_tags(conn, TagKind::Tag, &new_game.tags)?;
+        let genre_names = tag_names(&genre_tags);
+        let tag_list = tag_names(&tag_tags);
+
+        let game = diesel::insert_into(games::table)
+            .values((
+                &NewGame {
+                    genres: genre_names.iter().map(String::as_str).collect(),
+                    tags: tag_list.iter().map(String::as_str).collect(),
+                    ..new_game
+                },
+                games::owner_id.eq(user.account_id()),
+            ))
+            .get_result::<Game>(conn)?;
+
+        link_game_tags(conn, game.id, TagKind::Genre, &genre_tags)?;
+        link_game_tags(conn, game.id, TagKind::Tag, &tag_tags)?;
+
+        Ok(game)
+    })?;
+
+    let location = ItemKind::Games.location(created_game.id);
+    let response = game_response(&mut conn, created_game)?;
+
+    Ok(Created::new(location, response))
 }
 
 #[put("/<game_id>", format = "json", data = "<updated_game>")]
 pub fn update_game(
-    _user: User,
+    user: User,
+    if_match: IfMatch,
     game_id: i32,
     updated_game: Json<UpdateGame>,
-) -> Result<Json<Game>, Status> {
-    _user.require_admin().expect("User is not admin");
-    use crate::schema::games::dsl::*;
-
-    let mut conn = connect_db();
+) -> Result<Tagged<GameResponse>, ApiError> {
+    let mut conn = connect_db()?;
+    require_owner(&mut conn, &user, ItemKind::Games, game_id)?;
+
+    let changes = updated_game.into_inner();
+    changes.validate()?;
+    if let Some(extra) = &changes.extra {
+        validate_extra(&mut conn, ItemKind::Games, extra)?;
+    }
 
-    let game = diesel::update(games.filter(id.eq(&game_id)))
-        .set(&updated_game.into_inner())
-        .get_result::<Game>(&mut conn)
-        .map_err(|_| Status::NotFound)?;
+    let response = conn.transaction::<_, ApiError, _>(|conn| {
+        if_match.check(conn, ItemKind::Games, game_id, current_game)?;
+        let game = apply_game_update(conn, game_id, changes)?;
+        Ok(game_response(conn, game)?)
+    })?;
 
-    Ok(Json(game))
+    Ok(Tagged::new(response))
 }
 
 #[patch("/<game_id>", format = "json", data = "<patch_data>")]
 pub fn patch_game(
-    _user: User,
+    user: User,
+    if_match: IfMatch,
     game_id: i32,
     patch_data: Json<UpdateGame>,
-) -> Result<Json<Game>, Status> {
-    _user.require_admin().expect("User is not admin");
-    use crate::schema::games::dsl::*;
-
-    let mut conn = connect_db();
+) -> Result<Tagged<GameResponse>, ApiError> {
+    let mut conn = connect_db()?;
+    require_owner(&mut conn, &user, ItemKind::Games, game_id)?;
+
+    let changes = patch_data.into_inner();
+    changes.validate()?;
+    if let Some(extra) = &changes.extra {
+        validate_extra(&mut conn, ItemKind::Games, extra)?;
+    }
 
-    let game = diesel::update(games.filter(id.eq(&game_id)))
-        .set(&patch_data.into_inner())
-        .get_result::<Game>(&mut conn)
-        .map_err(|_| Status::NotFound)?;
+    let response = conn.transaction::<_, ApiError, _>(|conn| {
+        if_match.check(conn, ItemKind::Games, game_id, current_game)?;
+        let game = apply_game_update(conn, game_id, changes)?;
+        Ok(game_response(conn, game)?)
+    })?;
 
-    Ok(Json(game))
+    Ok(Tagged::new(response))
 }
 
 #[delete("/<game_id>")]
-pub fn delete_game(_user: User, game_id: i32) -> Result<Json<ApiResponse>, Status> {
-    _user.require_admin().expect("User is not admin");
+pub fn delete_game(
+    user: User,
+    if_match: IfMatch,
+    game_id: i32,
+) -> Result<Json<ApiResponse>, ApiError> {
     use crate::schema::games::dsl::*;
 
-    let mut conn = connect_db();
+    let mut conn = connect_db()?;
+    require_owner(&mut conn, &user, ItemKind::Games, game_id)?;
 
-    let rows_deleted = diesel::delete(games.filter(id.eq(&game_id)))
-        .execute(&mut conn)
-        .map_err(|_| Status::InternalServerError)?;
+    let rows_deleted = conn.transaction::<_, ApiError, _>(|conn| {
+        if_match.check(conn, ItemKind::Games, game_id, current_game)?;
+        Ok(
+            diesel::update(games.filter(id.eq(&game_id)).filter(deleted_at.is_null()))
+                .set(deleted_at.eq(diesel::dsl::now.nullable()))
+                .execute(conn)?,
+        )
+    })?;
 
     if rows_deleted > 0 {
         Ok(Json(ApiResponse {
-            message: "game deleted".to_string(),
+            message: "game moved to trash".to_string(),
             deleted: None,
             updated: None,
             count: None,
         }))
     } else {
-        Err(Status::NotFound)
+        Err(ApiError::NotFound)
     }
 }
 
+#[post("/<game_id>/restore")]
+pub fn restore_game(user: User, game_id: i32) -> Result<Json<Game>, ApiError> {
+    use crate::schema::games::dsl::*;
+
+    let mut conn = connect_db()?;
+    require_owner(&mut conn, &user, ItemKind::Games, game_id)?;
+
+    let game = diesel::update(
+        games
+            .filter(id.eq(&game_id))
+            .filter(deleted_at.is_not_null()),
+    )
+    .set(deleted_at.eq(None::<chrono::NaiveDateTime>))
+    .get_result::<Game>(&mut conn)?;
+
+    Ok(Json(game))
+}
+
+/// Folds a duplicate game into `merge.into`, which is returned.
+#[post("/<game_id>/merge", format = "json", data = "<merge>")]
+pub fn merge_game(
+    _admin: AdminUser,
+    game_id: i32,
+    merge: Json<MergeItems>,
+) -> Result<Tagged<GameResponse>, ApiError> {
+    let mut conn = connect_db()?;
+    merge_items(&mut conn, ItemKind::Games, game_id, merge.into)?;
+
+    Ok(Tagged::new(current_game(&mut conn, merge.into)?))
+}
+
 #[delete("/bulk", format = "json", data = "<filter>")]
 pub fn bulk_delete_games(
     _user: User,
     filter: Json<BulkDeleteFilter>,
-) -> Result<Json<ApiResponse>, Status> {
-    _user.require_admin().expect("User is not admin");
+) -> Result<Json<ApiResponse>, ApiError> {
+    _user.require_admin()?;
     use crate::schema::games::dsl::*;
 
-    let mut conn = connect_db();
+    let mut conn = connect_db()?;
     let filter = filter.into_inner();
 
-    let mut delete_query = diesel::delete(games).into_boxed();
+    let mut delete_query = diesel::update(games.filter(deleted_at.is_null())).into_boxed();
 
     if let Some(developer_filter) = &filter.developer {
         delete_query = delete_query.filter(developer.eq(developer_filter));
@@ -267,8 +497,8 @@ pub fn bulk_delete_games(
     }
 
     let deleted_count = delete_query
-        .execute(&mut conn)
-        .map_err(|_| Status::InternalServerError)? as i32;
+        .set(deleted_at.eq(diesel::dsl::now.nullable()))
+        .execute(&mut conn)? as i32;
 
     Ok(Json(ApiResponse {
         message: "bulk delete complete".to_string(),
@@ -282,14 +512,14 @@ pub fn bulk_delete_games(
 pub fn bulk_update_games(
     _user: User,
     payload: Json<BulkUpdatePayload>,
-) -> Result<Json<ApiResponse>, Status> {
-    _user.require_admin().expect("User is not admin");
+) -> Result<Json<ApiResponse>, ApiError> {
+    _user.require_admin()?;
     use crate::schema::games::dsl::*;
 
-    let mut conn = connect_db();
+    let mut conn = connect_db()?;
     let payload = payload.into_inner();
 
-    let mut update_query = diesel::update(games).into_boxed();
+    let mut update_query = diesel::update(games.filter(deleted_at.is_null())).into_boxed();
 
     if let Some(developer_filter) = payload.filter.get("developer") {
         update_query = update_query.filter(developer.eq(developer_filter));
@@ -313,6 +543,8 @@ pub fn bulk_update_games(
         explicit: None,
         percent: None,
         bad: None,
+        slug: None,
+        extra: None,
     };
 
     if let Some(new_status) = payload.update.get("status")
@@ -324,13 +556,12 @@ pub fn bulk_update_games(
     if let Some(new_rating) = payload.update.get("rating")
         && let Some(rating_num) = new_rating.as_f64()
     {
-        update_game.rating = Some(rating_num as i32);
+        update_game.rating = Some(Rating(rating_num));
     }
 
-    let updated_count = update_query
-        .set(&update_game)
-        .execute(&mut conn)
-        .map_err(|_| Status::InternalServerError)? as i32;
+    update_game.validate()?;
+
+    let updated_count = update_query.set(&update_game).execute(&mut conn)? as i32;
 
     Ok(Json(ApiResponse {
         message: "bulk update complete".to_string(),
@@ -348,6 +579,8 @@ pub fn games_routes() -> Vec<rocket::Route> {
         update_game,
         patch_game,
         delete_game,
+        restore_game,
+        merge_game,
         bulk_delete_games,
         bulk_update_games
     ]
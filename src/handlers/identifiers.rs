@@ -0,0 +1,192 @@
+use crate::errors::ApiError;
+use crate::handlers::ItemKind;
+use crate::handlers::etags::Tagged;
+use crate::schema::slug_history;
+use diesel::prelude::*;
+use rocket::Either;
+use rocket::request::FromParam;
+use rocket::response::Redirect;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// An item as addressed in a URL: by numeric id, UUID or slug.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ItemRef {
+    Id(i32),
+    Uuid(Uuid),
+    Slug(String),
+}
+
+impl<'a> FromParam<'a> for ItemRef {
+    type Error = &'a str;
+
+    fn from_param(param: &'a str) -> Result<Self, Self::Error> {
+        if let Ok(id) = param.parse::<i32>() {
+            return Ok(ItemRef::Id(id));
+        }
+
+        if let Ok(uuid) = Uuid::parse_str(param) {
+            return Ok(ItemRef::Uuid(uuid));
+        }
+
+        let is_slug = !param.is_empty()
+            && param
+                .chars()
+                .all(|c| c == '-' || (c.is_alphanumeric() && !c.is_uppercase()));
+
+        if is_slug {
+            Ok(ItemRef::Slug(param.to_string()))
+        } else {
+            Err(param)
+        }
+    }
+}
+
+/// Where an `ItemRef` points.
+pub enum Resolved {
+    Found(i32),
+    /// The slug used to belong to this item; redirect to its current one.
+    Moved(Box<Redirect>),
+}
+
+/// A lookup response: the item with its `ETag`, or a permanent redirect when
+/// it was requested by a slug that has since changed.
+pub type Located<T> = Either<Tagged<T>, Redirect>;
+
+/// Runs `$query` with `$table` bound to the schema module for `$kind`.
+macro_rules! for_kind {
+    ($kind:expr, $table:ident => $query:expr) => {
+        match $kind {
+            ItemKind::Albums => {
+                use crate::schema::albums as $table;
+                $query
+            }
+            ItemKind::Books => {
+                use crate::schema::books as $table;
+                $query
+            }
+            ItemKind::Games => {
+                use crate::schema::games as $table;
+                $query
+            }
+            ItemKind::Movies => {
+                use crate::schema::movies as $table;
+                $query
+            }
+            ItemKind::Shows => {
+                use crate::schema::shows as $table;
+                $query
+            }
+            ItemKind::Projects => {
+                use crate::schema::projects as $table;
+                $query
+            }
+            ItemKind::Reviews => {
+                use crate::schema::reviews as $table;
+                $query
+            }
+            ItemKind::Wplace => {
+                use crate::schema::wplace as $table;
+                $query
+            }
+        }
+    };
+}
+
+fn find_by_uuid(conn: &mut PgConnection, kind: ItemKind, value: Uuid) -> QueryResult<Option<i32>> {
+    for_kind!(kind, items => items::table
+        .filter(items::uuid.eq(value))
+        .filter(items::deleted_at.is_null())
+        .select(items::id)
+        .first::<i32>(conn)
+        .optional())
+}
+
+fn find_by_slug(conn: &mut PgConnection, kind: ItemKind, value: &str) -> QueryResult<Option<i32>> {
+    for_kind!(kind, items => items::table
+        .filter(items::slug.eq(value))
+        .filter(items::deleted_at.is_null())
+        .select(items::id)
+        .first::<i32>(conn)
+        .optional())
+}
+
+fn current_slug(
+    conn: &mut PgConnection,
+    kind: ItemKind,
+    item_id: i32,
+) -> QueryResult<Option<String>> {
+    for_kind!(kind, items => items::table
+        .find(item_id)
+        .filter(items::deleted_at.is_null())
+        .select(items::slug)
+        .first::<String>(conn)
+        .optional())
+}
+
+/// Looks up the current slugs of live items, keyed by id. Missing and
+/// trashed items are left out.
+pub fn item_slugs(
+    conn: &mut PgConnection,
+    kind: ItemKind,
+    ids: &[i32],
+) -> QueryResult<HashMap<i32, String>> {
+    let slugs = for_kind!(kind, items => items::table
+        .filter(items::id.eq_any(ids))
+        .filter(items::deleted_at.is_null())
+        .select((items::id, items::slug))
+        .load::<(i32, String)>(conn))?;
+
+    Ok(slugs.into_iter().collect())
+}
+
+/// Locks a live item's row until the end of the current transaction.
+/// Returns whether there was one to lock.
+pub fn lock_item(conn: &mut PgConnection, kind: ItemKind, item_id: i32) -> QueryResult<bool> {
+    for_kind!(kind, items => items::table
+        .find(item_id)
+        .filter(items::deleted_at.is_null())
+        .select(items::id)
+        .for_update()
+        .first::<i32>(conn)
+        .optional()
+        .map(|locked| locked.is_some()))
+}
+
+/// Resolves an id, UUID or slug to a live item's id. Slugs are checked
+/// against the current ones first, then against `slug_history`.
+pub fn resolve_ref(
+    conn: &mut PgConnection,
+    kind: ItemKind,
+    item_ref: &ItemRef,
+) -> Result<Resolved, ApiError> {
+    let found = match item_ref {
+        ItemRef::Id(id) => return Ok(Resolved::Found(*id)),
+        ItemRef::Uuid(uuid) => find_by_uuid(conn, kind, *uuid),
+        ItemRef::Slug(slug) => find_by_slug(conn, kind, slug),
+    }?;
+
+    if let Some(item_id) = found {
+        return Ok(Resolved::Found(item_id));
+    }
+
+    let ItemRef::Slug(slug) = item_ref else {
+        return Err(ApiError::NotFound);
+    };
+
+    let renamed = slug_history::table
+        .filter(slug_history::item_type.eq(kind.table_name()))
+        .filter(slug_history::slug.eq(slug))
+        .select(slug_history::item_id)
+        .first::<i32>(conn)
+        .optional()?
+        .ok_or(ApiError::NotFound)?;
+
+    let current = current_slug(conn, kind, renamed)?.ok_or(ApiError::NotFound)?;
+
+    Ok(Resolved::Moved(Box::new(Redirect::permanent(format!(
+        "{}/{}",
+        kind.mount_point(),
+        current
+    )))))
+}
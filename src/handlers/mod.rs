@@ -1,12 +1,115 @@
-use rocket::{catch, get};
+use {
+    crate::errors::ErrorPage,
+    chrono::{DateTime, NaiveDate, NaiveDateTime},
+    rocket::{catch, get, http::Status, request::FromParam},
+    serde::{Deserialize, Serialize},
+};
 
+pub mod accounts;
 pub mod admin;
+pub mod albums;
 pub mod books;
+pub mod collections;
+pub mod duplicates;
+pub mod etags;
+pub mod extra;
 pub mod games;
+pub mod identifiers;
+pub mod movies;
 pub mod projects;
+pub mod ratings;
+pub mod relations;
 pub mod reviews;
+pub mod revisions;
+pub mod series;
+pub mod shows;
+pub mod statuses;
+pub mod tags;
+pub mod trash;
 pub mod wplace;
 
+/// The content tables that can be addressed generically, e.g. `/revisions/books/1`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ItemKind {
+    Albums,
+    Books,
+    Games,
+    Movies,
+    Shows,
+    Projects,
+    Reviews,
+    Wplace,
+}
+
+impl ItemKind {
+    pub fn table_name(self) -> &'static str {
+        match self {
+            ItemKind::Albums => "albums",
+            ItemKind::Books => "books",
+            ItemKind::Games => "games",
+            ItemKind::Movies => "movies",
+            ItemKind::Shows => "shows",
+            ItemKind::Projects => "projects",
+            ItemKind::Reviews => "reviews",
+            ItemKind::Wplace => "wplace",
+        }
+    }
+
+    /// Where the kind's routes are mounted, used to build redirect locations.
+    pub fn mount_point(self) -> &'static str {
+        match self {
+            ItemKind::Albums => "/albums",
+            ItemKind::Books => "/read-watch",
+            ItemKind::Games => "/games",
+            ItemKind::Movies => "/movies",
+            ItemKind::Shows => "/shows",
+            ItemKind::Projects => "/projects",
+            ItemKind::Reviews => "/reviews",
+            ItemKind::Wplace => "/wplace",
+        }
+    }
+
+    /// Where the item with `id` can be read back, used for `Location` headers.
+    pub fn location(self, id: i32) -> String {
+        format!("{}/{}", self.mount_point(), id)
+    }
+}
+
+impl<'a> FromParam<'a> for ItemKind {
+    type Error = &'a str;
+
+    fn from_param(param: &'a str) -> Result<Self, Self::Error> {
+        match param {
+            "albums" => Ok(ItemKind::Albums),
+            "books" | "read-watch" => Ok(ItemKind::Books),
+            "games" => Ok(ItemKind::Games),
+            "movies" => Ok(ItemKind::Movies),
+            "shows" => Ok(ItemKind::Shows),
+            "projects" => Ok(ItemKind::Projects),
+            "reviews" => Ok(ItemKind::Reviews),
+            "wplace" => Ok(ItemKind::Wplace),
+            _ => Err(param),
+        }
+    }
+}
+
+/// Parses a timestamp query parameter, accepting RFC 3339, a naive
+/// `YYYY-MM-DDTHH:MM:SS` timestamp or a bare `YYYY-MM-DD` date.
+pub fn parse_timestamp(value: &str) -> Option<NaiveDateTime> {
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(value) {
+        return Some(timestamp.naive_utc());
+    }
+
+    if let Ok(timestamp) = NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f") {
+        return Some(timestamp);
+    }
+
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+}
+
 #[get("/")]
 pub fn index() -> &'static str {
     r#"
@@ -73,9 +176,7 @@ u8888cJ888    *888>       .8888Lu=  888E  888E 8888L          9888  888E 9888  9
 "#
 }
 
-#[catch(404)]
-pub fn catch404() -> &'static str {
-    r#"
+const NOT_FOUND_ART: &str = r#"
  ________   ________  ________           _______      ___    ___ ___  ________  _________  _______   ________   _________        ________  ________  ___       ___          
 |\   ___  \|\   __  \|\   ___  \        |\  ___ \    |\  \  /  /|\  \|\   ____\|\___   ___\\  ___ \ |\   ___  \|\___   ___\     |\   ____\|\   __  \|\  \     |\  \         
 \ \  \\ \  \ \  \|\  \ \  \\ \  \       \ \   __/|   \ \  \/  / | \  \ \  \___|\|___ \  \_\ \   __/|\ \  \\ \  \|___ \  \_|     \ \  \___|\ \  \|\  \ \  \    \ \  \        
@@ -94,12 +195,9 @@ pub fn catch404() -> &'static str {
    \ \__\    \ \__\ \_______\       \ \_______\/  /\   \    \ \__\____\_\  \   \ \__\ \ \_______\ \__\\ \__\   \ \__\ \ \__\ \__\ \__\ \_______\                            
     \|__|     \|__|\|_______|        \|_______/__/ /\ __\    \|__|\_________\   \|__|  \|_______|\|__| \|__|    \|__|  \|__|\|__|\|__|\|_______|                            
                                               |__|/ \|__|        \|_________|                                                                                               
-"#
-}
+"#;
 
-#[catch(401)]
-pub fn catch401() -> &'static str {
-    r#"
+const UNAUTHORIZED_ART: &str = r#"
    .-'''-.   ___    _     _______   .--.   .--.          ,---.    ,---.   ____     __          ______     .-./`)     _______   .--.   .--.   
   / _     \.'   |  | |   /   __  \  |  | _/  /           |    \  /    |   \   \   /  /        |    _ `''. \ .-.')   /   __  \  |  | _/  /    
  (`' )/`--'|   .'  | |  | ,_/  \__) | (`' ) /            |  ,  \/  ,  |    \  _. /  '         | _ | ) _  \/ `-' \  | ,_/  \__) | (`' ) /     
@@ -108,12 +206,68 @@ pub fn catch401() -> &'static str {
 .---.  \  :' (`. _` /| > (_)  )  __ |  |\ \  |  |        | (_ o _) |  ||   |(_,_)'            |(_    ._) ' |   |  > (_)  )  __ |  |\ \  |  | 
 \    `-'  || (_ (_) _)(  .  .-'_/  )|  | \ `'   /        |  (_,_)  |  ||   `-'  /             |  (_.\.' /  |   | (  .  .-'_/  )|  | \ `'   / 
  \       /  \ /  . \ / `-'`-'     / |  |  \    /         |  |      |  | \      /              |       .'   |   |  `-'`-'     / |  |  \    /  
-  `-...-'    ``-'`-''    `._____.'  `--'   `'-'          '--'      '--'  `-..-'               '-----'`     '---'    `._____.'  `--'   `'-'   "#
+  `-...-'    ``-'`-''    `._____.'  `--'   `'-'          '--'      '--'  `-..-'               '-----'`     '---'    `._____.'  `--'   `'-'   "#;
+
+const INTERNAL_ERROR_ART: &str = r#"
+you lost the game.
+    "#;
+
+#[catch(400)]
+pub fn catch400() -> ErrorPage {
+    ErrorPage::new(Status::BadRequest, "The request couldn't be understood")
+}
+
+#[catch(401)]
+pub fn catch401() -> ErrorPage {
+    ErrorPage::new(Status::Unauthorized, "A valid API key is required").with_art(UNAUTHORIZED_ART)
+}
+
+#[catch(403)]
+pub fn catch403() -> ErrorPage {
+    ErrorPage::new(Status::Forbidden, "This API key isn't allowed to do that")
+}
+
+#[catch(404)]
+pub fn catch404() -> ErrorPage {
+    ErrorPage::new(Status::NotFound, "Resource not found").with_art(NOT_FOUND_ART)
+}
+
+#[catch(409)]
+pub fn catch409() -> ErrorPage {
+    ErrorPage::new(
+        Status::Conflict,
+        "The request conflicts with the resource's current state",
+    )
+}
+
+#[catch(413)]
+pub fn catch413() -> ErrorPage {
+    ErrorPage::new(Status::PayloadTooLarge, "The request body is too large")
+}
+
+#[catch(422)]
+pub fn catch422() -> ErrorPage {
+    ErrorPage::new(
+        Status::UnprocessableEntity,
+        "The request body doesn't match what this endpoint expects",
+    )
+}
+
+#[catch(429)]
+pub fn catch429() -> ErrorPage {
+    ErrorPage::new(Status::TooManyRequests, "Too many requests, slow down")
 }
 
 #[catch(500)]
-pub fn catch500() -> &'static str {
-    r#"
-you lost the game.
-    "#
+pub fn catch500() -> ErrorPage {
+    ErrorPage::new(Status::InternalServerError, "Internal server error")
+        .with_art(INTERNAL_ERROR_ART)
+}
+
+#[catch(503)]
+pub fn catch503() -> ErrorPage {
+    ErrorPage::new(
+        Status::ServiceUnavailable,
+        "The service is temporarily unavailable",
+    )
 }
@@ -0,0 +1,491 @@
+use crate::auth::{AdminUser, User};
+use crate::db::connect_db;
+use crate::errors::ApiError;
+use crate::handlers::etags::{Created, IfMatch, Tagged};
+use crate::handlers::identifiers::{ItemRef, Located, Resolved, resolve_ref};
+use crate::handlers::ratings::{Rated, rated, rating_scale};
+use crate::handlers::{ItemKind, parse_timestamp};
+use crate::models::{Movie, NewMovie, Rating, UpdateMovie};
+use chrono::{NaiveDateTime, Utc};
+use diesel::prelude::*;
+use rocket::Either;
+use rocket::form::FromForm;
+use rocket::serde::{Deserialize, Serialize, json::Json};
+use rocket::{delete, get, patch, post, put, routes};
+use std::collections::HashMap;
+use validator::Validate;
+
+#[derive(FromForm, Debug)]
+pub struct MovieQuery {
+    title: Option<String>,
+    director: Option<String>,
+    studio: Option<String>,
+    genre: Option<String>,
+    tag: Option<String>,
+    status: Option<String>,
+    explicit: Option<String>,
+    #[field(name = "releaseYear")]
+    release_year: Option<i32>,
+    #[field(name = "minRuntime")]
+    min_runtime: Option<i32>,
+    #[field(name = "maxRuntime")]
+    max_runtime: Option<i32>,
+    #[field(name = "minRating")]
+    min_rating: Option<f64>,
+    #[field(name = "maxRating")]
+    max_rating: Option<f64>,
+    #[field(name = "exactRating")]
+    exact_rating: Option<f64>,
+    #[field(name = "minRewatches")]
+    min_rewatches: Option<i32>,
+    #[field(name = "watchedAfter")]
+    watched_after: Option<String>,
+    #[field(name = "createdAfter")]
+    created_after: Option<String>,
+    #[field(name = "updatedSince")]
+    updated_since: Option<String>,
+    sort: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct BulkDeleteFilter {
+    director: Option<String>,
+    status: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct BulkUpdatePayload {
+    filter: HashMap<String, String>,
+    update: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct WatchLog {
+    watched_at: Option<NaiveDateTime>,
+}
+
+#[derive(Serialize)]
+pub struct ApiResponse {
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    deleted: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    updated: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    count: Option<usize>,
+}
+
+#[get("/search?<query..>")]
+pub fn get_movies(query: MovieQuery) -> Result<Json<Vec<Rated<Movie>>>, ApiError> {
+    use crate::schema::movies::dsl::*;
+
+    let mut conn = connect_db()?;
+    let mut movie_query = movies.filter(deleted_at.is_null()).into_boxed();
+
+    if let Some(title_filter) = &query.title {
+        movie_query = movie_query.filter(title.ilike(format!("%{}%", title_filter)));
+    }
+
+    if let Some(director_filter) = &query.director {
+        movie_query = movie_query.filter(director.ilike(format!("%{}%", director_filter)));
+    }
+
+    if let Some(studio_filter) = &query.studio {
+        movie_query = movie_query.filter(studio.ilike(format!("%{}%", studio_filter)));
+    }
+
+    if let Some(status_filter) = &query.status {
+        movie_query = movie_query.filter(status.eq(status_filter));
+    }
+
+    if let Some(explicit_filter) = &query.explicit {
+        match explicit_filter.as_str() {
+            "true" => movie_query = movie_query.filter(explicit.eq(true)),
+            "false" => movie_query = movie_query.filter(explicit.eq(false)),
+            _ => {}
+        }
+    }
+
+    if let Some(release_year_filter) = query.release_year {
+        movie_query = movie_query.filter(release_year.eq(release_year_filter));
+    }
+
+    if let Some(min_runtime_filter) = query.min_runtime {
+        movie_query = movie_query.filter(runtime_minutes.ge(min_runtime_filter));
+    }
+
+    if let Some(max_runtime_filter) = query.max_runtime {
+        movie_query = movie_query.filter(runtime_minutes.le(max_runtime_filter));
+    }
+
+    if let Some(min_rating_filter) = query.min_rating {
+        movie_query = movie_query.filter(rating.ge(Rating(min_rating_filter)));
+    }
+
+    if let Some(max_rating_filter) = query.max_rating {
+        movie_query = movie_query.filter(rating.le(Rating(max_rating_filter)));
+    }
+
+    if let Some(exact_rating_filter) = query.exact_rating {
+        movie_query = movie_query.filter(rating.eq(Rating(exact_rating_filter)));
+    }
+
+    if let Some(min_rewatches_filter) = query.min_rewatches {
+        movie_query = movie_query.filter(rewatch_count.ge(min_rewatches_filter));
+    }
+
+    if let Some(watched_after_filter) = &query.watched_after {
+        let timestamp = parse_timestamp(watched_after_filter)
+            .ok_or_else(|| ApiError::bad_request("invalid watchedAfter timestamp"))?;
+        movie_query = movie_query.filter(last_watched_at.gt(timestamp));
+    }
+
+    if let Some(created_after_filter) = &query.created_after {
+        let timestamp = parse_timestamp(created_after_filter)
+            .ok_or_else(|| ApiError::bad_request("invalid createdAfter timestamp"))?;
+        movie_query = movie_query.filter(created_at.gt(timestamp));
+    }
+
+    if let Some(updated_since_filter) = &query.updated_since {
+        let timestamp = parse_timestamp(updated_since_filter)
+            .ok_or_else(|| ApiError::bad_request("invalid updatedSince timestamp"))?;
+        movie_query = movie_query.filter(updated_at.ge(timestamp));
+    }
+
+    if let Some(sort_by) = &query.sort {
+        match sort_by.as_str() {
+            "title" => movie_query = movie_query.order(title.asc()),
+            "director" => movie_query = movie_query.order(director.asc().nulls_last()),
+            "rating" => movie_query = movie_query.order(rating.desc()),
+            "runtime" => movie_query = movie_query.order(runtime_minutes.desc().nulls_last()),
+            "release" => movie_query = movie_query.order(release_year.desc().nulls_last()),
+            "watched" => movie_query = movie_query.order(last_watched_at.desc().nulls_last()),
+            "rewatches" => movie_query = movie_query.order(rewatch_count.desc()),
+            "created" => movie_query = movie_query.order(created_at.desc()),
+            "updated" => movie_query = movie_query.order(updated_at.desc()),
+            _ => {}
+        }
+    }
+
+    let results = movie_query.load::<Movie>(&mut conn)?;
+
+    let mut filtered_results = results;
+
+    if let Some(genre_filter) = &query.genre {
+        filtered_results.retain(|movie| {
+            movie
+                .genres
+                .iter()
+                .flatten()
+                .any(|genre| genre.to_lowercase().contains(&genre_filter.to_lowercase()))
+        });
+    }
+
+    if let Some(tag_filter) = &query.tag {
+        filtered_results.retain(|movie| {
+            movie
+                .tags
+                .iter()
+                .flatten()
+                .any(|tag| tag.to_lowercase().contains(&tag_filter.to_lowercase()))
+        });
+    }
+
+    let scale = rating_scale(&mut conn, ItemKind::Movies)?;
+
+    Ok(Json(
+        filtered_results
+            .into_iter()
+            .map(|movie| Rated::new(&scale, movie.rating, movie))
+            .collect(),
+    ))
+}
+
+/// A live movie as `GET /movies/<id>` returns it.
+fn current_movie(conn: &mut PgConnection, movie_id: i32) -> Result<Rated<Movie>, ApiError> {
+    use crate::schema::movies::dsl::*;
+
+    let movie = movies
+        .filter(id.eq(&movie_id))
+        .filter(deleted_at.is_null())
+        .first::<Movie>(conn)?;
+
+    Ok(rated(conn, ItemKind::Movies, movie.rating, movie)?)
+}
+
+#[get("/<movie_ref>")]
+pub fn get_movie_by_id(movie_ref: ItemRef) -> Result<Located<Rated<Movie>>, ApiError> {
+    let mut conn = connect_db()?;
+
+    let movie_id = match resolve_ref(&mut conn, ItemKind::Movies, &movie_ref)? {
+        Resolved::Found(found) => found,
+        Resolved::Moved(redirect) => return Ok(Either::Right(*redirect)),
+    };
+
+    let response = current_movie(&mut conn, movie_id)?;
+
+    Ok(Either::Left(Tagged::new(response)))
+}
+
+#[post("/", format = "json", data = "<new_movie>")]
+pub fn post_movies(
+    _user: User,
+    new_movie: Json<NewMovie>,
+) -> Result<Created<Rated<Movie>>, ApiError> {
+    _user.require_admin()?;
+    use crate::schema::movies;
+
+    new_movie.validate()?;
+
+    let mut conn = connect_db()?;
+
+    let movie = diesel::insert_into(movies::table)
+        .values(&new_movie.into_inner())
+        .get_result::<Movie>(&mut conn)?;
+
+    let location = ItemKind::Movies.location(movie.id);
+    let response = rated(&mut conn, ItemKind::Movies, movie.rating, movie)?;
+
+    Ok(Created::new(location, response))
+}
+
+#[put("/<movie_id>", format = "json", data = "<updated_movie>")]
+pub fn update_movie(
+    _user: User,
+    if_match: IfMatch,
+    movie_id: i32,
+    updated_movie: Json<UpdateMovie>,
+) -> Result<Tagged<Rated<Movie>>, ApiError> {
+    _user.require_admin()?;
+    use crate::schema::movies::dsl::*;
+
+    updated_movie.validate()?;
+
+    let mut conn = connect_db()?;
+
+    let response = conn.transaction::<_, ApiError, _>(|conn| {
+        if_match.check(conn, ItemKind::Movies, movie_id, current_movie)?;
+        let movie = diesel::update(movies.filter(id.eq(movie_id)).filter(deleted_at.is_null()))
+            .set(&updated_movie.into_inner())
+            .get_result::<Movie>(conn)?;
+        Ok(rated(conn, ItemKind::Movies, movie.rating, movie)?)
+    })?;
+
+    Ok(Tagged::new(response))
+}
+
+#[patch("/<movie_id>", format = "json", data = "<patch_data>")]
+pub fn patch_movie(
+    _user: User,
+    if_match: IfMatch,
+    movie_id: i32,
+    patch_data: Json<UpdateMovie>,
+) -> Result<Tagged<Rated<Movie>>, ApiError> {
+    _user.require_admin()?;
+    use crate::schema::movies::dsl::*;
+
+    patch_data.validate()?;
+
+    let mut conn = connect_db()?;
+
+    let response = conn.transaction::<_, ApiError, _>(|conn| {
+        if_match.check(conn, ItemKind::Movies, movie_id, current_movie)?;
+        let movie = diesel::update(movies.filter(id.eq(movie_id)).filter(deleted_at.is_null()))
+            .set(&patch_data.into_inner())
+            .get_result::<Movie>(conn)?;
+        Ok(rated(conn, ItemKind::Movies, movie.rating, movie)?)
+    })?;
+
+    Ok(Tagged::new(response))
+}
+
+/// Records a viewing. The first one sets `first_watched_at`; every later one
+/// counts as a rewatch.
+#[post("/<movie_id>/watches", format = "json", data = "<watch>")]
+pub fn log_movie_watch(
+    _admin: AdminUser,
+    movie_id: i32,
+    watch: Json<WatchLog>,
+) -> Result<Json<Movie>, ApiError> {
+    use crate::schema::movies::dsl::*;
+
+    let watched_at = watch
+        .into_inner()
+        .watched_at
+        .unwrap_or_else(|| Utc::now().naive_utc());
+    let mut conn = connect_db()?;
+
+    let movie = conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        let movie = movies
+            .filter(id.eq(movie_id))
+            .filter(deleted_at.is_null())
+            .for_update()
+            .first::<Movie>(conn)?;
+
+        diesel::update(movies.filter(id.eq(movie.id)))
+            .set(&UpdateMovie {
+                first_watched_at: Some(
+                    movie
+                        .first_watched_at
+                        .map_or(watched_at, |first| first.min(watched_at)),
+                ),
+                last_watched_at: Some(
+                    movie
+                        .last_watched_at
+                        .map_or(watched_at, |last| last.max(watched_at)),
+                ),
+                rewatch_count: Some(
+                    movie.rewatch_count + i32::from(movie.first_watched_at.is_some()),
+                ),
+                ..Default::default()
+            })
+            .get_result::<Movie>(conn)
+    })?;
+
+    Ok(Json(movie))
+}
+
+#[delete("/<movie_id>")]
+pub fn delete_movie(
+    _user: User,
+    if_match: IfMatch,
+    movie_id: i32,
+) -> Result<Json<ApiResponse>, ApiError> {
+    _user.require_admin()?;
+    use crate::schema::movies::dsl::*;
+
+    let mut conn = connect_db()?;
+
+    let rows_deleted = conn.transaction::<_, ApiError, _>(|conn| {
+        if_match.check(conn, ItemKind::Movies, movie_id, current_movie)?;
+        Ok(
+            diesel::update(movies.filter(id.eq(&movie_id)).filter(deleted_at.is_null()))
+                .set(deleted_at.eq(diesel::dsl::now.nullable()))
+                .execute(conn)?,
+        )
+    })?;
+
+    if rows_deleted > 0 {
+        Ok(Json(ApiResponse {
+            message: "movie moved to trash".to_string(),
+            deleted: None,
+            updated: None,
+            count: None,
+        }))
+    } else {
+        Err(ApiError::NotFound)
+    }
+}
+
+#[post("/<movie_id>/restore")]
+pub fn restore_movie(_admin: AdminUser, movie_id: i32) -> Result<Json<Movie>, ApiError> {
+    use crate::schema::movies::dsl::*;
+
+    let mut conn = connect_db()?;
+
+    let movie = diesel::update(
+        movies
+            .filter(id.eq(&movie_id))
+            .filter(deleted_at.is_not_null()),
+    )
+    .set(deleted_at.eq(None::<NaiveDateTime>))
+    .get_result::<Movie>(&mut conn)?;
+
+    Ok(Json(movie))
+}
+
+#[delete("/bulk", format = "json", data = "<filter>")]
+pub fn bulk_delete_movies(
+    _user: User,
+    filter: Json<BulkDeleteFilter>,
+) -> Result<Json<ApiResponse>, ApiError> {
+    _user.require_admin()?;
+    use crate::schema::movies::dsl::*;
+
+    let mut conn = connect_db()?;
+    let filter = filter.into_inner();
+
+    let mut delete_query = diesel::update(movies.filter(deleted_at.is_null())).into_boxed();
+
+    if let Some(director_filter) = &filter.director {
+        delete_query = delete_query.filter(director.eq(director_filter));
+    }
+
+    if let Some(status_filter) = &filter.status {
+        delete_query = delete_query.filter(status.eq(status_filter));
+    }
+
+    let deleted_count = delete_query
+        .set(deleted_at.eq(diesel::dsl::now.nullable()))
+        .execute(&mut conn)? as i32;
+
+    Ok(Json(ApiResponse {
+        message: "bulk delete complete".to_string(),
+        deleted: Some(deleted_count),
+        updated: None,
+        count: None,
+    }))
+}
+
+#[patch("/bulk", format = "json", data = "<payload>")]
+pub fn bulk_update_movies(
+    _user: User,
+    payload: Json<BulkUpdatePayload>,
+) -> Result<Json<ApiResponse>, ApiError> {
+    _user.require_admin()?;
+    use crate::schema::movies::dsl::*;
+
+    let mut conn = connect_db()?;
+    let payload = payload.into_inner();
+
+    let mut update_query = diesel::update(movies.filter(deleted_at.is_null())).into_boxed();
+
+    if let Some(director_filter) = payload.filter.get("director") {
+        update_query = update_query.filter(director.eq(director_filter));
+    }
+
+    if let Some(status_filter) = payload.filter.get("status") {
+        update_query = update_query.filter(status.eq(status_filter));
+    }
+
+    let mut update_movie = UpdateMovie::default();
+
+    if let Some(new_status) = payload.update.get("status")
+        && let Some(status_str) = new_status.as_str()
+    {
+        update_movie.status = Some(status_str.to_string());
+    }
+
+    if let Some(new_rating) = payload.update.get("rating")
+        && let Some(rating_num) = new_rating.as_f64()
+    {
+        update_movie.rating = Some(Rating(rating_num));
+    }
+
+    update_movie.validate()?;
+
+    let updated_count = update_query.set(&update_movie).execute(&mut conn)? as i32;
+
+    Ok(Json(ApiResponse {
+        message: "bulk update complete".to_string(),
+        deleted: None,
+        updated: Some(updated_count),
+        count: None,
+    }))
+}
+
+pub fn movies_routes() -> Vec<rocket::Route> {
+    routes![
+        get_movies,
+        get_movie_by_id,
+        post_movies,
+        update_movie,
+        patch_movie,
+        log_movie_watch,
+        delete_movie,
+        restore_movie,
+        bulk_delete_movies,
+        bulk_update_movies
+    ]
+}
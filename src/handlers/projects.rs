@@ -1,101 +1,255 @@
 use crate::db::connect_db;
+use crate::handlers::accounts::require_owner;
+use crate::handlers::duplicates::{CreateOptions, MergeItems, check_duplicates, merge_items};
+use crate::handlers::etags::{Created, IfMatch, Tagged};
+use crate::handlers::extra::validate_extra;
+use crate::handlers::identifiers::{ItemRef, Located, Resolved, resolve_ref};
+use crate::errors::ApiError;
+use crate::handlers::{ItemKind, parse_timestamp};
 use crate::models::{NewProject, Project, UpdateProject};
 use crate::schema::projects;
-use crate::auth::User;
+use crate::auth::{AdminUser, User};
 use diesel::prelude::*;
+use rocket::Either;
 use rocket::serde::json::Json;
-use rocket::{delete, get, patch, post, put, routes};
+use rocket::{FromForm, delete, get, patch, post, put, routes};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use validator::Validate;
 
-#[post("/", format = "json", data = "<new_project>")]
-pub fn create_project(_user: User, new_project: Json<NewProject<'_>>) -> Json<Project> {
-    _user.require_admin().expect("User is not admin");
-    let mut conn = connect_db();
+#[derive(FromForm, Debug)]
+pub struct ProjectQuery {
+    #[field(name = "createdAfter")]
+    created_after: Option<String>,
+    #[field(name = "updatedSince")]
+    updated_since: Option<String>,
+    status: Option<String>,
+    featured: Option<bool>,
+    /// Matches the primary or any secondary language.
+    language: Option<String>,
+    /// Exact matches on extra fields, e.g. `extra.platform=switch`. Array
+    /// fields match any element.
+    extra: Option<HashMap<String, String>>,
+    /// Limits results to one account's library, as `/users/<handle>/...` does.
+    #[field(name = "ownerId")]
+    pub(crate) owner_id: Option<i32>,
+    sort: Option<String>,
+}
+
+/// The new order for `PUT /projects/order`. Listed projects come first, in
+/// the given order; the rest keep their current order after them.
+#[derive(Deserialize)]
+pub struct ProjectOrder {
+    ids: Vec<i32>,
+}
+
+#[post("/?<options..>", format = "json", data = "<new_project>")]
+pub fn create_project(user: User, options: CreateOptions, new_project: Json<NewProject<'_>>) -> Result<Created<Project>, ApiError> {
+    let mut conn = connect_db()?;
     let new_project = new_project.into_inner();
-    diesel::insert_into(projects::table)
-        .values(&new_project)
-        .execute(&mut conn)
-        .expect("Error inserting new review");
-
-    Json(Project {
-        id: 0,
-        name: new_project.name.to_string(),
-        description: new_project.description.to_string(),
-        cover_image: Some(new_project.cover_image.unwrap().to_string()),
-        tags: Some(
-            new_project
-                .tags
-                .unwrap()
-                .into_iter()
-                .map(|w| Some(w.unwrap().to_string()))
-                .collect(),
-        ),
-        source: new_project.source.to_string(),
-        install_command: Some(new_project.install_command.unwrap().to_string()),
-    })
+    new_project.validate()?;
+    if let Some(extra) = &new_project.extra {
+        validate_extra(&mut conn, ItemKind::Projects, extra)?;
+    }
+    check_duplicates(&mut conn, ItemKind::Projects, user.account_id(), new_project.name, new_project.source, &options)?;
+
+    let project = diesel::insert_into(projects::table)
+        .values((&new_project, projects::owner_id.eq(user.account_id())))
+        .get_result::<Project>(&mut conn)?;
+
+    Ok(Created::new(ItemKind::Projects.location(project.id), project))
 }
 
-#[get("/<id>")]
-pub fn get_project(id: i32) -> Option<Json<Project>> {
-    let mut conn = connect_db();
+/// A live project as `GET /projects/<id>` returns it.
+fn current_project(conn: &mut PgConnection, id: i32) -> Result<Project, ApiError> {
     projects::table
         .find(id)
-        .first::<Project>(&mut conn)
-        .ok()
-        .map(Json)
+        .filter(projects::deleted_at.is_null())
+        .first::<Project>(conn)
+        .map_err(ApiError::from)
 }
 
-#[get("/")]
-pub fn get_projects() -> Option<Json<Vec<Project>>> {
-    let mut conn = connect_db();
-    projects::table
-        .load::<Project>(&mut conn)
-        .ok()
-        .map(Json)
+#[get("/<project_ref>")]
+pub fn get_project(project_ref: ItemRef) -> Result<Located<Project>, ApiError> {
+    let mut conn = connect_db()?;
+
+    let id = match resolve_ref(&mut conn, ItemKind::Projects, &project_ref)? {
+        Resolved::Found(found) => found,
+        Resolved::Moved(redirect) => return Ok(Either::Right(*redirect)),
+    };
+
+    current_project(&mut conn, id).map(|project| Either::Left(Tagged::new(project)))
+}
+
+#[get("/?<query..>")]
+pub fn get_projects(query: ProjectQuery) -> Result<Json<Vec<Project>>, ApiError> {
+    use crate::schema::projects::dsl::*;
+
+    let mut conn = connect_db()?;
+    let mut project_query = projects.filter(deleted_at.is_null()).into_boxed();
+
+    if let Some(owner_filter) = query.owner_id {
+        project_query = project_query.filter(owner_id.eq(owner_filter));
+    }
+
+    if let Some(created_after_filter) = &query.created_after {
+        let timestamp = parse_timestamp(created_after_filter)
+            .ok_or_else(|| ApiError::bad_request("invalid createdAfter timestamp"))?;
+        project_query = project_query.filter(created_at.gt(timestamp));
+    }
+
+    if let Some(updated_since_filter) = &query.updated_since {
+        let timestamp = parse_timestamp(updated_since_filter)
+            .ok_or_else(|| ApiError::bad_request("invalid updatedSince timestamp"))?;
+        project_query = project_query.filter(updated_at.ge(timestamp));
+    }
+
+    if let Some(status_filter) = &query.status {
+        project_query = project_query.filter(status.eq(status_filter));
+    }
+
+    if let Some(featured_filter) = query.featured {
+        project_query = project_query.filter(featured.eq(featured_filter));
+    }
+
+    if let Some(language_filter) = &query.language {
+        project_query = project_query.filter(
+            primary_language
+                .eq(language_filter)
+                .or(secondary_languages.contains(vec![Some(language_filter.clone())])),
+        );
+    }
+
+    if let Some(extra_filters) = &query.extra {
+        for (key, value) in extra_filters {
+            project_query = project_query.filter(
+                extra
+                    .retrieve_as_text(key.clone())
+                    .eq(value.clone())
+                    .or(extra.contains(serde_json::json!({ key: [value] }))),
+            );
+        }
+    }
+
+    match query.sort.as_deref() {
+        Some("name") => project_query = project_query.order(name.asc()),
+        Some("created") => project_query = project_query.order(created_at.desc()),
+        Some("updated") => project_query = project_query.order(updated_at.desc()),
+        _ => project_query = project_query.order((featured.desc(), position.asc(), id.asc())),
+    }
+
+    let results = project_query.load::<Project>(&mut conn)?;
+
+    Ok(Json(results))
 }
 
 #[put("/<id>", format = "json", data = "<update_data>")]
-pub fn update_project(_user: User, id: i32, update_data: Json<UpdateProject>) -> Option<Json<Project>> {
-    _user.require_admin().expect("User is not admin");
-    let mut conn = connect_db();
-    diesel::update(projects::table.find(id))
-        .set(&update_data.into_inner())
-        .execute(&mut conn)
-        .ok()?;
+pub fn update_project(user: User, if_match: IfMatch, id: i32, update_data: Json<UpdateProject>) -> Result<Tagged<Project>, ApiError> {
+    let mut conn = connect_db()?;
+    require_owner(&mut conn, &user, ItemKind::Projects, id)?;
+    let changes = update_data.into_inner();
+    changes.validate()?;
+    if let Some(extra) = &changes.extra {
+        validate_extra(&mut conn, ItemKind::Projects, extra)?;
+    }
 
-    projects::table
-        .find(id)
-        .first::<Project>(&mut conn)
-        .ok()
-        .map(Json)
+    conn.transaction::<_, ApiError, _>(|conn| {
+        if_match.check(conn, ItemKind::Projects, id, current_project)?;
+        Ok(diesel::update(projects::table.find(id).filter(projects::deleted_at.is_null()))
+            .set(&changes)
+            .get_result::<Project>(conn)?)
+    })
+    .map(Tagged::new)
 }
 
 #[delete("/<id>")]
-pub fn delete_project(_user: User, id: i32) -> Option<Json<Project>> {
-    _user.require_admin().expect("User is not admin");
-    let mut conn = connect_db();
-    let project = projects::table.find(id).first::<Project>(&mut conn).ok()?;
-    diesel::delete(projects::table.find(id))
-        .execute(&mut conn)
-        .ok()?;
-
-    Some(Json(project))
+pub fn delete_project(user: User, if_match: IfMatch, id: i32) -> Result<Json<Project>, ApiError> {
+    let mut conn = connect_db()?;
+    require_owner(&mut conn, &user, ItemKind::Projects, id)?;
+    conn.transaction::<_, ApiError, _>(|conn| {
+        if_match.check(conn, ItemKind::Projects, id, current_project)?;
+        Ok(diesel::update(projects::table.find(id).filter(projects::deleted_at.is_null()))
+            .set(projects::deleted_at.eq(diesel::dsl::now.nullable()))
+            .get_result::<Project>(conn)?)
+    })
+    .map(Json)
+}
+
+#[post("/<id>/restore")]
+pub fn restore_project(user: User, id: i32) -> Result<Json<Project>, ApiError> {
+    let mut conn = connect_db()?;
+    require_owner(&mut conn, &user, ItemKind::Projects, id)?;
+    diesel::update(projects::table.find(id).filter(projects::deleted_at.is_not_null()))
+        .set(projects::deleted_at.eq(None::<chrono::NaiveDateTime>))
+        .get_result::<Project>(&mut conn)
+        .map(Json)
+        .map_err(ApiError::from)
+}
+
+/// Folds a duplicate project into `merge.into`, which is returned.
+#[post("/<id>/merge", format = "json", data = "<merge>")]
+pub fn merge_project(_admin: AdminUser, id: i32, merge: Json<MergeItems>) -> Result<Tagged<Project>, ApiError> {
+    let mut conn = connect_db()?;
+    merge_items(&mut conn, ItemKind::Projects, id, merge.into)?;
+
+    Ok(Tagged::new(current_project(&mut conn, merge.into)?))
 }
 
 #[patch("/<id>", format = "json", data = "<update_data>")]
-pub fn patch_project(_user: User, id: i32, update_data: Json<UpdateProject>) -> Option<Json<Project>> {
-    _user.require_admin().expect("User is not admin");
-    let mut conn = connect_db();
+pub fn patch_project(user: User, if_match: IfMatch, id: i32, update_data: Json<UpdateProject>) -> Result<Tagged<Project>, ApiError> {
+    let mut conn = connect_db()?;
+    require_owner(&mut conn, &user, ItemKind::Projects, id)?;
+    let changes = update_data.into_inner();
+    changes.validate()?;
+    if let Some(extra) = &changes.extra {
+        validate_extra(&mut conn, ItemKind::Projects, extra)?;
+    }
 
-    diesel::update(projects::table.find(id))
-        .set(&update_data.into_inner())
-        .execute(&mut conn)
-        .ok()?;
+    conn.transaction::<_, ApiError, _>(|conn| {
+        if_match.check(conn, ItemKind::Projects, id, current_project)?;
+        Ok(diesel::update(projects::table.find(id).filter(projects::deleted_at.is_null()))
+            .set(&changes)
+            .get_result::<Project>(conn)?)
+    })
+    .map(Tagged::new)
+}
 
-    projects::table
-        .find(id)
-        .first::<Project>(&mut conn)
-        .ok()
-        .map(Json)
+#[put("/order", format = "json", data = "<order>")]
+pub fn reorder_projects(_admin: AdminUser, order: Json<ProjectOrder>) -> Result<Json<Vec<Project>>, ApiError> {
+    let mut conn = connect_db()?;
+    let order = order.into_inner();
+
+    let mut listed = HashSet::new();
+    if !order.ids.iter().all(|id| listed.insert(*id)) {
+        return Err(ApiError::validation("each project can only be listed once"));
+    }
+
+    conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        let current = projects::table
+            .filter(projects::deleted_at.is_null())
+            .order((projects::position.asc(), projects::id.asc()))
+            .select(projects::id)
+            .load::<i32>(conn)?;
+
+        if order.ids.iter().any(|id| !current.contains(id)) {
+            return Err(diesel::result::Error::NotFound);
+        }
+
+        let rest = current.iter().filter(|id| !listed.contains(*id));
+        for (index, id) in order.ids.iter().chain(rest).enumerate() {
+            let position = index as i32 + 1;
+            diesel::update(projects::table.find(*id).filter(projects::position.ne(position)))
+                .set(projects::position.eq(position))
+                .execute(conn)?;
+        }
+
+        projects::table
+            .filter(projects::deleted_at.is_null())
+            .order((projects::featured.desc(), projects::position.asc(), projects::id.asc()))
+            .load::<Project>(conn)
+    })
+    .map(Json)
+    .map_err(ApiError::from)
 }
 
 pub fn projects_routes() -> Vec<rocket::Route> {
@@ -105,6 +259,9 @@ pub fn projects_routes() -> Vec<rocket::Route> {
         get_projects,
         update_project,
         delete_project,
+        restore_project,
+        merge_project,
         patch_project,
+        reorder_projects,
     ]
 }
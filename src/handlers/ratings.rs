@@ -0,0 +1,187 @@
+use crate::auth::AdminUser;
+use crate::db::connect_db;
+use crate::errors::ApiError;
+use crate::handlers::ItemKind;
+use crate::models::{Rating, RatingScale, UpdateRatingScale};
+use crate::schema::rating_scales;
+use diesel::prelude::*;
+use diesel::sql_types::{BigInt, Nullable, Numeric};
+use rocket::serde::json::Json;
+use rocket::{Route, get, put, routes};
+use serde::Serialize;
+use validator::Validate;
+
+/// An item along with its rating mapped onto 0–1, so ratings from resources
+/// with different scales can be compared.
+#[derive(Serialize)]
+pub struct Rated<T> {
+    #[serde(flatten)]
+    item: T,
+    normalized_rating: f64,
+}
+
+impl<T> Rated<T> {
+    pub fn new(scale: &RatingScale, rating: Rating, item: T) -> Self {
+        Rated {
+            item,
+            normalized_rating: scale.normalize(rating),
+        }
+    }
+}
+
+#[derive(QueryableByName)]
+struct OutOfRange {
+    #[diesel(sql_type = BigInt)]
+    count: i64,
+}
+
+/// The tables holding ratings on `kind`'s scale, or `None` for kinds that
+/// aren't rated.
+fn rated_tables(kind: ItemKind) -> Option<&'static [&'static str]> {
+    match kind {
+        ItemKind::Albums => Some(&["albums", "album_tracks"]),
+        ItemKind::Books => Some(&["books"]),
+        ItemKind::Games => Some(&["games"]),
+        ItemKind::Movies => Some(&["movies"]),
+        ItemKind::Shows => Some(&["shows"]),
+        ItemKind::Reviews => Some(&["reviews"]),
+        ItemKind::Projects | ItemKind::Wplace => None,
+    }
+}
+
+pub fn rating_scale(conn: &mut PgConnection, kind: ItemKind) -> QueryResult<RatingScale> {
+    rating_scales::table
+        .find(kind.table_name())
+        .first::<RatingScale>(conn)
+}
+
+/// Pairs a single item with its rating normalized on `kind`'s scale.
+pub fn rated<T>(
+    conn: &mut PgConnection,
+    kind: ItemKind,
+    rating: Rating,
+    item: T,
+) -> QueryResult<Rated<T>> {
+    let scale = rating_scale(conn, kind)?;
+    Ok(Rated::new(&scale, rating, item))
+}
+
+/// Counts the stored ratings that fall outside a scale.
+fn count_out_of_range(
+    conn: &mut PgConnection,
+    kind: ItemKind,
+    max_rating: Rating,
+    step: Rating,
+) -> QueryResult<i64> {
+    let mut total = 0;
+    for table in rated_tables(kind).unwrap_or(&[]) {
+        total += diesel::sql_query(format!(
+            "SELECT COUNT(*) AS count FROM {table} \
+             WHERE rating < 0 OR rating > $1 OR mod(rating, $2) <> 0"
+        ))
+        .bind::<Numeric, _>(max_rating)
+        .bind::<Numeric, _>(step)
+        .get_result::<OutOfRange>(conn)?
+        .count;
+    }
+
+    Ok(total)
+}
+
+/// Moves `kind` onto a new scale, converting ratings recorded out of `from`
+/// into ratings out of `to` rounded to `step` (or the current step). When
+/// `above` is given only ratings greater than it are converted, which picks
+/// out the ones recorded on a larger scale than the rest. Returns the number
+/// of rows rescaled.
+pub fn rescale_ratings(
+    conn: &mut PgConnection,
+    kind: ItemKind,
+    from: Rating,
+    to: Rating,
+    step: Option<Rating>,
+    above: Option<Rating>,
+) -> QueryResult<usize> {
+    let tables = rated_tables(kind).ok_or(diesel::result::Error::NotFound)?;
+
+    conn.transaction(|conn| {
+        let scale = diesel::update(rating_scales::table.find(kind.table_name()))
+            .set(&UpdateRatingScale {
+                max_rating: Some(to),
+                step,
+            })
+            .get_result::<RatingScale>(conn)?;
+
+        let mut rescaled = 0;
+        for table in tables {
+            rescaled += diesel::sql_query(format!(
+                "UPDATE {table} \
+                 SET rating = LEAST($2, GREATEST(0, round(rating * $2 / $1 / $3) * $3)) \
+                 WHERE rating IS NOT NULL AND ($4 IS NULL OR rating > $4)"
+            ))
+            .bind::<Numeric, _>(from)
+            .bind::<Numeric, _>(scale.max_rating)
+            .bind::<Numeric, _>(scale.step)
+            .bind::<Nullable<Numeric>, _>(above)
+            .execute(conn)?;
+        }
+
+        Ok(rescaled)
+    })
+}
+
+#[get("/")]
+pub fn get_rating_scales() -> Result<Json<Vec<RatingScale>>, ApiError> {
+    let mut conn = connect_db()?;
+
+    rating_scales::table
+        .order(rating_scales::item_type.asc())
+        .load::<RatingScale>(&mut conn)
+        .map(Json)
+        .map_err(ApiError::from)
+}
+
+#[get("/<kind>")]
+pub fn get_rating_scale(kind: ItemKind) -> Result<Json<RatingScale>, ApiError> {
+    let mut conn = connect_db()?;
+
+    rating_scale(&mut conn, kind)
+        .map(Json)
+        .map_err(ApiError::from)
+}
+
+/// Changes a scale. Existing ratings must already fit it; use the
+/// `rescale-ratings` command to convert them otherwise.
+#[put("/<kind>", format = "json", data = "<changes>")]
+pub fn update_rating_scale(
+    _admin: AdminUser,
+    kind: ItemKind,
+    changes: Json<UpdateRatingScale>,
+) -> Result<Json<RatingScale>, ApiError> {
+    changes.validate()?;
+
+    let mut conn = connect_db()?;
+    let changes = changes.into_inner();
+
+    conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        let scale = diesel::update(rating_scales::table.find(kind.table_name()))
+            .set(&changes)
+            .get_result::<RatingScale>(conn)?;
+
+        if count_out_of_range(conn, kind, scale.max_rating, scale.step)? > 0 {
+            return Err(diesel::result::Error::RollbackTransaction);
+        }
+
+        Ok(scale)
+    })
+    .map(Json)
+    .map_err(|e| match e {
+        diesel::result::Error::RollbackTransaction => {
+            ApiError::conflict("existing ratings do not fit the new scale")
+        }
+        e => ApiError::from(e),
+    })
+}
+
+pub fn ratings_routes() -> Vec<Route> {
+    routes![get_rating_scales, get_rating_scale, update_rating_scale]
+}
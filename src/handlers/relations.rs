@@ -0,0 +1,395 @@
+use crate::auth::AdminUser;
+use crate::db::connect_db;
+use crate::errors::ApiError;
+use crate::handlers::ItemKind;
+use crate::handlers::identifiers::item_slugs;
+use crate::models::{NewRelation, Relation};
+use crate::schema::relations;
+use diesel::prelude::*;
+use rocket::request::FromParam;
+use rocket::serde::json::Json;
+use rocket::{Route, delete, get, post, routes};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::str::FromStr;
+
+/// How many hops `/relations/<kind>/<id>?depth=` may follow.
+const MAX_DEPTH: u32 = 3;
+
+/// How the source of a relation relates to its target, e.g. a game that is an
+/// `adaptation` of a book.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum RelationKind {
+    Adaptation,
+    AdaptedInto,
+    Sequel,
+    Prequel,
+    Similar,
+    SameUniverse,
+}
+
+impl RelationKind {
+    /// The same relation read from the other end.
+    fn inverse(self) -> Self {
+        match self {
+            RelationKind::Adaptation => RelationKind::AdaptedInto,
+            RelationKind::AdaptedInto => RelationKind::Adaptation,
+            RelationKind::Sequel => RelationKind::Prequel,
+            RelationKind::Prequel => RelationKind::Sequel,
+            RelationKind::Similar => RelationKind::Similar,
+            RelationKind::SameUniverse => RelationKind::SameUniverse,
+        }
+    }
+
+    fn is_symmetric(self) -> bool {
+        self.inverse() == self
+    }
+
+    /// Whether relations of this kind are stored as given rather than flipped
+    /// onto their inverse.
+    fn is_stored(self) -> bool {
+        !matches!(self, RelationKind::AdaptedInto | RelationKind::Prequel)
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            RelationKind::Adaptation => "adaptation",
+            RelationKind::AdaptedInto => "adapted-into",
+            RelationKind::Sequel => "sequel",
+            RelationKind::Prequel => "prequel",
+            RelationKind::Similar => "similar",
+            RelationKind::SameUniverse => "same-universe",
+        }
+    }
+}
+
+impl FromStr for RelationKind {
+    type Err = serde_json::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_value(serde_json::Value::String(s.to_string()))
+    }
+}
+
+/// One end of a relation, e.g. `{"type": "books", "id": 3}`.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Endpoint {
+    #[serde(rename = "type")]
+    kind: ItemKind,
+    id: i32,
+}
+
+impl Endpoint {
+    /// Orders endpoints the way the database compares `(type, id)` pairs.
+    fn key(self) -> (&'static str, i32) {
+        (self.kind.table_name(), self.id)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct NewRelationPayload {
+    source: Endpoint,
+    kind: RelationKind,
+    target: Endpoint,
+}
+
+#[derive(Serialize, Clone, Copy, Debug)]
+pub struct Edge {
+    id: i32,
+    source: Endpoint,
+    kind: RelationKind,
+    target: Endpoint,
+}
+
+impl Edge {
+    fn from_relation(relation: &Relation) -> Option<Self> {
+        Some(Edge {
+            id: relation.id,
+            source: Endpoint {
+                kind: ItemKind::from_param(&relation.source_type).ok()?,
+                id: relation.source_id,
+            },
+            kind: relation.kind.parse().ok()?,
+            target: Endpoint {
+                kind: ItemKind::from_param(&relation.target_type).ok()?,
+                id: relation.target_id,
+            },
+        })
+    }
+}
+
+#[derive(Serialize)]
+pub struct Node {
+    #[serde(flatten)]
+    endpoint: Endpoint,
+    slug: String,
+    /// Hops from the item the graph was requested for.
+    depth: u32,
+}
+
+/// A relation read from the item at the centre of the graph, so a book with
+/// a game adapted from it lists that game as `adapted-into`.
+#[derive(Serialize)]
+pub struct Neighbor {
+    relation_id: i32,
+    kind: RelationKind,
+    item: Endpoint,
+}
+
+#[derive(Serialize)]
+pub struct RelationGraph {
+    root: Endpoint,
+    relations: Vec<Neighbor>,
+    nodes: Vec<Node>,
+    edges: Vec<Edge>,
+}
+
+fn live_slugs(
+    conn: &mut PgConnection,
+    endpoints: &[Endpoint],
+) -> QueryResult<HashMap<Endpoint, String>> {
+    let mut by_kind: HashMap<ItemKind, Vec<i32>> = HashMap::new();
+    for endpoint in endpoints {
+        by_kind.entry(endpoint.kind).or_default().push(endpoint.id);
+    }
+
+    let mut slugs = HashMap::new();
+    for (kind, ids) in by_kind {
+        for (id, slug) in item_slugs(conn, kind, &ids)? {
+            slugs.insert(Endpoint { kind, id }, slug);
+        }
+    }
+
+    Ok(slugs)
+}
+
+/// Loads every relation with one of `endpoints` at either end.
+fn edges_touching(conn: &mut PgConnection, endpoints: &[Endpoint]) -> QueryResult<Vec<Edge>> {
+    let mut by_kind: HashMap<ItemKind, Vec<i32>> = HashMap::new();
+    for endpoint in endpoints {
+        by_kind.entry(endpoint.kind).or_default().push(endpoint.id);
+    }
+
+    let mut query = relations::table.into_boxed();
+    for (kind, ids) in by_kind {
+        query = query
+            .or_filter(
+                relations::source_type
+                    .eq(kind.table_name())
+                    .and(relations::source_id.eq_any(ids.clone())),
+            )
+            .or_filter(
+                relations::target_type
+                    .eq(kind.table_name())
+                    .and(relations::target_id.eq_any(ids)),
+            );
+    }
+
+    Ok(query
+        .order(relations::id.asc())
+        .load::<Relation>(conn)?
+        .iter()
+        .filter_map(Edge::from_relation)
+        .collect())
+}
+
+/// Moves the relations of item `from` onto `into` when the two are merged.
+/// Relations between the two are dropped, as are ones `into` already has.
+pub fn repoint_relations(
+    conn: &mut PgConnection,
+    kind: ItemKind,
+    from: i32,
+    into: i32,
+) -> QueryResult<()> {
+    let old = Endpoint { kind, id: from };
+    let new = Endpoint { kind, id: into };
+
+    let touching = edges_touching(conn, &[old])?;
+    let ids: Vec<i32> = touching.iter().map(|edge| edge.id).collect();
+    diesel::delete(relations::table.filter(relations::id.eq_any(ids))).execute(conn)?;
+
+    let repoint = |endpoint| if endpoint == old { new } else { endpoint };
+    for edge in touching {
+        let (mut source, mut target) = (repoint(edge.source), repoint(edge.target));
+        if source == target {
+            continue;
+        }
+        if edge.kind.is_symmetric() && source.key() > target.key() {
+            std::mem::swap(&mut source, &mut target);
+        }
+
+        diesel::insert_into(relations::table)
+            .values(&NewRelation {
+                source_type: source.kind.table_name(),
+                source_id: source.id,
+                kind: edge.kind.as_str(),
+                target_type: target.kind.table_name(),
+                target_id: target.id,
+            })
+            .on_conflict_do_nothing()
+            .execute(conn)?;
+    }
+
+    Ok(())
+}
+
+/// Walks outwards from `root` for up to `depth` hops. Trashed items are left
+/// out of the graph and not walked through.
+fn neighborhood(
+    conn: &mut PgConnection,
+    root: Endpoint,
+    root_slug: String,
+    depth: u32,
+) -> QueryResult<RelationGraph> {
+    let mut nodes = vec![Node {
+        endpoint: root,
+        slug: root_slug,
+        depth: 0,
+    }];
+    let mut seen = HashSet::from([root]);
+    let mut live = HashSet::from([root]);
+    let mut edges = BTreeMap::new();
+    let mut frontier = vec![root];
+
+    for level in 1..=depth {
+        if frontier.is_empty() {
+            break;
+        }
+
+        let touching = edges_touching(conn, &frontier)?;
+        let fresh: Vec<Endpoint> = touching
+            .iter()
+            .flat_map(|edge| [edge.source, edge.target])
+            .filter(|endpoint| seen.insert(*endpoint))
+            .collect();
+        let mut slugs = live_slugs(conn, &fresh)?;
+
+        frontier = Vec::new();
+        for endpoint in fresh {
+            if let Some(slug) = slugs.remove(&endpoint) {
+                nodes.push(Node {
+                    endpoint,
+                    slug,
+                    depth: level,
+                });
+                live.insert(endpoint);
+                frontier.push(endpoint);
+            }
+        }
+
+        for edge in touching {
+            if live.contains(&edge.source) && live.contains(&edge.target) {
+                edges.entry(edge.id).or_insert(edge);
+            }
+        }
+    }
+
+    let relations = edges
+        .values()
+        .filter_map(|edge| {
+            if edge.source == root {
+                Some((edge.kind, edge.target))
+            } else if edge.target == root {
+                Some((edge.kind.inverse(), edge.source))
+            } else {
+                None
+            }
+            .map(|(kind, item)| Neighbor {
+                relation_id: edge.id,
+                kind,
+                item,
+            })
+        })
+        .collect();
+
+    Ok(RelationGraph {
+        root,
+        relations,
+        nodes,
+        edges: edges.into_values().collect(),
+    })
+}
+
+#[get("/<kind>/<item_id>?<depth>")]
+pub fn get_relations(
+    kind: ItemKind,
+    item_id: i32,
+    depth: Option<u32>,
+) -> Result<Json<RelationGraph>, ApiError> {
+    let mut conn = connect_db()?;
+    let root = Endpoint { kind, id: item_id };
+
+    let root_slug = live_slugs(&mut conn, &[root])?
+        .remove(&root)
+        .ok_or(ApiError::NotFound)?;
+
+    let graph = neighborhood(
+        &mut conn,
+        root,
+        root_slug,
+        depth.unwrap_or(1).clamp(1, MAX_DEPTH),
+    )?;
+
+    Ok(Json(graph))
+}
+
+#[post("/", format = "json", data = "<relation>")]
+pub fn create_relation(
+    _admin: AdminUser,
+    relation: Json<NewRelationPayload>,
+) -> Result<Json<Edge>, ApiError> {
+    let NewRelationPayload {
+        mut source,
+        mut kind,
+        mut target,
+    } = relation.into_inner();
+
+    if source == target {
+        return Err(ApiError::validation("an item can't be related to itself"));
+    }
+
+    if !kind.is_stored() {
+        (source, kind, target) = (target, kind.inverse(), source);
+    }
+    if kind.is_symmetric() && source.key() > target.key() {
+        std::mem::swap(&mut source, &mut target);
+    }
+
+    let mut conn = connect_db()?;
+
+    let found = live_slugs(&mut conn, &[source, target])?;
+    if found.len() < 2 {
+        return Err(ApiError::NotFound);
+    }
+
+    let created = diesel::insert_into(relations::table)
+        .values(&NewRelation {
+            source_type: source.kind.table_name(),
+            source_id: source.id,
+            kind: kind.as_str(),
+            target_type: target.kind.table_name(),
+            target_id: target.id,
+        })
+        .get_result::<Relation>(&mut conn)?;
+
+    Edge::from_relation(&created)
+        .map(Json)
+        .ok_or(ApiError::Internal)
+}
+
+#[delete("/<relation_id>")]
+pub fn delete_relation(_admin: AdminUser, relation_id: i32) -> Result<Json<Edge>, ApiError> {
+    let mut conn = connect_db()?;
+
+    let deleted =
+        diesel::delete(relations::table.find(relation_id)).get_result::<Relation>(&mut conn)?;
+
+    Edge::from_relation(&deleted)
+        .map(Json)
+        .ok_or(ApiError::Internal)
+}
+
+pub fn relations_routes() -> Vec<Route> {
+    routes![get_relations, create_relation, delete_relation]
+}
@@ -1,19 +1,139 @@
-use crate::auth::User;
+use crate::auth::{AdminUser, User};
 use crate::db::connect_db;
-use crate::models::{NewReview, Review, UpdateReview};
-use crate::schema::reviews;
+use crate::errors::ApiError;
+use crate::handlers::accounts::require_owner;
+use crate::handlers::etags::{Created, IfMatch, Tagged};
+use crate::handlers::identifiers::{ItemRef, Located, Resolved, resolve_ref};
+use crate::handlers::ratings::{Rated, rated, rating_scale};
+use crate::handlers::{ItemKind, parse_timestamp};
+use crate::models::{NewReview, Rating, Review, UpdateReview};
+use crate::schema::{books, games, reviews};
+use diesel::dsl::{count_star, exists};
 use diesel::prelude::*;
-use rocket::http::Status;
+use diesel::result::{DatabaseErrorKind, Error};
+use rocket::Either;
 use rocket::serde::json::Json;
 use rocket::{FromForm, delete, get, options, patch, post, put, routes};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use validator::Validate;
 
 #[derive(FromForm, Debug)]
 pub struct ReviewQuery {
     chapter: Option<i32>,
-    rating: Option<i32>,
+    rating: Option<f64>,
     description: Option<String>,
     thoughts: Option<String>,
     is_first_chapter: Option<bool>,
+    #[field(name = "bookId")]
+    book_id: Option<i32>,
+    #[field(name = "gameId")]
+    game_id: Option<i32>,
+    #[field(name = "createdAfter")]
+    created_after: Option<String>,
+    #[field(name = "updatedSince")]
+    updated_since: Option<String>,
+    /// Limits results to one account's library, as `/users/<handle>/...` does.
+    #[field(name = "ownerId")]
+    pub(crate) owner_id: Option<i32>,
+    sort: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct AttachReviews {
+    review_ids: Vec<i32>,
+    book_id: Option<i32>,
+    game_id: Option<i32>,
+}
+
+/// Review count and average chapter rating for a book or game.
+#[derive(Serialize, Default, Clone, Copy, Debug)]
+pub struct ReviewSummary {
+    count: i64,
+    average_rating: Option<f64>,
+}
+
+impl ReviewSummary {
+    fn from_totals(total: Option<Rating>, count: i64) -> Self {
+        Self {
+            count,
+            average_rating: total
+                .filter(|_| count > 0)
+                .map(|total| total.0 / count as f64),
+        }
+    }
+}
+
+pub fn book_review_summaries(
+    conn: &mut PgConnection,
+    book_ids: &[i32],
+) -> QueryResult<HashMap<i32, ReviewSummary>> {
+    let totals = reviews::table
+        .filter(reviews::book_id.eq_any(book_ids))
+        .filter(reviews::deleted_at.is_null())
+        .group_by(reviews::book_id)
+        .select((
+            reviews::book_id,
+            diesel::dsl::sum(reviews::rating),
+            count_star(),
+        ))
+        .load::<(Option<i32>, Option<Rating>, i64)>(conn)?;
+
+    Ok(totals
+        .into_iter()
+        .filter_map(|(book_id, total, count)| {
+            book_id.map(|book_id| (book_id, ReviewSummary::from_totals(total, count)))
+        })
+        .collect())
+}
+
+pub fn game_review_summaries(
+    conn: &mut PgConnection,
+    game_ids: &[i32],
+) -> QueryResult<HashMap<i32, ReviewSummary>> {
+    let totals = reviews::table
+        .filter(reviews::game_id.eq_any(game_ids))
+        .filter(reviews::deleted_at.is_null())
+        .group_by(reviews::game_id)
+        .select((
+            reviews::game_id,
+            diesel::dsl::sum(reviews::rating),
+            count_star(),
+        ))
+        .load::<(Option<i32>, Option<Rating>, i64)>(conn)?;
+
+    Ok(totals
+        .into_iter()
+        .filter_map(|(game_id, total, count)| {
+            game_id.map(|game_id| (game_id, ReviewSummary::from_totals(total, count)))
+        })
+        .collect())
+}
+
+/// A review pointing at a book or game that doesn't exist is a 404 rather
+/// than a constraint violation.
+fn map_review_error(error: Error) -> ApiError {
+    match error {
+        Error::DatabaseError(DatabaseErrorKind::ForeignKeyViolation, _) => ApiError::NotFound,
+        error => ApiError::from(error),
+    }
+}
+
+/// Attaching a review to a book or game counts as changing it, so `user` has
+/// to be able to edit whichever one is given.
+fn require_target_owner(
+    conn: &mut PgConnection,
+    user: &User,
+    book_id: Option<i32>,
+    game_id: Option<i32>,
+) -> Result<(), ApiError> {
+    if let Some(book_id) = book_id {
+        require_owner(conn, user, ItemKind::Books, book_id)?;
+    }
+    if let Some(game_id) = game_id {
+        require_owner(conn, user, ItemKind::Games, game_id)?;
+    }
+    Ok(())
 }
 
 #[options("/")]
@@ -30,37 +150,44 @@ pub fn reviews_opts() -> Json<Vec<(&'static str, &'static str)>> {
 }
 
 #[post("/", format = "json", data = "<new_review>")]
-pub fn create_review(_user: User, new_review: Json<NewReview<'_>>) -> Json<Review> {
-    _user.require_admin().expect("User is not admin");
-    let mut conn = connect_db();
+pub fn create_review(
+    user: User,
+    new_review: Json<NewReview<'_>>,
+) -> Result<Created<Rated<Review>>, ApiError> {
+    new_review.validate()?;
+
+    let mut conn = connect_db()?;
     let new_review = new_review.into_inner();
-    diesel::insert_into(reviews::table)
-        .values(&new_review)
-        .execute(&mut conn)
-        .expect("Error inserting new review");
-
-    Json(Review {
-        id: 0,
-        chapter: new_review.chapter,
-        description: new_review.description.to_string(),
-        rating: new_review.rating,
-        thoughts: new_review.thoughts.to_string(),
-    })
+    require_target_owner(&mut conn, &user, new_review.book_id, new_review.game_id)?;
+
+    let review = diesel::insert_into(reviews::table)
+        .values((&new_review, reviews::owner_id.eq(user.account_id())))
+        .get_result::<Review>(&mut conn)
+        .map_err(map_review_error)?;
+
+    let location = ItemKind::Reviews.location(review.id);
+    let response = rated(&mut conn, ItemKind::Reviews, review.rating, review)?;
+
+    Ok(Created::new(location, response))
 }
 
 #[get("/search?<query..>")]
-pub fn search_reviews(query: ReviewQuery) -> Result<Json<Vec<Review>>, Status> {
+pub fn search_reviews(query: ReviewQuery) -> Result<Json<Vec<Rated<Review>>>, ApiError> {
     use crate::schema::reviews::dsl::*;
 
-    let mut conn = connect_db();
-    let mut review_query = reviews.into_boxed();
+    let mut conn = connect_db()?;
+    let mut review_query = reviews.filter(deleted_at.is_null()).into_boxed();
+
+    if let Some(owner_filter) = query.owner_id {
+        review_query = review_query.filter(owner_id.eq(owner_filter));
+    }
 
     if let Some(chapter_filter) = &query.chapter {
         review_query = review_query.filter(chapter.eq(chapter_filter));
     }
 
-    if let Some(rating_filter) = &query.rating {
-        review_query = review_query.filter(rating.eq(rating_filter));
+    if let Some(rating_filter) = query.rating {
+        review_query = review_query.filter(rating.eq(Rating(rating_filter)));
     }
 
     if let Some(description_filter) = &query.description {
@@ -75,80 +202,332 @@ pub fn search_reviews(query: ReviewQuery) -> Result<Json<Vec<Review>>, Status> {
         review_query = review_query.filter(chapter.eq(1));
     }
 
-    let results = review_query
-        .load::<Review>(&mut conn)
-        .map_err(|_| Status::InternalServerError)?;
+    if let Some(book_filter) = query.book_id {
+        review_query = review_query.filter(book_id.eq(book_filter));
+    }
+
+    if let Some(game_filter) = query.game_id {
+        review_query = review_query.filter(game_id.eq(game_filter));
+    }
+
+    if let Some(created_after_filter) = &query.created_after {
+        let timestamp = parse_timestamp(created_after_filter)
+            .ok_or_else(|| ApiError::bad_request("invalid createdAfter timestamp"))?;
+        review_query = review_query.filter(created_at.gt(timestamp));
+    }
+
+    if let Some(updated_since_filter) = &query.updated_since {
+        let timestamp = parse_timestamp(updated_since_filter)
+            .ok_or_else(|| ApiError::bad_request("invalid updatedSince timestamp"))?;
+        review_query = review_query.filter(updated_at.ge(timestamp));
+    }
+
+    if let Some(sort_by) = &query.sort {
+        match sort_by.as_str() {
+            "chapter" => review_query = review_query.order(chapter.asc()),
+            "rating" => review_query = review_query.order(rating.desc()),
+            "created" => review_query = review_query.order(created_at.desc()),
+            "updated" => review_query = review_query.order(updated_at.desc()),
+            _ => {}
+        }
+    }
+
+    let results = review_query.load::<Review>(&mut conn)?;
 
     let filtered_reviews = results;
 
-    Ok(Json(filtered_reviews))
+    let scale = rating_scale(&mut conn, ItemKind::Reviews)?;
+
+    Ok(Json(
+        filtered_reviews
+            .into_iter()
+            .map(|review| Rated::new(&scale, review.rating, review))
+            .collect(),
+    ))
 }
 
-#[get("/<review_id>")]
-pub fn get_review_by_id(review_id: i32) -> Result<Json<Review>, Status> {
-    use crate::schema::reviews::dsl::*;
-    let mut conn = connect_db();
-    let review = reviews
-        .filter(id.eq(&review_id))
-        .first::<Review>(&mut conn)
-        .map_err(|_| Status::NotFound)?;
+/// A live review as `GET /reviews/<id>` returns it.
+fn current_review(conn: &mut PgConnection, review_id: i32) -> Result<Rated<Review>, ApiError> {
+    let review = reviews::table
+        .find(review_id)
+        .filter(reviews::deleted_at.is_null())
+        .first::<Review>(conn)?;
+
+    Ok(rated(conn, ItemKind::Reviews, review.rating, review)?)
+}
+
+#[get("/<review_ref>")]
+pub fn get_review_by_id(review_ref: ItemRef) -> Result<Located<Rated<Review>>, ApiError> {
+    let mut conn = connect_db()?;
 
-    Ok(Json(review))
+    let review_id = match resolve_ref(&mut conn, ItemKind::Reviews, &review_ref)? {
+        Resolved::Found(found) => found,
+        Resolved::Moved(redirect) => return Ok(Either::Right(*redirect)),
+    };
+
+    let response = current_review(&mut conn, review_id)?;
+
+    Ok(Either::Left(Tagged::new(response)))
 }
 
 #[get("/")]
-pub fn get_reviews() -> Option<Json<Vec<Review>>> {
-    let mut conn = connect_db();
-    reviews::table.load::<Review>(&mut conn).ok().map(Json)
+pub fn get_reviews() -> Result<Json<Vec<Review>>, ApiError> {
+    let mut conn = connect_db()?;
+    reviews::table
+        .filter(reviews::deleted_at.is_null())
+        .load::<Review>(&mut conn)
+        .map(Json)
+        .map_err(ApiError::from)
+}
+
+#[get("/orphans")]
+pub fn get_orphan_reviews(_admin: AdminUser) -> Result<Json<Vec<Review>>, ApiError> {
+    let mut conn = connect_db()?;
+
+    let orphans = reviews::table
+        .filter(reviews::book_id.is_null())
+        .filter(reviews::game_id.is_null())
+        .filter(reviews::deleted_at.is_null())
+        .order(reviews::chapter.asc())
+        .load::<Review>(&mut conn)?;
+
+    Ok(Json(orphans))
+}
+
+#[post("/attach", format = "json", data = "<attach>")]
+pub fn attach_reviews(
+    _admin: AdminUser,
+    attach: Json<AttachReviews>,
+) -> Result<Json<Vec<Review>>, ApiError> {
+    let attach = attach.into_inner();
+    if attach.book_id.is_some() == attach.game_id.is_some() {
+        return Err(ApiError::validation(
+            "reviews are attached to exactly one of a book or a game",
+        ));
+    }
+
+    let mut conn = connect_db()?;
+
+    let attached = diesel::update(
+        reviews::table
+            .filter(reviews::id.eq_any(&attach.review_ids))
+            .filter(reviews::deleted_at.is_null()),
+    )
+    .set((
+        reviews::book_id.eq(attach.book_id),
+        reviews::game_id.eq(attach.game_id),
+    ))
+    .get_results::<Review>(&mut conn)
+    .map_err(map_review_error)?;
+
+    Ok(Json(attached))
 }
 
 #[put("/<id>", format = "json", data = "<update_data>")]
 pub fn update_review(
-    _user: User,
+    user: User,
+    if_match: IfMatch,
     id: i32,
     update_data: Json<UpdateReview>,
-) -> Option<Json<Review>> {
-    _user.require_admin().expect("User is not admin");
-    let mut conn = connect_db();
-    diesel::update(reviews::table.find(id))
-        .set(&update_data.into_inner())
-        .execute(&mut conn)
-        .ok()?;
+) -> Result<Tagged<Rated<Review>>, ApiError> {
+    let update_data = update_data.into_inner();
+    let mut conn = connect_db()?;
+    require_owner(&mut conn, &user, ItemKind::Reviews, id)?;
+    update_data.validate()?;
+    require_target_owner(&mut conn, &user, update_data.book_id, update_data.game_id)?;
 
-    reviews::table
-        .find(id)
-        .first::<Review>(&mut conn)
-        .ok()
-        .map(Json)
+    let response = conn.transaction::<_, ApiError, _>(|conn| {
+        if_match.check(conn, ItemKind::Reviews, id, current_review)?;
+        let review = diesel::update(
+            reviews::table
+                .find(id)
+                .filter(reviews::deleted_at.is_null()),
+        )
+        .set(&update_data)
+        .get_result::<Review>(conn)
+        .map_err(map_review_error)?;
+        Ok(rated(conn, ItemKind::Reviews, review.rating, review)?)
+    })?;
+
+    Ok(Tagged::new(response))
 }
 
 #[delete("/<id>")]
-pub fn delete_review(_user: User, id: i32) -> Option<Json<Review>> {
-    _user.require_admin().expect("User is not admin");
-    let mut conn = connect_db();
-    let review = reviews::table.find(id).first::<Review>(&mut conn).ok()?;
-    diesel::delete(reviews::table.find(id))
-        .execute(&mut conn)
-        .ok()?;
+pub fn delete_review(user: User, if_match: IfMatch, id: i32) -> Result<Json<Review>, ApiError> {
+    let mut conn = connect_db()?;
+    require_owner(&mut conn, &user, ItemKind::Reviews, id)?;
 
-    Some(Json(review))
+    conn.transaction::<_, ApiError, _>(|conn| {
+        if_match.check(conn, ItemKind::Reviews, id, current_review)?;
+        diesel::update(
+            reviews::table
+                .find(id)
+                .filter(reviews::deleted_at.is_null()),
+        )
+        .set(reviews::deleted_at.eq(diesel::dsl::now.nullable()))
+        .get_result::<Review>(conn)
+        .map_err(map_review_error)
+    })
+    .map(Json)
+}
+
+#[post("/<id>/restore")]
+pub fn restore_review(user: User, id: i32) -> Result<Json<Review>, ApiError> {
+    let mut conn = connect_db()?;
+    require_owner(&mut conn, &user, ItemKind::Reviews, id)?;
+
+    diesel::update(
+        reviews::table
+            .find(id)
+            .filter(reviews::deleted_at.is_not_null()),
+    )
+    .set(reviews::deleted_at.eq(None::<chrono::NaiveDateTime>))
+    .get_result::<Review>(&mut conn)
+    .map(Json)
+    .map_err(ApiError::from)
 }
 
 #[patch("/<id>", format = "json", data = "<update_data>")]
-pub fn patch_review(_user: User, id: i32, update_data: Json<UpdateReview>) -> Option<Json<Review>> {
-    _user.require_admin().expect("User is not admin");
-    let mut conn = connect_db();
+pub fn patch_review(
+    user: User,
+    if_match: IfMatch,
+    id: i32,
+    update_data: Json<UpdateReview>,
+) -> Result<Tagged<Rated<Review>>, ApiError> {
+    let update_data = update_data.into_inner();
+    let mut conn = connect_db()?;
+    require_owner(&mut conn, &user, ItemKind::Reviews, id)?;
+    update_data.validate()?;
+    require_target_owner(&mut conn, &user, update_data.book_id, update_data.game_id)?;
 
-    diesel::update(reviews::table.find(id))
-        .set(&update_data.into_inner())
-        .execute(&mut conn)
-        .ok()?;
+    let response = conn.transaction::<_, ApiError, _>(|conn| {
+        if_match.check(conn, ItemKind::Reviews, id, current_review)?;
+        let review = diesel::update(
+            reviews::table
+                .find(id)
+                .filter(reviews::deleted_at.is_null()),
+        )
+        .set(&update_data)
+        .get_result::<Review>(conn)
+        .map_err(map_review_error)?;
+        Ok(rated(conn, ItemKind::Reviews, review.rating, review)?)
+    })?;
 
-    reviews::table
-        .find(id)
-        .first::<Review>(&mut conn)
-        .ok()
-        .map(Json)
+    Ok(Tagged::new(response))
+}
+
+#[get("/<book_id>/reviews")]
+pub fn get_book_reviews(book_id: i32) -> Result<Json<Vec<Review>>, ApiError> {
+    let mut conn = connect_db()?;
+
+    let results = reviews::table
+        .filter(reviews::book_id.eq(book_id))
+        .filter(reviews::deleted_at.is_null())
+        .order(reviews::chapter.asc())
+        .load::<Review>(&mut conn)?;
+
+    Ok(Json(results))
+}
+
+#[post("/<book_id>/reviews", format = "json", data = "<new_review>")]
+pub fn create_book_review(
+    user: User,
+    book_id: i32,
+    new_review: Json<NewReview<'_>>,
+) -> Result<Created<Rated<Review>>, ApiError> {
+    let mut conn = connect_db()?;
+    require_owner(&mut conn, &user, ItemKind::Books, book_id)?;
+    new_review.validate()?;
+
+    let book_exists = diesel::select(exists(
+        books::table
+            .find(book_id)
+            .filter(books::deleted_at.is_null()),
+    ))
+    .get_result::<bool>(&mut conn)?;
+
+    if !book_exists {
+        return Err(ApiError::NotFound);
+    }
+
+    let review = diesel::insert_into(reviews::table)
+        .values((
+            &NewReview {
+                book_id: Some(book_id),
+                game_id: None,
+                ..new_review.into_inner()
+            },
+            reviews::owner_id.eq(user.account_id()),
+        ))
+        .get_result::<Review>(&mut conn)
+        .map_err(map_review_error)?;
+
+    let location = ItemKind::Reviews.location(review.id);
+    let response = rated(&mut conn, ItemKind::Reviews, review.rating, review)?;
+
+    Ok(Created::new(location, response))
+}
+
+#[get("/<game_id>/reviews")]
+pub fn get_game_reviews(game_id: i32) -> Result<Json<Vec<Review>>, ApiError> {
+    let mut conn = connect_db()?;
+
+    let results = reviews::table
+        .filter(reviews::game_id.eq(game_id))
+        .filter(reviews::deleted_at.is_null())
+        .order(reviews::chapter.asc())
+        .load::<Review>(&mut conn)?;
+
+    Ok(Json(results))
+}
+
+#[post("/<game_id>/reviews", format = "json", data = "<new_review>")]
+pub fn create_game_review(
+    user: User,
+    game_id: i32,
+    new_review: Json<NewReview<'_>>,
+) -> Result<Created<Rated<Review>>, ApiError> {
+    let mut conn = connect_db()?;
+    require_owner(&mut conn, &user, ItemKind::Games, game_id)?;
+    new_review.validate()?;
+
+    let game_exists = diesel::select(exists(
+        games::table
+            .find(game_id)
+            .filter(games::deleted_at.is_null()),
+    ))
+    .get_result::<bool>(&mut conn)?;
+
+    if !game_exists {
+        return Err(ApiError::NotFound);
+    }
+
+    let review = diesel::insert_into(reviews::table)
+        .values((
+            &NewReview {
+                book_id: None,
+                game_id: Some(game_id),
+                ..new_review.into_inner()
+            },
+            reviews::owner_id.eq(user.account_id()),
+        ))
+        .get_result::<Review>(&mut conn)
+        .map_err(map_review_error)?;
+
+    let location = ItemKind::Reviews.location(review.id);
+    let response = rated(&mut conn, ItemKind::Reviews, review.rating, review)?;
+
+    Ok(Created::new(location, response))
+}
+
+/// Review routes nested under a book, mounted at `/read-watch`.
+pub fn book_reviews_routes() -> Vec<rocket::Route> {
+    routes![get_book_reviews, create_book_review]
+}
+
+/// Review routes nested under a game, mounted at `/games`.
+pub fn game_reviews_routes() -> Vec<rocket::Route> {
+    routes![get_game_reviews, create_game_review]
 }
 
 pub fn reviews_routes() -> Vec<rocket::Route> {
@@ -157,9 +536,12 @@ pub fn reviews_routes() -> Vec<rocket::Route> {
         create_review,
         search_reviews,
         get_reviews,
+        get_orphan_reviews,
+        attach_reviews,
         get_review_by_id,
         update_review,
         delete_review,
+        restore_review,
         patch_review,
     ]
 }
@@ -0,0 +1,464 @@
+use crate::auth::{AdminUser, User};
+use crate::db::connect_db;
+use crate::errors::ApiError;
+use crate::handlers::ItemKind;
+use crate::handlers::accounts::require_owner;
+use crate::handlers::books::apply_book_update;
+use crate::handlers::extra::validate_extra;
+use crate::handlers::games::apply_game_update;
+use crate::models::{
+    Album, Book, Game, Movie, Project, Review, Revision, Show, UpdateAlbum, UpdateBook, UpdateGame,
+    UpdateMovie, UpdateProject, UpdateReview, UpdateShow, UpdateWplaceScreenshot, WplaceScreenshot,
+};
+use crate::schema::{albums, books, games, movies, projects, reviews, revisions, shows, wplace};
+use diesel::prelude::*;
+use rocket::serde::json::Json;
+use rocket::{get, post, routes};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+use validator::Validate;
+
+#[derive(Serialize)]
+pub struct FieldChange {
+    field: String,
+    from: Value,
+    to: Value,
+}
+
+#[derive(Serialize)]
+pub struct RevisionDiff {
+    from: i32,
+    /// `None` when the diff was taken against the item's current state.
+    to: Option<i32>,
+    changes: Vec<FieldChange>,
+}
+
+fn to_json<T: Serialize>(item: QueryResult<T>) -> Result<Value, ApiError> {
+    let item = item?;
+    serde_json::to_value(item).map_err(|_| ApiError::Internal)
+}
+
+fn from_snapshot<T: DeserializeOwned + Validate>(data: Value) -> Result<T, ApiError> {
+    let changes: T = serde_json::from_value(data)
+        .map_err(|e| ApiError::validation(format!("revision can't be restored: {e}")))?;
+    changes.validate()?;
+    Ok(changes)
+}
+
+fn find_revision(
+    conn: &mut PgConnection,
+    kind: ItemKind,
+    item_id: i32,
+    number: i32,
+) -> Result<Revision, ApiError> {
+    revisions::table
+        .filter(revisions::item_type.eq(kind.table_name()))
+        .filter(revisions::item_id.eq(item_id))
+        .filter(revisions::revision.eq(number))
+        .first::<Revision>(conn)
+        .map_err(ApiError::from)
+}
+
+fn load_item(conn: &mut PgConnection, kind: ItemKind, item_id: i32) -> Result<Value, ApiError> {
+    match kind {
+        ItemKind::Albums => to_json(albums::table.find(item_id).first::<Album>(conn)),
+        ItemKind::Books => to_json(books::table.find(item_id).first::<Book>(conn)),
+        ItemKind::Games => to_json(games::table.find(item_id).first::<Game>(conn)),
+        ItemKind::Movies => to_json(movies::table.find(item_id).first::<Movie>(conn)),
+        ItemKind::Shows => to_json(shows::table.find(item_id).first::<Show>(conn)),
+        ItemKind::Projects => to_json(projects::table.find(item_id).first::<Project>(conn)),
+        ItemKind::Reviews => to_json(reviews::table.find(item_id).first::<Review>(conn)),
+        ItemKind::Wplace => to_json(wplace::table.find(item_id).first::<WplaceScreenshot>(conn)),
+    }
+}
+
+/// Writes the editable fields of a snapshot back onto the live row. The
+/// `record_revision` trigger stores the state being replaced as a new revision.
+/// Books and games go through their regular update so their genre and tag
+/// links follow the restored arrays. A restored `extra` object is checked
+/// against the current schema, which may have changed since the snapshot.
+fn apply_snapshot(
+    conn: &mut PgConnection,
+    kind: ItemKind,
+    item_id: i32,
+    data: Value,
+) -> Result<Value, ApiError> {
+    match kind {
+        ItemKind::Albums => {
+            let changes = from_snapshot::<UpdateAlbum>(data)?;
+            to_json(
+                diesel::update(
+                    albums::table
+                        .find(item_id)
+                        .filter(albums::deleted_at.is_null()),
+                )
+                .set(&changes)
+                .get_result::<Album>(conn),
+            )
+        }
+        ItemKind::Books => {
+            let changes = from_snapshot::<UpdateBook>(data)?;
+            if let Some(extra) = &changes.extra {
+                validate_extra(conn, ItemKind::Books, extra)?;
+            }
+            to_json(apply_book_update(conn, item_id, changes))
+        }
+        ItemKind::Games => {
+            let changes = from_snapshot::<UpdateGame>(data)?;
+            if let Some(extra) = &changes.extra {
+                validate_extra(conn, ItemKind::Games, extra)?;
+            }
+            to_json(apply_game_update(conn, item_id, changes))
+        }
+        ItemKind::Movies => {
+            let changes = from_snapshot::<UpdateMovie>(data)?;
+            to_json(
+                diesel::update(
+                    movies::table
+                        .find(item_id)
+                        .filter(movies::deleted_at.is_null()),
+                )
+                .set(&changes)
+                .get_result::<Movie>(conn),
+            )
+        }
+        ItemKind::Shows => {
+            let changes = from_snapshot::<UpdateShow>(data)?;
+            to_json(
+                diesel::update(
+                    shows::table
+                        .find(item_id)
+                        .filter(shows::deleted_at.is_null()),
+                )
+                .set(&changes)
+                .get_result::<Show>(conn),
+            )
+        }
+        ItemKind::Projects => {
+            let changes = from_snapshot::<UpdateProject>(data)?;
+            if let Some(extra) = &changes.extra {
+                validate_extra(conn, ItemKind::Projects, extra)?;
+            }
+            to_json(
+                diesel::update(
+                    projects::table
+                        .find(item_id)
+                        .filter(projects::deleted_at.is_null()),
+                )
+                .set(&changes)
+                .get_result::<Project>(conn),
+            )
+        }
+        ItemKind::Reviews => {
+            let changes = from_snapshot::<UpdateReview>(data)?;
+            to_json(
+                diesel::update(
+                    reviews::table
+                        .find(item_id)
+                        .filter(reviews::deleted_at.is_null()),
+                )
+                .set(&changes)
+                .get_result::<Review>(conn),
+            )
+        }
+        ItemKind::Wplace => {
+            let changes = from_snapshot::<UpdateWplaceScreenshot>(data)?;
+            to_json(
+                diesel::update(
+                    wplace::table
+                        .find(item_id)
+                        .filter(wplace::deleted_at.is_null()),
+                )
+                .set(&changes)
+                .get_result::<WplaceScreenshot>(conn),
+            )
+        }
+    }
+}
+
+/// Revisions are readable by the item's owner and by admins. Admins can also
+/// read the history of items that have since been purged.
+fn require_history_access(
+    conn: &mut PgConnection,
+    user: &User,
+    kind: ItemKind,
+    item_id: i32,
+) -> Result<(), ApiError> {
+    if user.is_admin() {
+        return Ok(());
+    }
+
+    require_owner(conn, user, kind, item_id)
+}
+
+fn diff_snapshots(from: &Value, to: &Value) -> Vec<FieldChange> {
+    let empty = Map::new();
+    let from = from.as_object().unwrap_or(&empty);
+    let to = to.as_object().unwrap_or(&empty);
+
+    let mut fields: Vec<&String> = from.keys().chain(to.keys()).collect();
+    fields.sort();
+    fields.dedup();
+
+    fields
+        .into_iter()
+        .filter_map(|field| {
+            let before = from.get(field).cloned().unwrap_or(Value::Null);
+            let after = to.get(field).cloned().unwrap_or(Value::Null);
+
+            (before != after).then(|| FieldChange {
+                field: field.clone(),
+                from: before,
+                to: after,
+            })
+        })
+        .collect()
+}
+
+#[get("/<kind>/<item_id>")]
+pub fn list_revisions(
+    user: User,
+    kind: ItemKind,
+    item_id: i32,
+) -> Result<Json<Vec<Revision>>, ApiError> {
+    let mut conn = connect_db()?;
+    require_history_access(&mut conn, &user, kind, item_id)?;
+
+    let results = revisions::table
+        .filter(revisions::item_type.eq(kind.table_name()))
+        .filter(revisions::item_id.eq(item_id))
+        .order(revisions::revision.desc())
+        .load::<Revision>(&mut conn)?;
+
+    Ok(Json(results))
+}
+
+#[get("/<kind>/<item_id>/<number>")]
+pub fn get_revision(
+    user: User,
+    kind: ItemKind,
+    item_id: i32,
+    number: i32,
+) -> Result<Json<Revision>, ApiError> {
+    let mut conn = connect_db()?;
+    require_history_access(&mut conn, &user, kind, item_id)?;
+    find_revision(&mut conn, kind, item_id, number).map(Json)
+}
+
+#[get("/<kind>/<item_id>/diff?<from>&<to>")]
+pub fn diff_revisions(
+    user: User,
+    kind: ItemKind,
+    item_id: i32,
+    from: i32,
+    to: Option<i32>,
+) -> Result<Json<RevisionDiff>, ApiError> {
+    let mut conn = connect_db()?;
+    require_history_access(&mut conn, &user, kind, item_id)?;
+
+    let before = find_revision(&mut conn, kind, item_id, from)?.data;
+    let after = match to {
+        Some(number) => find_revision(&mut conn, kind, item_id, number)?.data,
+        None => load_item(&mut conn, kind, item_id)?,
+    };
+
+    Ok(Json(RevisionDiff {
+        from,
+        to,
+        changes: diff_snapshots(&before, &after),
+    }))
+}
+
+#[post("/<kind>/<item_id>/<number>/rollback")]
+pub fn rollback_revision(
+    _admin: AdminUser,
+    kind: ItemKind,
+    item_id: i32,
+    number: i32,
+) -> Result<Json<Value>, ApiError> {
+    let mut conn = connect_db()?;
+
+    let revision = find_revision(&mut conn, kind, item_id, number)?;
+    let item = apply_snapshot(&mut conn, kind, item_id, revision.data)?;
+
+    Ok(Json(item))
+}
+
+pub fn revisions_routes() -> Vec<rocket::Route> {
+    routes![
+        list_revisions,
+        get_revision,
+        diff_revisions,
+        rollback_revision
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply_snapshot, diff_snapshots, find_revision};
+    use crate::db::connect_db;
+    use crate::handlers::ItemKind;
+    use crate::handlers::books::apply_book_update;
+    use crate::handlers::games::apply_game_update;
+    use crate::models::{Book, Game, NewBook, NewGame, UpdateBook, UpdateGame};
+    use crate::schema::{accounts, book_tags, books, game_tags, games, tags};
+    use diesel::prelude::*;
+    use serde_json::json;
+
+    fn fields(from: serde_json::Value, to: serde_json::Value) -> Vec<String> {
+        diff_snapshots(&from, &to)
+            .into_iter()
+            .map(|change| change.field)
+            .collect()
+    }
+
+    #[test]
+    fn identical_snapshots_have_no_changes() {
+        let snapshot = json!({"title": "Dune", "rating": 4, "tags": ["sci-fi"]});
+        assert!(diff_snapshots(&snapshot, &snapshot).is_empty());
+    }
+
+    #[test]
+    fn reports_changed_fields_in_name_order() {
+        let changes = diff_snapshots(
+            &json!({"title": "Dune", "rating": 4, "author": "Herbert"}),
+            &json!({"title": "Dune Messiah", "rating": 5, "author": "Herbert"}),
+        );
+
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].field, "rating");
+        assert_eq!(changes[0].from, json!(4));
+        assert_eq!(changes[0].to, json!(5));
+        assert_eq!(changes[1].field, "title");
+        assert_eq!(changes[1].from, json!("Dune"));
+        assert_eq!(changes[1].to, json!("Dune Messiah"));
+    }
+
+    #[test]
+    fn missing_fields_compare_as_null() {
+        let changes = diff_snapshots(&json!({"title": "Dune"}), &json!({"extra": {"isbn": "1"}}));
+
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].field, "extra");
+        assert_eq!(changes[0].from, json!(null));
+        assert_eq!(changes[1].field, "title");
+        assert_eq!(changes[1].to, json!(null));
+
+        assert!(fields(json!({"cover": null}), json!({})).is_empty());
+    }
+
+    #[test]
+    fn nested_values_are_compared_whole() {
+        assert_eq!(
+            fields(json!({"tags": ["a", "b"]}), json!({"tags": ["b", "a"]})),
+            ["tags"]
+        );
+    }
+
+    #[test]
+    fn non_object_snapshots_are_treated_as_empty() {
+        assert_eq!(fields(json!(null), json!({"title": "Dune"})), ["title"]);
+        assert!(fields(json!([1, 2]), json!("x")).is_empty());
+    }
+
+    /// Runs `test` against the database at `DATABASE_URL`, rolling back
+    /// everything it wrote.
+    fn with_database(test: impl FnOnce(&mut PgConnection)) {
+        connect_db()
+            .unwrap()
+            .test_transaction::<_, diesel::result::Error, _>(|conn| {
+                test(conn);
+                Ok(())
+            });
+    }
+
+    fn test_account(conn: &mut PgConnection) -> i32 {
+        diesel::insert_into(accounts::table)
+            .values(accounts::handle.eq("rollback-test"))
+            .returning(accounts::id)
+            .get_result(conn)
+            .unwrap()
+    }
+
+    #[test]
+    #[ignore = "needs a migrated database at DATABASE_URL"]
+    fn rolling_back_a_book_relinks_its_tags() {
+        with_database(|conn| {
+            let new_book = json!({
+                "title": "Rollback Test", "author": "Nobody", "genres": [], "tags": [],
+                "rating": 3, "status": "in-progress", "description": "", "my_thoughts": "",
+                "cover_image": "https://example.com/cover.jpg", "explicit": false,
+            });
+            let book = diesel::insert_into(books::table)
+                .values((
+                    &serde_json::from_value::<NewBook>(new_book).unwrap(),
+                    books::owner_id.eq(test_account(conn)),
+                ))
+                .get_result::<Book>(conn)
+                .unwrap();
+
+            for genres in ["Fantasy", "Horror"] {
+                let changes = json!({"genres": [genres]});
+                apply_book_update(
+                    conn,
+                    book.id,
+                    serde_json::from_value::<UpdateBook>(changes).unwrap(),
+                )
+                .unwrap();
+            }
+
+            let revision = find_revision(conn, ItemKind::Books, book.id, 2).unwrap();
+            apply_snapshot(conn, ItemKind::Books, book.id, revision.data).unwrap();
+
+            let linked = book_tags::table
+                .inner_join(tags::table)
+                .filter(book_tags::book_id.eq(book.id))
+                .select(tags::slug)
+                .load::<String>(conn)
+                .unwrap();
+            assert_eq!(linked, ["fantasy"]);
+        });
+    }
+
+    #[test]
+    #[ignore = "needs a migrated database at DATABASE_URL"]
+    fn rolling_back_a_game_relinks_its_tags() {
+        with_database(|conn| {
+            let new_game = json!({
+                "title": "Rollback Test", "developer": "Nobody", "genres": [], "tags": [],
+                "rating": 3, "status": "in-progress", "description": "", "my_thoughts": "",
+                "cover_image": "https://example.com/cover.jpg", "explicit": false, "percent": 0, "bad": false,
+            });
+            let new_game = new_game.to_string();
+            let game = diesel::insert_into(games::table)
+                .values((
+                    &serde_json::from_str::<NewGame>(&new_game).unwrap(),
+                    games::owner_id.eq(test_account(conn)),
+                ))
+                .get_result::<Game>(conn)
+                .unwrap();
+
+            for tag in ["Co-op", "Roguelike"] {
+                let changes = json!({"tags": [tag]});
+                apply_game_update(
+                    conn,
+                    game.id,
+                    serde_json::from_value::<UpdateGame>(changes).unwrap(),
+                )
+                .unwrap();
+            }
+
+            let revision = find_revision(conn, ItemKind::Games, game.id, 2).unwrap();
+            apply_snapshot(conn, ItemKind::Games, game.id, revision.data).unwrap();
+
+            let linked = game_tags::table
+                .inner_join(tags::table)
+                .filter(game_tags::game_id.eq(game.id))
+                .select(tags::slug)
+                .load::<String>(conn)
+                .unwrap();
+            assert_eq!(linked, ["co-op"]);
+        });
+    }
+}
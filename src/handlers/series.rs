@@ -0,0 +1,343 @@
+use crate::auth::AdminUser;
+use crate::db::connect_db;
+use crate::errors::ApiError;
+use crate::handlers::etags::{Created, IfMatch, Tagged};
+use crate::models::{Book, NewSeries, Series, UpdateSeries};
+use crate::schema::{books, series};
+use diesel::prelude::*;
+use rocket::serde::json::Json;
+use rocket::{Route, delete, get, patch, post, put, routes};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use validator::Validate;
+
+/// One book's place in a series.
+#[derive(Serialize, Clone, Debug)]
+pub struct SeriesVolume {
+    book_id: i32,
+    slug: String,
+    title: String,
+    volume: Option<i32>,
+    status: String,
+    read: bool,
+}
+
+impl SeriesVolume {
+    fn from_book(book: &Book) -> Self {
+        SeriesVolume {
+            book_id: book.id,
+            slug: book.slug.clone(),
+            title: book.title.clone(),
+            volume: book.series_volume,
+            status: book.status.clone(),
+            read: book.status == "finished",
+        }
+    }
+
+    /// Dropped volumes are skipped over rather than suggested as the next read.
+    fn is_unread(&self) -> bool {
+        !self.read && self.status != "dropped"
+    }
+}
+
+/// A series with its books in reading order.
+#[derive(Serialize)]
+pub struct SeriesResponse {
+    #[serde(flatten)]
+    series: Series,
+    volumes: Vec<SeriesVolume>,
+    next_unread: Option<SeriesVolume>,
+}
+
+/// Where a book sits in its series, as shown on the book itself.
+#[derive(Serialize, Clone, Debug)]
+pub struct BookSeries {
+    id: i32,
+    name: String,
+    volume: Option<i32>,
+    /// The first unread volume after this one.
+    next_unread: Option<SeriesVolume>,
+}
+
+#[derive(Deserialize)]
+pub struct Membership {
+    volume: Option<i32>,
+}
+
+/// Loads the live books of each series in reading order: by volume number,
+/// with unnumbered books last.
+fn load_volumes(
+    conn: &mut PgConnection,
+    series_ids: &[i32],
+) -> QueryResult<HashMap<i32, Vec<SeriesVolume>>> {
+    let members = books::table
+        .filter(books::series_id.eq_any(series_ids))
+        .filter(books::deleted_at.is_null())
+        .order((books::series_volume.asc().nulls_last(), books::title.asc()))
+        .load::<Book>(conn)?;
+
+    let mut volumes: HashMap<i32, Vec<SeriesVolume>> = HashMap::new();
+    for book in &members {
+        if let Some(series_id) = book.series_id {
+            volumes
+                .entry(series_id)
+                .or_default()
+                .push(SeriesVolume::from_book(book));
+        }
+    }
+
+    Ok(volumes)
+}
+
+fn series_responses(
+    conn: &mut PgConnection,
+    loaded: Vec<Series>,
+) -> QueryResult<Vec<SeriesResponse>> {
+    let ids: Vec<i32> = loaded.iter().map(|series| series.id).collect();
+    let mut volumes = load_volumes(conn, &ids)?;
+
+    Ok(loaded
+        .into_iter()
+        .map(|series| {
+            let volumes = volumes.remove(&series.id).unwrap_or_default();
+            let next_unread = volumes.iter().find(|volume| volume.is_unread()).cloned();
+            SeriesResponse {
+                series,
+                volumes,
+                next_unread,
+            }
+        })
+        .collect())
+}
+
+fn series_response(conn: &mut PgConnection, series: Series) -> QueryResult<SeriesResponse> {
+    Ok(series_responses(conn, vec![series])?.remove(0))
+}
+
+/// Works out each book's series placement, keyed by book id. Books outside a
+/// series are left out.
+pub fn book_series(
+    conn: &mut PgConnection,
+    loaded: &[Book],
+) -> QueryResult<HashMap<i32, BookSeries>> {
+    let mut ids: Vec<i32> = loaded.iter().filter_map(|book| book.series_id).collect();
+    ids.sort_unstable();
+    ids.dedup();
+
+    if ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let names: HashMap<i32, String> = series::table
+        .filter(series::id.eq_any(&ids))
+        .select((series::id, series::name))
+        .load::<(i32, String)>(conn)?
+        .into_iter()
+        .collect();
+    let volumes = load_volumes(conn, &ids)?;
+
+    Ok(loaded
+        .iter()
+        .filter_map(|book| {
+            let series_id = book.series_id?;
+            let members = volumes.get(&series_id).map(Vec::as_slice).unwrap_or(&[]);
+            let after = members
+                .iter()
+                .position(|volume| volume.book_id == book.id)
+                .map_or(0, |index| index + 1);
+
+            Some((
+                book.id,
+                BookSeries {
+                    id: series_id,
+                    name: names.get(&series_id).cloned().unwrap_or_default(),
+                    volume: book.series_volume,
+                    next_unread: members[after..]
+                        .iter()
+                        .find(|volume| volume.is_unread())
+                        .cloned(),
+                },
+            ))
+        })
+        .collect())
+}
+
+fn find_series(conn: &mut PgConnection, series_id: i32) -> Result<Series, ApiError> {
+    series::table
+        .find(series_id)
+        .first::<Series>(conn)
+        .optional()?
+        .ok_or(ApiError::NotFound)
+}
+
+/// Locks a series' row until the end of the current transaction and loads it
+/// the way `GET` returns it.
+fn lock_series(conn: &mut PgConnection, series_id: i32) -> Result<SeriesResponse, ApiError> {
+    let found = series::table
+        .find(series_id)
+        .for_update()
+        .first::<Series>(conn)
+        .optional()?
+        .ok_or(ApiError::NotFound)?;
+
+    Ok(series_response(conn, found)?)
+}
+
+#[get("/")]
+pub fn get_series() -> Result<Json<Vec<SeriesResponse>>, ApiError> {
+    let mut conn = connect_db()?;
+
+    let loaded = series::table
+        .order(series::name.asc())
+        .load::<Series>(&mut conn)?;
+
+    let responses = series_responses(&mut conn, loaded)?;
+
+    Ok(Json(responses))
+}
+
+#[get("/<series_id>")]
+pub fn get_series_by_id(series_id: i32) -> Result<Tagged<SeriesResponse>, ApiError> {
+    let mut conn = connect_db()?;
+
+    let found = find_series(&mut conn, series_id)?;
+    let response = series_response(&mut conn, found)?;
+
+    Ok(Tagged::new(response))
+}
+
+#[post("/", format = "json", data = "<new_series>")]
+pub fn create_series(
+    _admin: AdminUser,
+    new_series: Json<NewSeries>,
+) -> Result<Created<Series>, ApiError> {
+    new_series.validate()?;
+
+    let mut conn = connect_db()?;
+
+    let created = diesel::insert_into(series::table)
+        .values(&new_series.into_inner())
+        .get_result::<Series>(&mut conn)?;
+
+    Ok(Created::new(
+        format!("/read-watch/series/{}", created.id),
+        created,
+    ))
+}
+
+#[patch("/<series_id>", format = "json", data = "<changes>")]
+pub fn update_series(
+    _admin: AdminUser,
+    if_match: IfMatch,
+    series_id: i32,
+    changes: Json<UpdateSeries>,
+) -> Result<Tagged<SeriesResponse>, ApiError> {
+    changes.validate()?;
+
+    let mut conn = connect_db()?;
+
+    let response = conn.transaction::<_, ApiError, _>(|conn| {
+        if_match.check_with(conn, |conn| lock_series(conn, series_id))?;
+        let updated = diesel::update(series::table.find(series_id))
+            .set(&changes.into_inner())
+            .get_result::<Series>(conn)?;
+        Ok(series_response(conn, updated)?)
+    })?;
+
+    Ok(Tagged::new(response))
+}
+
+/// Deletes a series. Its books are kept and simply leave the series.
+#[delete("/<series_id>")]
+pub fn delete_series(
+    _admin: AdminUser,
+    if_match: IfMatch,
+    series_id: i32,
+) -> Result<Json<Series>, ApiError> {
+    let mut conn = connect_db()?;
+
+    conn.transaction::<_, ApiError, _>(|conn| {
+        if_match.check_with(conn, |conn| lock_series(conn, series_id))?;
+        diesel::update(books::table.filter(books::series_id.eq(series_id)))
+            .set((
+                books::series_id.eq(None::<i32>),
+                books::series_volume.eq(None::<i32>),
+            ))
+            .execute(conn)?;
+
+        Ok(diesel::delete(series::table.find(series_id)).get_result::<Series>(conn)?)
+    })
+    .map(Json)
+}
+
+/// Adds a book to the series, or moves it to a different volume number.
+#[put("/<series_id>/books/<book_id>", format = "json", data = "<membership>")]
+pub fn add_series_book(
+    _admin: AdminUser,
+    series_id: i32,
+    book_id: i32,
+    membership: Json<Membership>,
+) -> Result<Json<SeriesResponse>, ApiError> {
+    let mut conn = connect_db()?;
+    let found = find_series(&mut conn, series_id)?;
+
+    let updated = diesel::update(
+        books::table
+            .find(book_id)
+            .filter(books::deleted_at.is_null()),
+    )
+    .set((
+        books::series_id.eq(Some(series_id)),
+        books::series_volume.eq(membership.volume),
+    ))
+    .execute(&mut conn)?;
+
+    if updated == 0 {
+        return Err(ApiError::NotFound);
+    }
+
+    let response = series_response(&mut conn, found)?;
+
+    Ok(Json(response))
+}
+
+#[delete("/<series_id>/books/<book_id>")]
+pub fn remove_series_book(
+    _admin: AdminUser,
+    series_id: i32,
+    book_id: i32,
+) -> Result<Json<SeriesResponse>, ApiError> {
+    let mut conn = connect_db()?;
+    let found = find_series(&mut conn, series_id)?;
+
+    let updated = diesel::update(
+        books::table
+            .find(book_id)
+            .filter(books::series_id.eq(series_id)),
+    )
+    .set((
+        books::series_id.eq(None::<i32>),
+        books::series_volume.eq(None::<i32>),
+    ))
+    .execute(&mut conn)?;
+
+    if updated == 0 {
+        return Err(ApiError::NotFound);
+    }
+
+    let response = series_response(&mut conn, found)?;
+
+    Ok(Json(response))
+}
+
+pub fn series_routes() -> Vec<Route> {
+    routes![
+        get_series,
+        get_series_by_id,
+        create_series,
+        update_series,
+        delete_series,
+        add_series_book,
+        remove_series_book,
+    ]
+}
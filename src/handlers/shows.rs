@@ -0,0 +1,532 @@
+use crate::auth::{AdminUser, User};
+use crate::db::connect_db;
+use crate::errors::ApiError;
+use crate::handlers::etags::{Created, IfMatch, Tagged};
+use crate::handlers::identifiers::{ItemRef, Located, Resolved, resolve_ref};
+use crate::handlers::ratings::{Rated, rated, rating_scale};
+use crate::handlers::{ItemKind, parse_timestamp};
+use crate::models::{NewShow, NewShowEpisode, Rating, Show, ShowEpisode, UpdateShow};
+use crate::schema::{show_episodes, shows};
+use chrono::{NaiveDateTime, Utc};
+use diesel::prelude::*;
+use rocket::Either;
+use rocket::form::FromForm;
+use rocket::serde::{Deserialize, Serialize, json::Json};
+use rocket::{delete, get, patch, post, put, routes};
+use std::collections::HashMap;
+use validator::Validate;
+
+#[derive(FromForm, Debug)]
+pub struct ShowQuery {
+    title: Option<String>,
+    director: Option<String>,
+    studio: Option<String>,
+    genre: Option<String>,
+    tag: Option<String>,
+    status: Option<String>,
+    explicit: Option<String>,
+    #[field(name = "minProgress")]
+    min_progress: Option<i32>,
+    #[field(name = "maxProgress")]
+    max_progress: Option<i32>,
+    #[field(name = "exactProgress")]
+    exact_progress: Option<i32>,
+    #[field(name = "minRuntime")]
+    min_runtime: Option<i32>,
+    #[field(name = "maxRuntime")]
+    max_runtime: Option<i32>,
+    #[field(name = "minRating")]
+    min_rating: Option<f64>,
+    #[field(name = "maxRating")]
+    max_rating: Option<f64>,
+    #[field(name = "exactRating")]
+    exact_rating: Option<f64>,
+    #[field(name = "minRewatches")]
+    min_rewatches: Option<i32>,
+    #[field(name = "watchedAfter")]
+    watched_after: Option<String>,
+    #[field(name = "createdAfter")]
+    created_after: Option<String>,
+    #[field(name = "updatedSince")]
+    updated_since: Option<String>,
+    sort: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct BulkDeleteFilter {
+    director: Option<String>,
+    status: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct BulkUpdatePayload {
+    filter: HashMap<String, String>,
+    update: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+pub struct EpisodeWatch {
+    season: i32,
+    episode: i32,
+    note: Option<String>,
+    watched_at: Option<NaiveDateTime>,
+    total_seasons: Option<i32>,
+    total_episodes: Option<i32>,
+}
+
+#[derive(Serialize)]
+pub struct ApiResponse {
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    deleted: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    updated: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    count: Option<usize>,
+}
+
+#[get("/search?<query..>")]
+pub fn get_shows(query: ShowQuery) -> Result<Json<Vec<Rated<Show>>>, ApiError> {
+    use crate::schema::shows::dsl::*;
+
+    let mut conn = connect_db()?;
+    let mut show_query = shows.filter(deleted_at.is_null()).into_boxed();
+
+    if let Some(title_filter) = &query.title {
+        show_query = show_query.filter(title.ilike(format!("%{}%", title_filter)));
+    }
+
+    if let Some(director_filter) = &query.director {
+        show_query = show_query.filter(director.ilike(format!("%{}%", director_filter)));
+    }
+
+    if let Some(studio_filter) = &query.studio {
+        show_query = show_query.filter(studio.ilike(format!("%{}%", studio_filter)));
+    }
+
+    if let Some(status_filter) = &query.status {
+        show_query = show_query.filter(status.eq(status_filter));
+    }
+
+    if let Some(explicit_filter) = &query.explicit {
+        match explicit_filter.as_str() {
+            "true" => show_query = show_query.filter(explicit.eq(true)),
+            "false" => show_query = show_query.filter(explicit.eq(false)),
+            _ => {}
+        }
+    }
+
+    if let Some(min_progress_filter) = query.min_progress {
+        show_query = show_query.filter(percent.ge(min_progress_filter));
+    }
+
+    if let Some(max_progress_filter) = query.max_progress {
+        show_query = show_query.filter(percent.le(max_progress_filter));
+    }
+
+    if let Some(exact_progress_filter) = query.exact_progress {
+        show_query = show_query.filter(percent.eq(exact_progress_filter));
+    }
+
+    if let Some(min_runtime_filter) = query.min_runtime {
+        show_query = show_query.filter(runtime_minutes.ge(min_runtime_filter));
+    }
+
+    if let Some(max_runtime_filter) = query.max_runtime {
+        show_query = show_query.filter(runtime_minutes.le(max_runtime_filter));
+    }
+
+    if let Some(min_rating_filter) = query.min_rating {
+        show_query = show_query.filter(rating.ge(Rating(min_rating_filter)));
+    }
+
+    if let Some(max_rating_filter) = query.max_rating {
+        show_query = show_query.filter(rating.le(Rating(max_rating_filter)));
+    }
+
+    if let Some(exact_rating_filter) = query.exact_rating {
+        show_query = show_query.filter(rating.eq(Rating(exact_rating_filter)));
+    }
+
+    if let Some(min_rewatches_filter) = query.min_rewatches {
+        show_query = show_query.filter(rewatch_count.ge(min_rewatches_filter));
+    }
+
+    if let Some(watched_after_filter) = &query.watched_after {
+        let timestamp = parse_timestamp(watched_after_filter)
+            .ok_or_else(|| ApiError::bad_request("invalid watchedAfter timestamp"))?;
+        show_query = show_query.filter(last_watched_at.gt(timestamp));
+    }
+
+    if let Some(created_after_filter) = &query.created_after {
+        let timestamp = parse_timestamp(created_after_filter)
+            .ok_or_else(|| ApiError::bad_request("invalid createdAfter timestamp"))?;
+        show_query = show_query.filter(created_at.gt(timestamp));
+    }
+
+    if let Some(updated_since_filter) = &query.updated_since {
+        let timestamp = parse_timestamp(updated_since_filter)
+            .ok_or_else(|| ApiError::bad_request("invalid updatedSince timestamp"))?;
+        show_query = show_query.filter(updated_at.ge(timestamp));
+    }
+
+    if let Some(sort_by) = &query.sort {
+        match sort_by.as_str() {
+            "title" => show_query = show_query.order(title.asc()),
+            "director" => show_query = show_query.order(director.asc().nulls_last()),
+            "rating" => show_query = show_query.order(rating.desc()),
+            "runtime" => show_query = show_query.order(runtime_minutes.desc().nulls_last()),
+            "progress" => show_query = show_query.order(percent.desc().nulls_last()),
+            "watched" => show_query = show_query.order(last_watched_at.desc().nulls_last()),
+            "rewatches" => show_query = show_query.order(rewatch_count.desc()),
+            "created" => show_query = show_query.order(created_at.desc()),
+            "updated" => show_query = show_query.order(updated_at.desc()),
+            _ => {}
+        }
+    }
+
+    let results = show_query.load::<Show>(&mut conn)?;
+
+    let mut filtered_results = results;
+
+    if let Some(genre_filter) = &query.genre {
+        filtered_results.retain(|show| {
+            show.genres
+                .iter()
+                .flatten()
+                .any(|genre| genre.to_lowercase().contains(&genre_filter.to_lowercase()))
+        });
+    }
+
+    if let Some(tag_filter) = &query.tag {
+        filtered_results.retain(|show| {
+            show.tags
+                .iter()
+                .flatten()
+                .any(|tag| tag.to_lowercase().contains(&tag_filter.to_lowercase()))
+        });
+    }
+
+    let scale = rating_scale(&mut conn, ItemKind::Shows)?;
+
+    Ok(Json(
+        filtered_results
+            .into_iter()
+            .map(|show| Rated::new(&scale, show.rating, show))
+            .collect(),
+    ))
+}
+
+/// A live show as `GET /shows/<id>` returns it.
+fn current_show(conn: &mut PgConnection, show_id: i32) -> Result<Rated<Show>, ApiError> {
+    use crate::schema::shows::dsl::*;
+
+    let show = shows
+        .filter(id.eq(&show_id))
+        .filter(deleted_at.is_null())
+        .first::<Show>(conn)?;
+
+    Ok(rated(conn, ItemKind::Shows, show.rating, show)?)
+}
+
+#[get("/<show_ref>")]
+pub fn get_show_by_id(show_ref: ItemRef) -> Result<Located<Rated<Show>>, ApiError> {
+    let mut conn = connect_db()?;
+
+    let show_id = match resolve_ref(&mut conn, ItemKind::Shows, &show_ref)? {
+        Resolved::Found(found) => found,
+        Resolved::Moved(redirect) => return Ok(Either::Right(*redirect)),
+    };
+
+    let response = current_show(&mut conn, show_id)?;
+
+    Ok(Either::Left(Tagged::new(response)))
+}
+
+#[post("/", format = "json", data = "<new_show>")]
+pub fn post_shows(_user: User, new_show: Json<NewShow>) -> Result<Created<Rated<Show>>, ApiError> {
+    _user.require_admin()?;
+    new_show.validate()?;
+
+    let mut conn = connect_db()?;
+
+    let show = diesel::insert_into(shows::table)
+        .values(&new_show.into_inner())
+        .get_result::<Show>(&mut conn)?;
+
+    let location = ItemKind::Shows.location(show.id);
+    let response = rated(&mut conn, ItemKind::Shows, show.rating, show)?;
+
+    Ok(Created::new(location, response))
+}
+
+#[put("/<show_id>", format = "json", data = "<updated_show>")]
+pub fn update_show(
+    _user: User,
+    if_match: IfMatch,
+    show_id: i32,
+    updated_show: Json<UpdateShow>,
+) -> Result<Tagged<Rated<Show>>, ApiError> {
+    _user.require_admin()?;
+    use crate::schema::shows::dsl::*;
+
+    updated_show.validate()?;
+
+    let mut conn = connect_db()?;
+
+    let response = conn.transaction::<_, ApiError, _>(|conn| {
+        if_match.check(conn, ItemKind::Shows, show_id, current_show)?;
+        let show = diesel::update(shows.filter(id.eq(show_id)).filter(deleted_at.is_null()))
+            .set(&updated_show.into_inner())
+            .get_result::<Show>(conn)?;
+        Ok(rated(conn, ItemKind::Shows, show.rating, show)?)
+    })?;
+
+    Ok(Tagged::new(response))
+}
+
+#[patch("/<show_id>", format = "json", data = "<patch_data>")]
+pub fn patch_show(
+    _user: User,
+    if_match: IfMatch,
+    show_id: i32,
+    patch_data: Json<UpdateShow>,
+) -> Result<Tagged<Rated<Show>>, ApiError> {
+    _user.require_admin()?;
+    use crate::schema::shows::dsl::*;
+
+    patch_data.validate()?;
+
+    let mut conn = connect_db()?;
+
+    let response = conn.transaction::<_, ApiError, _>(|conn| {
+        if_match.check(conn, ItemKind::Shows, show_id, current_show)?;
+        let show = diesel::update(shows.filter(id.eq(show_id)).filter(deleted_at.is_null()))
+            .set(&patch_data.into_inner())
+            .get_result::<Show>(conn)?;
+        Ok(rated(conn, ItemKind::Shows, show.rating, show)?)
+    })?;
+
+    Ok(Tagged::new(response))
+}
+
+#[get("/<show_id>/episodes")]
+pub fn get_show_episodes(show_id: i32) -> Result<Json<Vec<ShowEpisode>>, ApiError> {
+    let mut conn = connect_db()?;
+
+    let log = show_episodes::table
+        .filter(show_episodes::show_id.eq(show_id))
+        .order(show_episodes::watched_at.asc())
+        .load::<ShowEpisode>(&mut conn)?;
+
+    Ok(Json(log))
+}
+
+/// Logs a watched episode and moves the show's current position to it.
+/// `episodes_watched` counts distinct episodes, so rewatching one doesn't
+/// inflate progress.
+#[post("/<show_id>/episodes", format = "json", data = "<watch>")]
+pub fn log_show_episode(
+    _admin: AdminUser,
+    show_id: i32,
+    watch: Json<EpisodeWatch>,
+) -> Result<Json<Show>, ApiError> {
+    let watch = watch.into_inner();
+    if watch.season < 1 || watch.episode < 1 {
+        return Err(ApiError::validation("season and episode start at 1"));
+    }
+
+    let watched_at = watch.watched_at.unwrap_or_else(|| Utc::now().naive_utc());
+    let mut conn = connect_db()?;
+
+    let show = conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        let show = shows::table
+            .find(show_id)
+            .filter(shows::deleted_at.is_null())
+            .for_update()
+            .first::<Show>(conn)?;
+
+        diesel::insert_into(show_episodes::table)
+            .values(&NewShowEpisode {
+                show_id,
+                season: watch.season,
+                episode: watch.episode,
+                note: watch.note,
+                watched_at: Some(watched_at),
+            })
+            .execute(conn)?;
+
+        let watched = show_episodes::table
+            .filter(show_episodes::show_id.eq(show_id))
+            .select((show_episodes::season, show_episodes::episode))
+            .distinct()
+            .load::<(i32, i32)>(conn)?
+            .len() as i32;
+
+        diesel::update(shows::table.find(show.id))
+            .set(&UpdateShow {
+                current_season: Some(watch.season),
+                current_episode: Some(watch.episode),
+                episodes_watched: Some(watched),
+                total_seasons: watch.total_seasons,
+                total_episodes: watch.total_episodes,
+                first_watched_at: Some(
+                    show.first_watched_at
+                        .map_or(watched_at, |first| first.min(watched_at)),
+                ),
+                last_watched_at: Some(
+                    show.last_watched_at
+                        .map_or(watched_at, |last| last.max(watched_at)),
+                ),
+                ..Default::default()
+            })
+            .get_result::<Show>(conn)
+    })?;
+
+    Ok(Json(show))
+}
+
+#[delete("/<show_id>")]
+pub fn delete_show(
+    _user: User,
+    if_match: IfMatch,
+    show_id: i32,
+) -> Result<Json<ApiResponse>, ApiError> {
+    _user.require_admin()?;
+    use crate::schema::shows::dsl::*;
+
+    let mut conn = connect_db()?;
+
+    let rows_deleted = conn.transaction::<_, ApiError, _>(|conn| {
+        if_match.check(conn, ItemKind::Shows, show_id, current_show)?;
+        Ok(
+            diesel::update(shows.filter(id.eq(&show_id)).filter(deleted_at.is_null()))
+                .set(deleted_at.eq(diesel::dsl::now.nullable()))
+                .execute(conn)?,
+        )
+    })?;
+
+    if rows_deleted > 0 {
+        Ok(Json(ApiResponse {
+            message: "show moved to trash".to_string(),
+            deleted: None,
+            updated: None,
+            count: None,
+        }))
+    } else {
+        Err(ApiError::NotFound)
+    }
+}
+
+#[post("/<show_id>/restore")]
+pub fn restore_show(_admin: AdminUser, show_id: i32) -> Result<Json<Show>, ApiError> {
+    use crate::schema::shows::dsl::*;
+
+    let mut conn = connect_db()?;
+
+    let show = diesel::update(
+        shows
+            .filter(id.eq(&show_id))
+            .filter(deleted_at.is_not_null()),
+    )
+    .set(deleted_at.eq(None::<NaiveDateTime>))
+    .get_result::<Show>(&mut conn)?;
+
+    Ok(Json(show))
+}
+
+#[delete("/bulk", format = "json", data = "<filter>")]
+pub fn bulk_delete_shows(
+    _user: User,
+    filter: Json<BulkDeleteFilter>,
+) -> Result<Json<ApiResponse>, ApiError> {
+    _user.require_admin()?;
+    use crate::schema::shows::dsl::*;
+
+    let mut conn = connect_db()?;
+    let filter = filter.into_inner();
+
+    let mut delete_query = diesel::update(shows.filter(deleted_at.is_null())).into_boxed();
+
+    if let Some(director_filter) = &filter.director {
+        delete_query = delete_query.filter(director.eq(director_filter));
+    }
+
+    if let Some(status_filter) = &filter.status {
+        delete_query = delete_query.filter(status.eq(status_filter));
+    }
+
+    let deleted_count = delete_query
+        .set(deleted_at.eq(diesel::dsl::now.nullable()))
+        .execute(&mut conn)? as i32;
+
+    Ok(Json(ApiResponse {
+        message: "bulk delete complete".to_string(),
+        deleted: Some(deleted_count),
+        updated: None,
+        count: None,
+    }))
+}
+
+#[patch("/bulk", format = "json", data = "<payload>")]
+pub fn bulk_update_shows(
+    _user: User,
+    payload: Json<BulkUpdatePayload>,
+) -> Result<Json<ApiResponse>, ApiError> {
+    _user.require_admin()?;
+    use crate::schema::shows::dsl::*;
+
+    let mut conn = connect_db()?;
+    let payload = payload.into_inner();
+
+    let mut update_query = diesel::update(shows.filter(deleted_at.is_null())).into_boxed();
+
+    if let Some(director_filter) = payload.filter.get("director") {
+        update_query = update_query.filter(director.eq(director_filter));
+    }
+
+    if let Some(status_filter) = payload.filter.get("status") {
+        update_query = update_query.filter(status.eq(status_filter));
+    }
+
+    let mut update_show = UpdateShow::default();
+
+    if let Some(new_status) = payload.update.get("status")
+        && let Some(status_str) = new_status.as_str()
+    {
+        update_show.status = Some(status_str.to_string());
+    }
+
+    if let Some(new_rating) = payload.update.get("rating")
+        && let Some(rating_num) = new_rating.as_f64()
+    {
+        update_show.rating = Some(Rating(rating_num));
+    }
+
+    update_show.validate()?;
+
+    let updated_count = update_query.set(&update_show).execute(&mut conn)? as i32;
+
+    Ok(Json(ApiResponse {
+        message: "bulk update complete".to_string(),
+        deleted: None,
+        updated: Some(updated_count),
+        count: None,
+    }))
+}
+
+pub fn shows_routes() -> Vec<rocket::Route> {
+    routes![
+        get_shows,
+        get_show_by_id,
+        post_shows,
+        update_show,
+        patch_show,
+        get_show_episodes,
+        log_show_episode,
+        delete_show,
+        restore_show,
+        bulk_delete_shows,
+        bulk_update_shows
+    ]
+}
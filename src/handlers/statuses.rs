@@ -0,0 +1,134 @@
+use crate::auth::AdminUser;
+use crate::db::connect_db;
+use crate::errors::ApiError;
+use crate::handlers::ItemKind;
+use crate::models::{StatusChange, StatusTransition};
+use crate::schema::{status_changes, status_transitions};
+use diesel::prelude::*;
+use rocket::serde::json::Json;
+use rocket::{get, put, routes};
+use serde::{Deserialize, Serialize};
+
+/// Every status a book, game, movie or show can be in, mirroring the `*_status_check` constraints.
+pub const STATUSES: [&str; 5] = ["planned", "in-progress", "paused", "finished", "dropped"];
+
+#[derive(Serialize, Deserialize)]
+pub struct TransitionEdge {
+    from: String,
+    to: String,
+}
+
+#[derive(Serialize)]
+pub struct StatusLifecycle {
+    statuses: Vec<&'static str>,
+    transitions: Vec<TransitionEdge>,
+}
+
+fn lifecycle_kind(kind: ItemKind) -> Result<&'static str, ApiError> {
+    match kind {
+        ItemKind::Books | ItemKind::Games | ItemKind::Movies | ItemKind::Shows => {
+            Ok(kind.table_name())
+        }
+        _ => Err(ApiError::NotFound),
+    }
+}
+
+fn load_lifecycle(conn: &mut PgConnection, item_type: &str) -> QueryResult<StatusLifecycle> {
+    let transitions = status_transitions::table
+        .filter(status_transitions::item_type.eq(item_type))
+        .order((
+            status_transitions::from_status.asc(),
+            status_transitions::to_status.asc(),
+        ))
+        .load::<StatusTransition>(conn)?
+        .into_iter()
+        .map(|transition| TransitionEdge {
+            from: transition.from_status,
+            to: transition.to_status,
+        })
+        .collect();
+
+    Ok(StatusLifecycle {
+        statuses: STATUSES.to_vec(),
+        transitions,
+    })
+}
+
+#[get("/<kind>")]
+pub fn get_lifecycle(kind: ItemKind) -> Result<Json<StatusLifecycle>, ApiError> {
+    let item_type = lifecycle_kind(kind)?;
+    let mut conn = connect_db()?;
+
+    load_lifecycle(&mut conn, item_type)
+        .map(Json)
+        .map_err(ApiError::from)
+}
+
+#[put("/<kind>/transitions", format = "json", data = "<transitions>")]
+pub fn set_transitions(
+    _admin: AdminUser,
+    kind: ItemKind,
+    transitions: Json<Vec<TransitionEdge>>,
+) -> Result<Json<StatusLifecycle>, ApiError> {
+    let item_type = lifecycle_kind(kind)?;
+
+    let transitions: Vec<StatusTransition> = transitions
+        .into_inner()
+        .into_iter()
+        .map(|edge| StatusTransition {
+            item_type: item_type.to_string(),
+            from_status: edge.from,
+            to_status: edge.to,
+        })
+        .collect();
+
+    let is_known = |status: &str| STATUSES.contains(&status);
+    if transitions.iter().any(|transition| {
+        !is_known(&transition.from_status)
+            || !is_known(&transition.to_status)
+            || transition.from_status == transition.to_status
+    }) {
+        return Err(ApiError::validation(
+            "transitions must be between two different known statuses",
+        ));
+    }
+
+    let mut conn = connect_db()?;
+
+    conn.transaction(|conn| {
+        diesel::delete(
+            status_transitions::table.filter(status_transitions::item_type.eq(item_type)),
+        )
+        .execute(conn)?;
+
+        diesel::insert_into(status_transitions::table)
+            .values(&transitions)
+            .on_conflict_do_nothing()
+            .execute(conn)?;
+
+        load_lifecycle(conn, item_type)
+    })
+    .map(Json)
+    .map_err(ApiError::from)
+}
+
+#[get("/<kind>/<item_id>/history")]
+pub fn get_status_history(
+    kind: ItemKind,
+    item_id: i32,
+) -> Result<Json<Vec<StatusChange>>, ApiError> {
+    let item_type = lifecycle_kind(kind)?;
+    let mut conn = connect_db()?;
+
+    let history = status_changes::table
+        .filter(status_changes::item_type.eq(item_type))
+        .filter(status_changes::item_id.eq(item_id))
+        .order(status_changes::changed_at.asc())
+        .load::<StatusChange>(&mut conn)?;
+
+    Ok(Json(history))
+}
+
+pub fn statuses_routes() -> Vec<rocket::Route> {
+    routes![get_lifecycle, set_transitions, get_status_history]
+}
@@ -0,0 +1,434 @@
+use crate::auth::AdminUser;
+use crate::db::connect_db;
+use crate::errors::ApiError;
+use crate::models::{Book, Game, NewBookTag, NewGameTag, NewTag, Tag};
+use crate::schema::{book_tags, books, game_tags, games, tags};
+use diesel::dsl::count_star;
+use diesel::prelude::*;
+use diesel::result::{DatabaseErrorKind, Error};
+use rocket::serde::json::Json;
+use rocket::{FromForm, delete, get, patch, post, routes};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TagKind {
+    Genre,
+    Tag,
+}
+
+impl TagKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TagKind::Genre => "genre",
+            TagKind::Tag => "tag",
+        }
+    }
+}
+
+#[derive(FromForm, Debug)]
+pub struct TagQuery {
+    kind: Option<String>,
+    sort: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct RenameTag {
+    name: String,
+}
+
+#[derive(Deserialize)]
+pub struct MergeTags {
+    into: i32,
+}
+
+#[derive(Serialize)]
+pub struct TagUsage {
+    #[serde(flatten)]
+    tag: Tag,
+    books: i64,
+    games: i64,
+}
+
+/// Lowercases a tag and collapses every run of non-alphanumeric characters
+/// into a single hyphen, so "Sci-Fi" and "sci fi" both become "sci-fi".
+pub fn normalize_tag(name: &str) -> String {
+    name.split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Looks up (creating where needed) the tags for a list of free-text names,
+/// dropping blanks and names that normalize to a tag already in the list.
+pub fn resolve_tags<S: AsRef<str>>(
+    conn: &mut PgConnection,
+    kind: TagKind,
+    names: &[S],
+) -> QueryResult<Vec<Tag>> {
+    let mut resolved: Vec<Tag> = Vec::new();
+
+    for name in names {
+        let name = name.as_ref().trim();
+        let slug = normalize_tag(name);
+
+        if slug.is_empty() || resolved.iter().any(|tag| tag.slug == slug) {
+            continue;
+        }
+
+        diesel::insert_into(tags::table)
+            .values(&NewTag {
+                name,
+                slug: &slug,
+                kind: kind.as_str(),
+            })
+            .on_conflict((tags::kind, tags::slug))
+            .do_nothing()
+            .execute(conn)?;
+
+        let tag = tags::table
+            .filter(tags::kind.eq(kind.as_str()))
+            .filter(tags::slug.eq(&slug))
+            .first::<Tag>(conn)?;
+
+        resolved.push(tag);
+    }
+
+    Ok(resolved)
+}
+
+pub fn tag_names(resolved: &[Tag]) -> Vec<String> {
+    resolved.iter().map(|tag| tag.name.clone()).collect()
+}
+
+/// Replaces a book's links to tags of `kind` with `resolved`.
+pub fn link_book_tags(
+    conn: &mut PgConnection,
+    book_id: i32,
+    kind: TagKind,
+    resolved: &[Tag],
+) -> QueryResult<()> {
+    let kind_ids = tags::table
+        .filter(tags::kind.eq(kind.as_str()))
+        .select(tags::id);
+
+    diesel::delete(
+        book_tags::table
+            .filter(book_tags::book_id.eq(book_id))
+            .filter(book_tags::tag_id.eq_any(kind_ids)),
+    )
+    .execute(conn)?;
+
+    let links: Vec<NewBookTag> = resolved
+        .iter()
+        .map(|tag| NewBookTag {
+            book_id,
+            tag_id: tag.id,
+        })
+        .collect();
+
+    diesel::insert_into(book_tags::table)
+        .values(&links)
+        .on_conflict_do_nothing()
+        .execute(conn)?;
+
+    Ok(())
+}
+
+/// Replaces a game's links to tags of `kind` with `resolved`.
+pub fn link_game_tags(
+    conn: &mut PgConnection,
+    game_id: i32,
+    kind: TagKind,
+    resolved: &[Tag],
+) -> QueryResult<()> {
+    let kind_ids = tags::table
+        .filter(tags::kind.eq(kind.as_str()))
+        .select(tags::id);
+
+    diesel::delete(
+        game_tags::table
+            .filter(game_tags::game_id.eq(game_id))
+            .filter(game_tags::tag_id.eq_any(kind_ids)),
+    )
+    .execute(conn)?;
+
+    let links: Vec<NewGameTag> = resolved
+        .iter()
+        .map(|tag| NewGameTag {
+            game_id,
+            tag_id: tag.id,
+        })
+        .collect();
+
+    diesel::insert_into(game_tags::table)
+        .values(&links)
+        .on_conflict_do_nothing()
+        .execute(conn)?;
+
+    Ok(())
+}
+
+fn rewrite_names(names: &[Option<String>], slug: &str, replacement: Option<&str>) -> Vec<String> {
+    let mut rewritten: Vec<String> = Vec::new();
+
+    for name in names.iter().flatten() {
+        let name = if normalize_tag(name) == slug {
+            match replacement {
+                Some(replacement) => replacement.to_string(),
+                None => continue,
+            }
+        } else {
+            name.clone()
+        };
+
+        let normalized = normalize_tag(&name);
+        if !rewritten
+            .iter()
+            .any(|existing| normalize_tag(existing) == normalized)
+        {
+            rewritten.push(name);
+        }
+    }
+
+    rewritten
+}
+
+/// Keeps the `genres`/`tags` arrays of every item linked to `tag` in step with
+/// the taxonomy, replacing the tag with `replacement` or removing it.
+fn rewrite_item_arrays(
+    conn: &mut PgConnection,
+    tag: &Tag,
+    replacement: Option<&str>,
+) -> QueryResult<()> {
+    let is_genre = tag.kind == TagKind::Genre.as_str();
+
+    let linked_books = books::table
+        .filter(
+            books::id.eq_any(
+                book_tags::table
+                    .filter(book_tags::tag_id.eq(tag.id))
+                    .select(book_tags::book_id),
+            ),
+        )
+        .load::<Book>(conn)?;
+
+    for book in linked_books {
+        let target = books::table.find(book.id);
+        if is_genre {
+            diesel::update(target)
+                .set(books::genres.eq(rewrite_names(&book.genres, &tag.slug, replacement)))
+                .execute(conn)?;
+        } else {
+            diesel::update(target)
+                .set(books::tags.eq(rewrite_names(&book.tags, &tag.slug, replacement)))
+                .execute(conn)?;
+        }
+    }
+
+    let linked_games = games::table
+        .filter(
+            games::id.eq_any(
+                game_tags::table
+                    .filter(game_tags::tag_id.eq(tag.id))
+                    .select(game_tags::game_id),
+            ),
+        )
+        .load::<Game>(conn)?;
+
+    for game in linked_games {
+        let target = games::table.find(game.id);
+        if is_genre {
+            diesel::update(target)
+                .set(games::genres.eq(rewrite_names(&game.genres, &tag.slug, replacement)))
+                .execute(conn)?;
+        } else {
+            diesel::update(target)
+                .set(games::tags.eq(rewrite_names(&game.tags, &tag.slug, replacement)))
+                .execute(conn)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn usage_counts(conn: &mut PgConnection) -> QueryResult<(HashMap<i32, i64>, HashMap<i32, i64>)> {
+    let book_counts = book_tags::table
+        .inner_join(books::table)
+        .filter(books::deleted_at.is_null())
+        .group_by(book_tags::tag_id)
+        .select((book_tags::tag_id, count_star()))
+        .load::<(i32, i64)>(conn)?;
+
+    let game_counts = game_tags::table
+        .inner_join(games::table)
+        .filter(games::deleted_at.is_null())
+        .group_by(game_tags::tag_id)
+        .select((game_tags::tag_id, count_star()))
+        .load::<(i32, i64)>(conn)?;
+
+    Ok((
+        book_counts.into_iter().collect(),
+        game_counts.into_iter().collect(),
+    ))
+}
+
+fn map_tag_error(error: Error) -> ApiError {
+    match error {
+        Error::DatabaseError(DatabaseErrorKind::UniqueViolation, _) => {
+            ApiError::conflict("a tag with that name already exists")
+        }
+        error => ApiError::from(error),
+    }
+}
+
+#[get("/?<query..>")]
+pub fn get_tags(query: TagQuery) -> Result<Json<Vec<TagUsage>>, ApiError> {
+    let mut conn = connect_db()?;
+    let mut tag_query = tags::table.into_boxed();
+
+    if let Some(kind_filter) = &query.kind {
+        tag_query = tag_query.filter(tags::kind.eq(kind_filter));
+    }
+
+    let results = tag_query.order(tags::name.asc()).load::<Tag>(&mut conn)?;
+
+    let (book_counts, game_counts) = usage_counts(&mut conn)?;
+
+    let mut usage: Vec<TagUsage> = results
+        .into_iter()
+        .map(|tag| TagUsage {
+            books: book_counts.get(&tag.id).copied().unwrap_or(0),
+            games: game_counts.get(&tag.id).copied().unwrap_or(0),
+            tag,
+        })
+        .collect();
+
+    if query.sort.as_deref() == Some("usage") {
+        usage.sort_by_key(|entry| std::cmp::Reverse(entry.books + entry.games));
+    }
+
+    Ok(Json(usage))
+}
+
+#[get("/<tag_id>")]
+pub fn get_tag(tag_id: i32) -> Result<Json<Tag>, ApiError> {
+    let mut conn = connect_db()?;
+
+    tags::table
+        .find(tag_id)
+        .first::<Tag>(&mut conn)
+        .map(Json)
+        .map_err(ApiError::from)
+}
+
+#[patch("/<tag_id>", format = "json", data = "<rename>")]
+pub fn rename_tag(
+    _admin: AdminUser,
+    tag_id: i32,
+    rename: Json<RenameTag>,
+) -> Result<Json<Tag>, ApiError> {
+    let name = rename.name.trim();
+    let slug = normalize_tag(name);
+    if slug.is_empty() {
+        return Err(ApiError::validation("tag name must not be empty"));
+    }
+
+    let mut conn = connect_db()?;
+
+    conn.transaction::<_, Error, _>(|conn| {
+        let tag = tags::table.find(tag_id).first::<Tag>(conn)?;
+        rewrite_item_arrays(conn, &tag, Some(name))?;
+
+        diesel::update(tags::table.find(tag_id))
+            .set((tags::name.eq(name), tags::slug.eq(&slug)))
+            .get_result::<Tag>(conn)
+    })
+    .map(Json)
+    .map_err(map_tag_error)
+}
+
+#[post("/<tag_id>/merge", format = "json", data = "<merge>")]
+pub fn merge_tags(
+    _admin: AdminUser,
+    tag_id: i32,
+    merge: Json<MergeTags>,
+) -> Result<Json<Tag>, ApiError> {
+    if merge.into == tag_id {
+        return Err(ApiError::validation("a tag can't be merged into itself"));
+    }
+
+    let mut conn = connect_db()?;
+
+    let source = tags::table.find(tag_id).first::<Tag>(&mut conn)?;
+    let target = tags::table.find(merge.into).first::<Tag>(&mut conn)?;
+
+    if source.kind != target.kind {
+        return Err(ApiError::validation(
+            "genres and tags can't be merged into each other",
+        ));
+    }
+
+    conn.transaction::<_, Error, _>(|conn| {
+        rewrite_item_arrays(conn, &source, Some(target.name.as_str()))?;
+
+        let book_links: Vec<NewBookTag> = book_tags::table
+            .filter(book_tags::tag_id.eq(source.id))
+            .select(book_tags::book_id)
+            .load::<i32>(conn)?
+            .into_iter()
+            .map(|book_id| NewBookTag {
+                book_id,
+                tag_id: target.id,
+            })
+            .collect();
+
+        diesel::insert_into(book_tags::table)
+            .values(&book_links)
+            .on_conflict_do_nothing()
+            .execute(conn)?;
+
+        let game_links: Vec<NewGameTag> = game_tags::table
+            .filter(game_tags::tag_id.eq(source.id))
+            .select(game_tags::game_id)
+            .load::<i32>(conn)?
+            .into_iter()
+            .map(|game_id| NewGameTag {
+                game_id,
+                tag_id: target.id,
+            })
+            .collect();
+
+        diesel::insert_into(game_tags::table)
+            .values(&game_links)
+            .on_conflict_do_nothing()
+            .execute(conn)?;
+
+        diesel::delete(tags::table.find(source.id)).execute(conn)?;
+
+        Ok(())
+    })
+    .map_err(map_tag_error)?;
+
+    Ok(Json(target))
+}
+
+#[delete("/<tag_id>")]
+pub fn delete_tag(_admin: AdminUser, tag_id: i32) -> Result<Json<Tag>, ApiError> {
+    let mut conn = connect_db()?;
+
+    conn.transaction::<_, Error, _>(|conn| {
+        let tag = tags::table.find(tag_id).first::<Tag>(conn)?;
+        rewrite_item_arrays(conn, &tag, None)?;
+        diesel::delete(tags::table.find(tag_id)).execute(conn)?;
+
+        Ok(tag)
+    })
+    .map(Json)
+    .map_err(map_tag_error)
+}
+
+pub fn tags_routes() -> Vec<rocket::Route> {
+    routes![get_tags, get_tag, rename_tag, merge_tags, delete_tag]
+}
@@ -0,0 +1,194 @@
+use crate::auth::{AdminUser, User};
+use crate::db::connect_db;
+use crate::errors::ApiError;
+use crate::models::{Album, Book, Game, Movie, Project, Review, Show, WplaceScreenshot};
+use crate::schema::{albums, books, games, movies, projects, reviews, shows, wplace};
+use diesel::dsl::{IntervalDsl, now};
+use diesel::prelude::*;
+use rocket::serde::json::Json;
+use rocket::{FromForm, delete, get, routes};
+use serde::Serialize;
+use std::time::Duration;
+
+/// How long soft-deleted rows are kept when `TRASH_RETENTION_DAYS` is unset.
+const DEFAULT_RETENTION_DAYS: i64 = 30;
+
+/// How often the background task purges expired trash.
+const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+#[derive(FromForm, Debug)]
+pub struct PurgeQuery {
+    #[field(name = "olderThanDays")]
+    older_than_days: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct TrashListing {
+    albums: Vec<Album>,
+    books: Vec<Book>,
+    games: Vec<Game>,
+    movies: Vec<Movie>,
+    shows: Vec<Show>,
+    projects: Vec<Project>,
+    reviews: Vec<Review>,
+    wplace: Vec<WplaceScreenshot>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct PurgeReport {
+    albums: usize,
+    books: usize,
+    games: usize,
+    movies: usize,
+    shows: usize,
+    projects: usize,
+    reviews: usize,
+    wplace: usize,
+}
+
+impl PurgeReport {
+    pub fn total(&self) -> usize {
+        self.albums
+            + self.books
+            + self.games
+            + self.movies
+            + self.shows
+            + self.projects
+            + self.reviews
+            + self.wplace
+    }
+}
+
+pub fn retention_days() -> i64 {
+    std::env::var("TRASH_RETENTION_DAYS")
+        .ok()
+        .and_then(|days| days.parse::<i64>().ok())
+        .filter(|days| *days >= 0)
+        .unwrap_or(DEFAULT_RETENTION_DAYS)
+}
+
+/// Permanently removes every soft-deleted row that was trashed more than
+/// `days` days ago. The cutoff is computed by the database, against the same
+/// clock that stamped `deleted_at`.
+pub fn purge_trash(conn: &mut PgConnection, days: i64) -> QueryResult<PurgeReport> {
+    let cutoff = (now - days.days()).nullable();
+
+    conn.transaction(|conn| {
+        Ok(PurgeReport {
+            albums: diesel::delete(albums::table.filter(albums::deleted_at.lt(cutoff)))
+                .execute(conn)?,
+            books: diesel::delete(books::table.filter(books::deleted_at.lt(cutoff)))
+                .execute(conn)?,
+            games: diesel::delete(games::table.filter(games::deleted_at.lt(cutoff)))
+                .execute(conn)?,
+            movies: diesel::delete(movies::table.filter(movies::deleted_at.lt(cutoff)))
+                .execute(conn)?,
+            shows: diesel::delete(shows::table.filter(shows::deleted_at.lt(cutoff)))
+                .execute(conn)?,
+            projects: diesel::delete(projects::table.filter(projects::deleted_at.lt(cutoff)))
+                .execute(conn)?,
+            reviews: diesel::delete(reviews::table.filter(reviews::deleted_at.lt(cutoff)))
+                .execute(conn)?,
+            wplace: diesel::delete(wplace::table.filter(wplace::deleted_at.lt(cutoff)))
+                .execute(conn)?,
+        })
+    })
+}
+
+/// Runs forever, purging trash older than the configured retention period.
+pub async fn purge_task() {
+    let mut interval = tokio::time::interval(PURGE_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        let result = tokio::task::spawn_blocking(|| -> Result<PurgeReport, ApiError> {
+            Ok(purge_trash(&mut connect_db()?, retention_days())?)
+        })
+        .await;
+
+        match result {
+            Ok(Ok(report)) if report.total() > 0 => {
+                println!("🗑️  Purged {} expired trash entries", report.total());
+            }
+            Ok(Err(e)) => eprintln!("❌ Failed to purge trash: {}", e),
+            _ => {}
+        }
+    }
+}
+
+/// Soft-deleted rows of an account-owned table, newest first, limited to
+/// `$owner`'s rows when it's `Some`.
+macro_rules! owned_trash {
+    ($conn:expr, $table:ident, $model:ty, $owner:expr) => {{
+        let mut query = $table::table
+            .filter($table::deleted_at.is_not_null())
+            .into_boxed();
+        if let Some(owner) = $owner {
+            query = query.filter($table::owner_id.eq(owner));
+        }
+        query
+            .order($table::deleted_at.desc())
+            .load::<$model>($conn)?
+    }};
+}
+
+/// Lists the caller's trash. Admins see everyone's, including albums, movies
+/// and shows, which no account owns.
+#[get("/")]
+pub fn get_trash(user: User) -> Result<Json<TrashListing>, ApiError> {
+    let mut conn = connect_db()?;
+    let owner = (!user.is_admin()).then(|| user.account_id());
+
+    let listing = TrashListing {
+        albums: if user.is_admin() {
+            albums::table
+                .filter(albums::deleted_at.is_not_null())
+                .order(albums::deleted_at.desc())
+                .load::<Album>(&mut conn)?
+        } else {
+            Vec::new()
+        },
+        books: owned_trash!(&mut conn, books, Book, owner),
+        games: owned_trash!(&mut conn, games, Game, owner),
+        movies: if user.is_admin() {
+            movies::table
+                .filter(movies::deleted_at.is_not_null())
+                .order(movies::deleted_at.desc())
+                .load::<Movie>(&mut conn)?
+        } else {
+            Vec::new()
+        },
+        shows: if user.is_admin() {
+            shows::table
+                .filter(shows::deleted_at.is_not_null())
+                .order(shows::deleted_at.desc())
+                .load::<Show>(&mut conn)?
+        } else {
+            Vec::new()
+        },
+        projects: owned_trash!(&mut conn, projects, Project, owner),
+        reviews: owned_trash!(&mut conn, reviews, Review, owner),
+        wplace: owned_trash!(&mut conn, wplace, WplaceScreenshot, owner),
+    };
+
+    Ok(Json(listing))
+}
+
+#[delete("/purge?<query..>")]
+pub fn purge(_admin: AdminUser, query: PurgeQuery) -> Result<Json<PurgeReport>, ApiError> {
+    let days = query.older_than_days.unwrap_or_else(retention_days);
+    if days < 0 {
+        return Err(ApiError::bad_request("olderThanDays must not be negative"));
+    }
+
+    let mut conn = connect_db()?;
+
+    let report = purge_trash(&mut conn, days)?;
+
+    Ok(Json(report))
+}
+
+pub fn trash_routes() -> Vec<rocket::Route> {
+    routes![get_trash, purge]
+}
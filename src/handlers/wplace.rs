@@ -1,107 +1,437 @@
-use diesel::{QueryDsl, RunQueryDsl};
-use rocket::{delete, get, patch, post, put, routes, serde::json::Json};
+use diesel::{
+    Connection, ExpressionMethods, NullableExpressionMethods, OptionalExtension, PgConnection,
+    QueryDsl, QueryResult, RunQueryDsl,
+};
+use rocket::{Either, FromForm, delete, get, patch, post, put, routes, serde::json::Json};
+use serde::Serialize;
+use validator::Validate;
 
-use crate::auth::User;
+use crate::auth::{AdminUser, User};
 use crate::{
     db::connect_db,
-    models::{NewWplaceScreenshot, UpdateWplaceScreenshot, WplaceScreenshot},
-    schema::wplace,
+    errors::ApiError,
+    handlers::{
+        ItemKind,
+        accounts::require_owner,
+        etags::{Created, IfMatch, Tagged},
+        identifiers::{ItemRef, Located, Resolved, resolve_ref},
+        parse_timestamp,
+    },
+    models::{
+        NewWplaceAlbum, NewWplaceScreenshot, UpdateWplaceAlbum, UpdateWplaceScreenshot,
+        WplaceAlbum, WplaceScreenshot,
+    },
+    schema::{wplace, wplace_albums},
 };
 
+/// The width and height of a canvas tile in pixels.
+const TILE_SIZE: i32 = 1000;
+
+#[derive(FromForm, Debug)]
+pub struct ScreenshotQuery {
+    #[field(name = "createdAfter")]
+    created_after: Option<String>,
+    #[field(name = "updatedSince")]
+    updated_since: Option<String>,
+    #[field(name = "capturedAfter")]
+    captured_after: Option<String>,
+    #[field(name = "capturedBefore")]
+    captured_before: Option<String>,
+    artwork: Option<String>,
+    #[field(name = "albumId")]
+    album_id: Option<i32>,
+    /// A region of the canvas in absolute pixels (`tile * 1000 + pixel`),
+    /// inclusive on all sides. Only screenshots whose bounding box overlaps it
+    /// are returned, so all four bounds must be given together.
+    #[field(name = "minX")]
+    min_x: Option<i32>,
+    #[field(name = "minY")]
+    min_y: Option<i32>,
+    #[field(name = "maxX")]
+    max_x: Option<i32>,
+    #[field(name = "maxY")]
+    max_y: Option<i32>,
+    /// Limits results to one account's library, as `/users/<handle>/...` does.
+    #[field(name = "ownerId")]
+    pub(crate) owner_id: Option<i32>,
+    sort: Option<String>,
+}
+
+/// An album with its screenshots in the order they were captured.
+#[derive(Serialize)]
+pub struct WplaceAlbumResponse {
+    #[serde(flatten)]
+    album: WplaceAlbum,
+    screenshots: Vec<WplaceScreenshot>,
+}
+
 #[post("/", format = "json", data = "<new_screenshot>")]
 pub fn create_screenshot(
-    _user: User,
+    user: User,
     new_screenshot: Json<NewWplaceScreenshot<'_>>,
-) -> Json<WplaceScreenshot> {
-    _user.require_admin().expect("User is not admin");
-    let mut conn = connect_db();
+) -> Result<Created<WplaceScreenshot>, ApiError> {
+    new_screenshot.validate()?;
+
+    let mut conn = connect_db()?;
     let new_screenshot = new_screenshot.into_inner();
-    diesel::insert_into(wplace::table)
-        .values(&new_screenshot)
-        .execute(&mut conn)
-        .expect("Failed inserting new screenshot");
-
-    Json(WplaceScreenshot {
-        id: 0,
-        alt: new_screenshot.alt.to_string(),
-        coverimage: new_screenshot.coverimage.to_string(),
-    })
+    let screenshot = diesel::insert_into(wplace::table)
+        .values((&new_screenshot, wplace::owner_id.eq(user.account_id())))
+        .get_result::<WplaceScreenshot>(&mut conn)?;
+
+    Ok(Created::new(
+        ItemKind::Wplace.location(screenshot.id),
+        screenshot,
+    ))
 }
 
-#[get("/<id>")]
-pub fn get_screenshot(id: i32) -> Option<Json<WplaceScreenshot>> {
-    let mut conn = connect_db();
+/// A live screenshot as `GET /wplace/<id>` returns it.
+fn current_screenshot(conn: &mut PgConnection, id: i32) -> Result<WplaceScreenshot, ApiError> {
     wplace::table
         .find(id)
-        .first::<WplaceScreenshot>(&mut conn)
-        .ok()
-        .map(Json)
+        .filter(wplace::deleted_at.is_null())
+        .first::<WplaceScreenshot>(conn)
+        .map_err(ApiError::from)
 }
 
-#[get("/")]
-pub fn get_screenshots() -> Option<Json<Vec<WplaceScreenshot>>> {
-    let mut conn = connect_db();
-    wplace::table
-        .load::<WplaceScreenshot>(&mut conn)
-        .ok()
-        .map(Json)
+#[get("/<screenshot_ref>")]
+pub fn get_screenshot(screenshot_ref: ItemRef) -> Result<Located<WplaceScreenshot>, ApiError> {
+    let mut conn = connect_db()?;
+
+    let id = match resolve_ref(&mut conn, ItemKind::Wplace, &screenshot_ref)? {
+        Resolved::Found(found) => found,
+        Resolved::Moved(redirect) => return Ok(Either::Right(*redirect)),
+    };
+
+    current_screenshot(&mut conn, id).map(|screenshot| Either::Left(Tagged::new(screenshot)))
+}
+
+#[get("/?<query..>")]
+pub fn get_screenshots(query: ScreenshotQuery) -> Result<Json<Vec<WplaceScreenshot>>, ApiError> {
+    use crate::schema::wplace::dsl::*;
+
+    let mut conn = connect_db()?;
+    let mut screenshot_query = wplace.filter(deleted_at.is_null()).into_boxed();
+
+    if let Some(owner_filter) = query.owner_id {
+        screenshot_query = screenshot_query.filter(owner_id.eq(owner_filter));
+    }
+
+    if let Some(created_after_filter) = &query.created_after {
+        let timestamp = parse_timestamp(created_after_filter)
+            .ok_or_else(|| ApiError::bad_request("invalid createdAfter timestamp"))?;
+        screenshot_query = screenshot_query.filter(created_at.gt(timestamp));
+    }
+
+    if let Some(updated_since_filter) = &query.updated_since {
+        let timestamp = parse_timestamp(updated_since_filter)
+            .ok_or_else(|| ApiError::bad_request("invalid updatedSince timestamp"))?;
+        screenshot_query = screenshot_query.filter(updated_at.ge(timestamp));
+    }
+
+    if let Some(captured_after_filter) = &query.captured_after {
+        let timestamp = parse_timestamp(captured_after_filter)
+            .ok_or_else(|| ApiError::bad_request("invalid capturedAfter timestamp"))?;
+        screenshot_query = screenshot_query.filter(captured_at.gt(timestamp));
+    }
+
+    if let Some(captured_before_filter) = &query.captured_before {
+        let timestamp = parse_timestamp(captured_before_filter)
+            .ok_or_else(|| ApiError::bad_request("invalid capturedBefore timestamp"))?;
+        screenshot_query = screenshot_query.filter(captured_at.lt(timestamp));
+    }
+
+    if let Some(artwork_filter) = &query.artwork {
+        screenshot_query = screenshot_query.filter(artwork.eq(artwork_filter));
+    }
+
+    if let Some(album_filter) = query.album_id {
+        screenshot_query = screenshot_query.filter(album_id.eq(album_filter));
+    }
+
+    match (query.min_x, query.min_y, query.max_x, query.max_y) {
+        (None, None, None, None) => {}
+        (Some(min_x), Some(min_y), Some(max_x), Some(max_y))
+            if min_x <= max_x && min_y <= max_y =>
+        {
+            screenshot_query = screenshot_query
+                .filter((tile_x * TILE_SIZE + pixel_x).le(max_x))
+                .filter((tile_x * TILE_SIZE + pixel_x + width).gt(min_x))
+                .filter((tile_y * TILE_SIZE + pixel_y).le(max_y))
+                .filter((tile_y * TILE_SIZE + pixel_y + height).gt(min_y));
+        }
+        _ => {
+            return Err(ApiError::bad_request(
+                "a region needs minX <= maxX and minY <= maxY, all four given",
+            ));
+        }
+    }
+
+    if let Some(sort_by) = &query.sort {
+        match sort_by.as_str() {
+            "created" => screenshot_query = screenshot_query.order(created_at.desc()),
+            "updated" => screenshot_query = screenshot_query.order(updated_at.desc()),
+            "captured" => screenshot_query = screenshot_query.order(captured_at.asc()),
+            _ => {}
+        }
+    }
+
+    let results = screenshot_query.load::<WplaceScreenshot>(&mut conn)?;
+
+    Ok(Json(results))
+}
+
+/// Every capture of an artwork, oldest first, to follow how it changed.
+#[get("/artworks/<name>")]
+pub fn get_artwork_timeline(name: &str) -> Result<Json<Vec<WplaceScreenshot>>, ApiError> {
+    let mut conn = connect_db()?;
+
+    let captures = wplace::table
+        .filter(wplace::artwork.eq(name))
+        .filter(wplace::deleted_at.is_null())
+        .order((wplace::captured_at.asc(), wplace::id.asc()))
+        .load::<WplaceScreenshot>(&mut conn)?;
+
+    if captures.is_empty() {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok(Json(captures))
 }
 
 #[put("/<id>", format = "json", data = "<update_data>")]
 pub fn update_screenshot(
-    _user: User,
+    user: User,
+    if_match: IfMatch,
     id: i32,
     update_data: Json<UpdateWplaceScreenshot>,
-) -> Option<Json<WplaceScreenshot>> {
-    _user.require_admin().expect("User is not admin");
-    let mut conn = connect_db();
-    diesel::update(wplace::table.find(id))
-        .set(&update_data.into_inner())
-        .execute(&mut conn)
-        .expect("Failed to set updated data");
-
-    wplace::table
-        .find(id)
-        .first::<WplaceScreenshot>(&mut conn)
-        .ok()
-        .map(Json)
+) -> Result<Tagged<WplaceScreenshot>, ApiError> {
+    let mut conn = connect_db()?;
+    require_owner(&mut conn, &user, ItemKind::Wplace, id)?;
+    update_data.validate()?;
+    conn.transaction::<_, ApiError, _>(|conn| {
+        if_match.check(conn, ItemKind::Wplace, id, current_screenshot)?;
+        Ok(
+            diesel::update(wplace::table.find(id).filter(wplace::deleted_at.is_null()))
+                .set(&update_data.into_inner())
+                .get_result::<WplaceScreenshot>(conn)?,
+        )
+    })
+    .map(Tagged::new)
 }
 
 #[delete("/<id>")]
-pub fn delete_screenshot(_user: User, id: i32) -> Option<Json<WplaceScreenshot>> {
-    _user.require_admin().expect("User is not admin");
-    let mut conn = connect_db();
-    let screenshot = wplace::table
-        .find(id)
-        .first::<WplaceScreenshot>(&mut conn)
-        .expect("Failed to find screenshot");
+pub fn delete_screenshot(
+    user: User,
+    if_match: IfMatch,
+    id: i32,
+) -> Result<Json<WplaceScreenshot>, ApiError> {
+    let mut conn = connect_db()?;
+    require_owner(&mut conn, &user, ItemKind::Wplace, id)?;
+    conn.transaction::<_, ApiError, _>(|conn| {
+        if_match.check(conn, ItemKind::Wplace, id, current_screenshot)?;
+        Ok(
+            diesel::update(wplace::table.find(id).filter(wplace::deleted_at.is_null()))
+                .set(wplace::deleted_at.eq(diesel::dsl::now.nullable()))
+                .get_result::<WplaceScreenshot>(conn)?,
+        )
+    })
+    .map(Json)
+}
 
-    diesel::delete(wplace::table.find(id))
-        .execute(&mut conn)
-        .expect("Failed to delete screenshot");
+#[post("/<id>/restore")]
+pub fn restore_screenshot(user: User, id: i32) -> Result<Json<WplaceScreenshot>, ApiError> {
+    let mut conn = connect_db()?;
+    require_owner(&mut conn, &user, ItemKind::Wplace, id)?;
 
-    Some(Json(screenshot))
+    diesel::update(
+        wplace::table
+            .find(id)
+            .filter(wplace::deleted_at.is_not_null()),
+    )
+    .set(wplace::deleted_at.eq(None::<chrono::NaiveDateTime>))
+    .get_result::<WplaceScreenshot>(&mut conn)
+    .map(Json)
+    .map_err(ApiError::from)
 }
 
 #[patch("/<id>", format = "json", data = "<update_data>")]
 pub fn patch_screenshot(
-    _user: User,
+    user: User,
+    if_match: IfMatch,
     id: i32,
     update_data: Json<UpdateWplaceScreenshot>,
-) -> Option<Json<WplaceScreenshot>> {
-    _user.require_admin().expect("User is not admin");
-    let mut conn = connect_db();
+) -> Result<Tagged<WplaceScreenshot>, ApiError> {
+    let mut conn = connect_db()?;
+    require_owner(&mut conn, &user, ItemKind::Wplace, id)?;
+    update_data.validate()?;
+    conn.transaction::<_, ApiError, _>(|conn| {
+        if_match.check(conn, ItemKind::Wplace, id, current_screenshot)?;
+        Ok(
+            diesel::update(wplace::table.find(id).filter(wplace::deleted_at.is_null()))
+                .set(&update_data.into_inner())
+                .get_result::<WplaceScreenshot>(conn)?,
+        )
+    })
+    .map(Tagged::new)
+}
 
-    diesel::update(wplace::table.find(id))
-        .set(&update_data.into_inner())
-        .execute(&mut conn)
-        .ok()?;
+fn album_response(conn: &mut PgConnection, album: WplaceAlbum) -> QueryResult<WplaceAlbumResponse> {
+    let screenshots = wplace::table
+        .filter(wplace::album_id.eq(album.id))
+        .filter(wplace::deleted_at.is_null())
+        .order((wplace::captured_at.asc(), wplace::id.asc()))
+        .load::<WplaceScreenshot>(conn)?;
 
-    wplace::table
-        .find(id)
-        .first::<WplaceScreenshot>(&mut conn)
-        .ok()
+    Ok(WplaceAlbumResponse { album, screenshots })
+}
+
+fn find_album(conn: &mut PgConnection, album_id: i32) -> Result<WplaceAlbum, ApiError> {
+    wplace_albums::table
+        .find(album_id)
+        .first::<WplaceAlbum>(conn)
+        .optional()?
+        .ok_or(ApiError::NotFound)
+}
+
+/// Locks an album's row until the end of the current transaction and loads it
+/// the way `GET` returns it.
+fn lock_album(conn: &mut PgConnection, album_id: i32) -> Result<WplaceAlbumResponse, ApiError> {
+    let album = wplace_albums::table
+        .find(album_id)
+        .for_update()
+        .first::<WplaceAlbum>(conn)
+        .optional()?
+        .ok_or(ApiError::NotFound)?;
+
+    Ok(album_response(conn, album)?)
+}
+
+#[get("/")]
+pub fn get_wplace_albums() -> Result<Json<Vec<WplaceAlbum>>, ApiError> {
+    let mut conn = connect_db()?;
+
+    wplace_albums::table
+        .order(wplace_albums::name.asc())
+        .load::<WplaceAlbum>(&mut conn)
         .map(Json)
+        .map_err(ApiError::from)
+}
+
+#[get("/<album_id>")]
+pub fn get_album(album_id: i32) -> Result<Tagged<WplaceAlbumResponse>, ApiError> {
+    let mut conn = connect_db()?;
+
+    let album = find_album(&mut conn, album_id)?;
+    let response = album_response(&mut conn, album)?;
+
+    Ok(Tagged::new(response))
+}
+
+#[post("/", format = "json", data = "<new_album>")]
+pub fn create_album(
+    _admin: AdminUser,
+    new_album: Json<NewWplaceAlbum>,
+) -> Result<Created<WplaceAlbum>, ApiError> {
+    new_album.validate()?;
+
+    let mut conn = connect_db()?;
+
+    let album = diesel::insert_into(wplace_albums::table)
+        .values(&new_album.into_inner())
+        .get_result::<WplaceAlbum>(&mut conn)?;
+
+    Ok(Created::new(format!("/wplace/albums/{}", album.id), album))
+}
+
+#[patch("/<album_id>", format = "json", data = "<changes>")]
+pub fn update_wplace_album(
+    _admin: AdminUser,
+    if_match: IfMatch,
+    album_id: i32,
+    changes: Json<UpdateWplaceAlbum>,
+) -> Result<Tagged<WplaceAlbumResponse>, ApiError> {
+    changes.validate()?;
+
+    let mut conn = connect_db()?;
+
+    let response = conn.transaction::<_, ApiError, _>(|conn| {
+        if_match.check_with(conn, |conn| lock_album(conn, album_id))?;
+        let album = diesel::update(wplace_albums::table.find(album_id))
+            .set(&changes.into_inner())
+            .get_result::<WplaceAlbum>(conn)?;
+        Ok(album_response(conn, album)?)
+    })?;
+
+    Ok(Tagged::new(response))
+}
+
+/// Deletes an album. Its screenshots are kept and simply leave the album.
+#[delete("/<album_id>")]
+pub fn delete_wplace_album(
+    _admin: AdminUser,
+    if_match: IfMatch,
+    album_id: i32,
+) -> Result<Json<WplaceAlbum>, ApiError> {
+    let mut conn = connect_db()?;
+
+    conn.transaction::<_, ApiError, _>(|conn| {
+        if_match.check_with(conn, |conn| lock_album(conn, album_id))?;
+        Ok(diesel::delete(wplace_albums::table.find(album_id)).get_result::<WplaceAlbum>(conn)?)
+    })
+    .map(Json)
+}
+
+/// Moves a screenshot into the album, out of any album it was in before.
+#[put("/<album_id>/screenshots/<screenshot_id>")]
+pub fn add_album_screenshot(
+    _admin: AdminUser,
+    album_id: i32,
+    screenshot_id: i32,
+) -> Result<Json<WplaceAlbumResponse>, ApiError> {
+    let mut conn = connect_db()?;
+    let album = find_album(&mut conn, album_id)?;
+
+    let updated = diesel::update(
+        wplace::table
+            .find(screenshot_id)
+            .filter(wplace::deleted_at.is_null()),
+    )
+    .set(wplace::album_id.eq(Some(album_id)))
+    .execute(&mut conn)?;
+
+    if updated == 0 {
+        return Err(ApiError::NotFound);
+    }
+
+    let response = album_response(&mut conn, album)?;
+
+    Ok(Json(response))
+}
+
+#[delete("/<album_id>/screenshots/<screenshot_id>")]
+pub fn remove_album_screenshot(
+    _admin: AdminUser,
+    album_id: i32,
+    screenshot_id: i32,
+) -> Result<Json<WplaceAlbumResponse>, ApiError> {
+    let mut conn = connect_db()?;
+    let album = find_album(&mut conn, album_id)?;
+
+    let updated = diesel::update(
+        wplace::table
+            .find(screenshot_id)
+            .filter(wplace::album_id.eq(album_id)),
+    )
+    .set(wplace::album_id.eq(None::<i32>))
+    .execute(&mut conn)?;
+
+    if updated == 0 {
+        return Err(ApiError::NotFound);
+    }
+
+    let response = album_response(&mut conn, album)?;
+
+    Ok(Json(response))
 }
 
 pub fn wplace_routes() -> Vec<rocket::Route> {
@@ -109,8 +439,22 @@ pub fn wplace_routes() -> Vec<rocket::Route> {
         create_screenshot,
         get_screenshot,
         get_screenshots,
+        get_artwork_timeline,
         update_screenshot,
         delete_screenshot,
+        restore_screenshot,
         patch_screenshot,
     ]
 }
+
+pub fn wplace_album_routes() -> Vec<rocket::Route> {
+    routes![
+        get_wplace_albums,
+        get_album,
+        create_album,
+        update_wplace_album,
+        delete_wplace_album,
+        add_album_screenshot,
+        remove_album_screenshot,
+    ]
+}
@@ -4,3 +4,4 @@ pub mod models;
 pub mod schema;
 pub mod handlers;
 pub mod errors;
+pub mod validation;
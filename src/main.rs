@@ -1,8 +1,13 @@
 use {
     auth::AuthService,
+    errors::RequestId,
     handlers::index,
-    handlers::{admin::*, books::*, games::*, projects::*, reviews::*, wplace::*, *},
-    rocket::{catchers, http::Method, launch, routes},
+    handlers::{
+        accounts::*, admin::*, albums::*, books::*, collections::*, extra::*, games::*, movies::*,
+        projects::*, ratings::*, relations::*, reviews::*, revisions::*, series::*, shows::*,
+        statuses::*, tags::*, trash::*, wplace::*, *,
+    },
+    rocket::{catchers, fairing::AdHoc, http::Method, launch, routes},
     rocket_cors::{AllowedOrigins, CorsOptions},
 };
 
@@ -12,6 +17,7 @@ pub mod errors;
 pub mod handlers;
 pub mod models;
 pub mod schema;
+pub mod validation;
 
 pub mod cli;
 
@@ -31,9 +37,18 @@ fn rocket() -> _ {
             .map(From::from)
             .collect(),
         )
+        .expose_headers(
+            ["ETag", "Location", "X-Request-Id"]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+        )
         .allow_credentials(true);
 
-    let catchers = catchers![catch401, catch404, catch500];
+    let catchers = catchers![
+        catch400, catch401, catch403, catch404, catch409, catch413, catch422, catch429, catch500,
+        catch503,
+    ];
 
     let auth_service = AuthService::new();
     if std::env::args().len() > 1
@@ -51,12 +66,35 @@ fn rocket() -> _ {
     rocket::build()
         .manage(auth_service)
         .attach(cors.to_cors().unwrap())
+        .attach(RequestId::fairing())
+        .attach(etags::conditional_get())
+        .attach(AdHoc::on_liftoff("Trash purge", |_| {
+            Box::pin(async {
+                tokio::spawn(purge_task());
+            })
+        }))
         .register("/", catchers)
         .mount("/", routes![index])
         .mount("/wplace", wplace_routes())
+        .mount("/wplace/albums", wplace_album_routes())
         .mount("/reviews", reviews_routes())
         .mount("/projects", projects_routes())
         .mount("/read-watch", read_watch_routes())
+        .mount("/read-watch", book_reviews_routes())
+        .mount("/read-watch/series", series_routes())
         .mount("/games", games_routes())
+        .mount("/games", game_reviews_routes())
+        .mount("/movies", movies_routes())
+        .mount("/albums", albums_routes())
+        .mount("/shows", shows_routes())
         .mount("/admin", admin_routes())
+        .mount("/trash", trash_routes())
+        .mount("/revisions", revisions_routes())
+        .mount("/tags", tags_routes())
+        .mount("/statuses", statuses_routes())
+        .mount("/collections", collections_routes())
+        .mount("/extra-schemas", extra_routes())
+        .mount("/ratings", ratings_routes())
+        .mount("/relations", relations_routes())
+        .mount("/users", accounts_routes())
 }
@@ -1,11 +1,216 @@
 use {
-    crate::schema::*,
+    crate::{schema::*, validation},
     chrono::NaiveDateTime,
-    diesel::{pg::Pg, prelude::*},
+    diesel::{
+        AsExpression, FromSqlRow,
+        deserialize::{self, FromSql},
+        pg::{Pg, PgValue, data_types::PgNumeric},
+        prelude::*,
+        serialize::{self, IsNull, Output, ToSql},
+        sql_types::{Jsonb, Numeric},
+    },
     serde::Deserialize,
     serde::Serialize,
+    std::{collections::BTreeMap, io::Write, str::FromStr},
+    uuid::Uuid,
+    validator::Validate,
 };
 
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkKind {
+    Store,
+    Wiki,
+    Official,
+    Review,
+    Docs,
+    Demo,
+    Package,
+    Other,
+}
+
+impl FromStr for LinkKind {
+    type Err = serde_json::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_value(serde_json::Value::String(s.to_lowercase()))
+    }
+}
+
+/// An external link on a book, game or project, e.g.
+/// `{"kind": "store", "label": "Steam", "url": "https://store.steampowered.com/app/1"}`.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct Link {
+    pub kind: LinkKind,
+    pub label: String,
+    pub url: String,
+}
+
+impl Link {
+    /// Requires a non-blank label and an absolute http(s) URL with a host.
+    pub fn is_valid(&self) -> bool {
+        let host = self
+            .url
+            .strip_prefix("https://")
+            .or_else(|| self.url.strip_prefix("http://"))
+            .and_then(|rest| rest.split(['/', '?', '#']).next())
+            .unwrap_or("");
+
+        !self.label.trim().is_empty()
+            && !host.is_empty()
+            && !self.url.chars().any(char::is_whitespace)
+    }
+}
+
+/// A list of links, stored as a JSONB array.
+#[derive(
+    Serialize, Deserialize, Clone, PartialEq, Eq, Debug, Default, AsExpression, FromSqlRow,
+)]
+#[diesel(sql_type = Jsonb)]
+#[serde(transparent)]
+pub struct Links(pub Vec<Link>);
+
+impl Links {
+    pub fn is_valid(&self) -> bool {
+        self.0.iter().all(Link::is_valid)
+    }
+}
+
+impl FromSql<Jsonb, Pg> for Links {
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+        let value = <serde_json::Value as FromSql<Jsonb, Pg>>::from_sql(bytes)?;
+        Ok(serde_json::from_value(value)?)
+    }
+}
+
+impl ToSql<Jsonb, Pg> for Links {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        // JSONB's binary format is a version byte followed by the JSON text.
+        out.write_all(&[1])?;
+        serde_json::to_writer(out, self)?;
+        Ok(IsNull::No)
+    }
+}
+
+/// Install commands keyed by platform, e.g. `{"linux": "cargo install foo"}`,
+/// stored as a JSONB object. `any` holds a command that works everywhere.
+#[derive(
+    Serialize, Deserialize, Clone, PartialEq, Eq, Debug, Default, AsExpression, FromSqlRow,
+)]
+#[diesel(sql_type = Jsonb)]
+#[serde(transparent)]
+pub struct InstallCommands(pub BTreeMap<String, String>);
+
+impl InstallCommands {
+    /// Requires non-blank platforms and commands.
+    pub fn is_valid(&self) -> bool {
+        self.0
+            .iter()
+            .all(|(platform, command)| !platform.trim().is_empty() && !command.trim().is_empty())
+    }
+}
+
+impl FromSql<Jsonb, Pg> for InstallCommands {
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+        let value = <serde_json::Value as FromSql<Jsonb, Pg>>::from_sql(bytes)?;
+        Ok(serde_json::from_value(value)?)
+    }
+}
+
+impl ToSql<Jsonb, Pg> for InstallCommands {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        out.write_all(&[1])?;
+        serde_json::to_writer(out, self)?;
+        Ok(IsNull::No)
+    }
+}
+
+/// A rating, stored as `NUMERIC(6, 2)` so half steps and the like are exact.
+#[derive(
+    Serialize, Deserialize, Clone, Copy, PartialEq, PartialOrd, Debug, AsExpression, FromSqlRow,
+)]
+#[diesel(sql_type = Numeric)]
+#[serde(transparent)]
+pub struct Rating(pub f64);
+
+impl From<Rating> for f64 {
+    fn from(rating: Rating) -> Self {
+        rating.0
+    }
+}
+
+impl TryFrom<PgNumeric> for Rating {
+    type Error = &'static str;
+
+    fn try_from(numeric: PgNumeric) -> Result<Self, Self::Error> {
+        let (negative, weight, digits) = match numeric {
+            PgNumeric::Positive { weight, digits, .. } => (false, weight, digits),
+            PgNumeric::Negative { weight, digits, .. } => (true, weight, digits),
+            PgNumeric::NaN => return Err("NaN is not a valid rating"),
+        };
+
+        // `digits` are base-10000, the first one scaled by 10000^weight.
+        let magnitude = digits
+            .iter()
+            .enumerate()
+            .map(|(i, &digit)| f64::from(digit) * 10_000f64.powi(i32::from(weight) - i as i32))
+            .sum::<f64>();
+
+        Ok(Rating(if negative { -magnitude } else { magnitude }))
+    }
+}
+
+impl TryFrom<Rating> for PgNumeric {
+    type Error = &'static str;
+
+    fn try_from(rating: Rating) -> Result<Self, Self::Error> {
+        if !rating.0.is_finite() {
+            return Err("ratings must be finite");
+        }
+
+        let hundredths = (rating.0.abs() * 100.0).round() as u64;
+        let mut whole = hundredths / 100;
+        let mut digits = Vec::new();
+        while whole > 0 {
+            digits.insert(0, (whole % 10_000) as i16);
+            whole /= 10_000;
+        }
+        let weight = digits.len() as i16 - 1;
+        digits.push((hundredths % 100) as i16 * 100);
+        while digits.last() == Some(&0) {
+            digits.pop();
+        }
+
+        Ok(if rating.0 < 0.0 {
+            PgNumeric::Negative {
+                weight,
+                scale: 2,
+                digits,
+            }
+        } else {
+            PgNumeric::Positive {
+                weight,
+                scale: 2,
+                digits,
+            }
+        })
+    }
+}
+
+impl FromSql<Numeric, Pg> for Rating {
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+        let numeric = <PgNumeric as FromSql<Numeric, Pg>>::from_sql(bytes)?;
+        Ok(Rating::try_from(numeric)?)
+    }
+}
+
+impl ToSql<Numeric, Pg> for Rating {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        let numeric = PgNumeric::try_from(*self)?;
+        ToSql::<Numeric, Pg>::to_sql(&numeric, &mut out.reborrow())
+    }
+}
+
 #[derive(Queryable, Selectable, Serialize, Deserialize)]
 #[diesel(table_name = reviews)]
 #[diesel(check_for_backend(Pg))]
@@ -13,27 +218,48 @@ pub struct Review {
     pub id: i32,
     pub chapter: i32,
     pub description: String,
-    pub rating: i32,
+    pub rating: Rating,
     pub thoughts: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub deleted_at: Option<NaiveDateTime>,
+    pub book_id: Option<i32>,
+    pub game_id: Option<i32>,
+    pub uuid: Uuid,
+    pub slug: String,
+    pub owner_id: i32,
 }
 
-#[derive(Queryable, Insertable, Serialize, Deserialize)]
+#[derive(Queryable, Insertable, Serialize, Deserialize, Validate)]
 #[diesel(table_name = reviews)]
 #[diesel(check_for_backend(Pg))]
 pub struct NewReview<'a> {
+    #[validate(range(min = 0))]
     pub chapter: i32,
+    #[validate(length(max = 20000))]
     pub description: &'a str,
-    pub rating: i32,
+    #[validate(custom(function = "validation::non_negative"))]
+    pub rating: Rating,
+    #[validate(length(max = 20000))]
     pub thoughts: &'a str,
+    pub book_id: Option<i32>,
+    pub game_id: Option<i32>,
+    pub slug: Option<String>,
 }
 
-#[derive(Deserialize, AsChangeset, Serialize)]
+#[derive(Deserialize, AsChangeset, Serialize, Validate)]
 #[diesel(table_name = reviews)]
 #[diesel(check_for_backend(Pg))]
 pub struct UpdateReview {
+    #[validate(length(max = 20000))]
     description: Option<String>,
-    rating: Option<i32>,
+    #[validate(custom(function = "validation::non_negative"))]
+    rating: Option<Rating>,
+    #[validate(length(max = 20000))]
     thoughts: Option<String>,
+    pub book_id: Option<i32>,
+    pub game_id: Option<i32>,
+    slug: Option<String>,
 }
 
 #[derive(Queryable, Selectable, Serialize, Deserialize)]
@@ -43,22 +269,75 @@ pub struct WplaceScreenshot {
     pub id: i32,
     pub alt: String,
     pub coverimage: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub deleted_at: Option<NaiveDateTime>,
+    pub uuid: Uuid,
+    pub slug: String,
+    pub tile_x: Option<i32>,
+    pub tile_y: Option<i32>,
+    pub pixel_x: Option<i32>,
+    pub pixel_y: Option<i32>,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub captured_at: NaiveDateTime,
+    pub artwork: Option<String>,
+    pub album_id: Option<i32>,
+    pub owner_id: i32,
 }
 
-#[derive(Queryable, Insertable, Serialize, Deserialize)]
+#[derive(Queryable, Insertable, Serialize, Deserialize, Validate)]
 #[diesel(table_name = wplace)]
 #[diesel(check_for_backend(Pg))]
 pub struct NewWplaceScreenshot<'a> {
+    #[validate(length(max = 200))]
     pub alt: &'a str,
+    #[validate(url)]
     pub coverimage: &'a str,
+    pub slug: Option<String>,
+    #[validate(range(min = 0))]
+    pub tile_x: Option<i32>,
+    #[validate(range(min = 0))]
+    pub tile_y: Option<i32>,
+    #[validate(range(min = 0, max = 999))]
+    pub pixel_x: Option<i32>,
+    #[validate(range(min = 0, max = 999))]
+    pub pixel_y: Option<i32>,
+    #[validate(range(min = 1))]
+    pub width: Option<i32>,
+    #[validate(range(min = 1))]
+    pub height: Option<i32>,
+    pub captured_at: Option<NaiveDateTime>,
+    #[validate(length(max = 200))]
+    pub artwork: Option<String>,
+    pub album_id: Option<i32>,
 }
 
-#[derive(Deserialize, AsChangeset, Serialize)]
+#[derive(Deserialize, AsChangeset, Serialize, Validate)]
 #[diesel(table_name = wplace)]
 #[diesel(check_for_backend(Pg))]
 pub struct UpdateWplaceScreenshot {
+    #[validate(length(max = 200))]
     alt: Option<String>,
+    #[validate(url)]
     coverimage: Option<String>,
+    slug: Option<String>,
+    #[validate(range(min = 0))]
+    tile_x: Option<i32>,
+    #[validate(range(min = 0))]
+    tile_y: Option<i32>,
+    #[validate(range(min = 0, max = 999))]
+    pixel_x: Option<i32>,
+    #[validate(range(min = 0, max = 999))]
+    pixel_y: Option<i32>,
+    #[validate(range(min = 1))]
+    width: Option<i32>,
+    #[validate(range(min = 1))]
+    height: Option<i32>,
+    captured_at: Option<NaiveDateTime>,
+    #[validate(length(max = 200))]
+    artwork: Option<String>,
+    album_id: Option<i32>,
 }
 
 #[derive(Queryable, Selectable, Serialize, Deserialize, Debug)]
@@ -71,31 +350,81 @@ pub struct Project {
     pub tags: Option<Vec<Option<String>>>,
     pub source: String,
     pub cover_image: Option<String>,
-    pub install_command: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub deleted_at: Option<NaiveDateTime>,
+    pub uuid: Uuid,
+    pub slug: String,
+    pub primary_language: Option<String>,
+    pub secondary_languages: Vec<Option<String>>,
+    pub status: String,
+    pub links: Links,
+    pub install_commands: InstallCommands,
+    pub featured: bool,
+    pub position: i32,
+    pub extra: serde_json::Value,
+    pub owner_id: i32,
 }
 
-#[derive(Insertable, Serialize, Deserialize, Debug)]
+#[derive(Insertable, Serialize, Deserialize, Debug, Validate)]
 #[diesel(table_name = projects)]
 #[diesel(check_for_backend(Pg))]
 pub struct NewProject<'a> {
+    #[validate(custom(function = "validation::not_blank"), length(max = 200))]
     pub name: &'a str,
+    #[validate(length(max = 20000))]
     pub description: &'a str,
+    #[validate(custom(function = "validation::no_missing_entries"))]
     pub tags: Option<Vec<Option<&'a str>>>,
+    #[validate(url)]
     pub source: &'a str,
+    #[validate(url)]
     pub cover_image: Option<&'a str>,
-    pub install_command: Option<&'a str>,
+    pub slug: Option<String>,
+    #[validate(length(max = 200))]
+    pub primary_language: Option<&'a str>,
+    #[serde(default)]
+    #[validate(custom(function = "validation::no_missing_entries"))]
+    pub secondary_languages: Vec<Option<&'a str>>,
+    pub status: Option<&'a str>,
+    #[serde(default)]
+    #[validate(custom(function = "validation::links"))]
+    pub links: Links,
+    #[serde(default)]
+    #[validate(custom(function = "validation::install_commands"))]
+    pub install_commands: InstallCommands,
+    pub featured: Option<bool>,
+    pub position: Option<i32>,
+    pub extra: Option<serde_json::Value>,
 }
 
-#[derive(Deserialize, AsChangeset, Serialize, Debug)]
+#[derive(Deserialize, AsChangeset, Serialize, Debug, Validate)]
 #[diesel(table_name = projects)]
 #[diesel(check_for_backend(Pg))]
 pub struct UpdateProject {
+    #[validate(custom(function = "validation::not_blank"), length(max = 200))]
     pub name: Option<String>,
+    #[validate(length(max = 20000))]
     pub description: Option<String>,
+    #[validate(custom(function = "validation::no_missing_entries"))]
     pub tags: Option<Vec<Option<String>>>,
+    #[validate(url)]
     pub source: Option<String>,
+    #[validate(url)]
     pub cover_image: Option<String>,
-    pub install_command: Option<String>,
+    pub slug: Option<String>,
+    #[validate(length(max = 200))]
+    pub primary_language: Option<String>,
+    #[validate(custom(function = "validation::no_missing_entries"))]
+    pub secondary_languages: Option<Vec<Option<String>>>,
+    pub status: Option<String>,
+    #[validate(custom(function = "validation::links"))]
+    pub links: Option<Links>,
+    #[validate(custom(function = "validation::install_commands"))]
+    pub install_commands: Option<InstallCommands>,
+    pub featured: Option<bool>,
+    pub position: Option<i32>,
+    pub extra: Option<serde_json::Value>,
 }
 
 #[derive(Queryable, Selectable, Serialize, Deserialize, Debug)]
@@ -107,50 +436,170 @@ pub struct Book {
     pub author: String,
     pub genres: Vec<Option<String>>,
     pub tags: Vec<Option<String>>,
-    pub rating: i32,
+    pub rating: Rating,
     pub status: String,
     pub description: String,
     pub my_thoughts: String,
-    pub links: Option<Vec<Option<serde_json::Value>>>,
+    pub links: Links,
     pub cover_image: String,
     pub explicit: bool,
     pub color: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub deleted_at: Option<NaiveDateTime>,
+    pub started_at: Option<NaiveDateTime>,
+    pub finished_at: Option<NaiveDateTime>,
+    pub current_page: Option<i32>,
+    pub total_pages: Option<i32>,
+    pub current_chapter: Option<i32>,
+    pub total_chapters: Option<i32>,
+    pub percent: Option<i32>,
+    pub uuid: Uuid,
+    pub slug: String,
+    pub series_id: Option<i32>,
+    pub series_volume: Option<i32>,
+    pub extra: serde_json::Value,
+    pub owner_id: i32,
 }
 
-#[derive(Insertable, Deserialize, Serialize, Debug)]
+#[derive(Insertable, Deserialize, Serialize, Debug, Validate)]
 #[diesel(table_name = books)]
 #[diesel(check_for_backend(Pg))]
 pub struct NewBook {
+    #[validate(custom(function = "validation::not_blank"), length(max = 200))]
     pub title: String,
+    #[validate(custom(function = "validation::not_blank"), length(max = 200))]
     pub author: String,
+    #[validate(custom(function = "validation::no_blank_entries"))]
     pub genres: Vec<String>,
+    #[validate(custom(function = "validation::no_blank_entries"))]
     pub tags: Vec<String>,
-    pub rating: i32,
+    #[validate(custom(function = "validation::non_negative"))]
+    pub rating: Rating,
+    #[validate(length(max = 200))]
     pub status: String,
+    #[validate(length(max = 20000))]
     pub description: String,
+    #[validate(length(max = 20000))]
     pub my_thoughts: String,
-    pub links: Option<Vec<Option<serde_json::Value>>>,
+    #[serde(default)]
+    #[validate(custom(function = "validation::links"))]
+    pub links: Links,
+    #[validate(url)]
     pub cover_image: String,
     pub explicit: bool,
+    #[validate(custom(function = "validation::hex_color"))]
     pub color: Option<String>,
+    #[validate(range(min = 0))]
+    pub current_page: Option<i32>,
+    #[validate(range(min = 1))]
+    pub total_pages: Option<i32>,
+    #[validate(range(min = 0))]
+    pub current_chapter: Option<i32>,
+    #[validate(range(min = 1))]
+    pub total_chapters: Option<i32>,
+    pub slug: Option<String>,
+    pub series_id: Option<i32>,
+    #[validate(range(min = 1))]
+    pub series_volume: Option<i32>,
+    pub extra: Option<serde_json::Value>,
 }
 
-#[derive(Deserialize, AsChangeset, Serialize, Debug)]
+#[derive(Deserialize, AsChangeset, Serialize, Debug, Default, Validate)]
 #[diesel(table_name = books)]
 #[diesel(check_for_backend(Pg))]
 pub struct UpdateBook {
+    #[validate(custom(function = "validation::not_blank"), length(max = 200))]
     pub title: Option<String>,
+    #[validate(custom(function = "validation::not_blank"), length(max = 200))]
     pub author: Option<String>,
+    #[validate(custom(function = "validation::no_blank_entries"))]
     pub genres: Option<Vec<String>>,
+    #[validate(custom(function = "validation::no_blank_entries"))]
     pub tags: Option<Vec<String>>,
-    pub rating: Option<i32>,
+    #[validate(custom(function = "validation::non_negative"))]
+    pub rating: Option<Rating>,
+    #[validate(length(max = 200))]
     pub status: Option<String>,
+    #[validate(length(max = 20000))]
     pub description: Option<String>,
+    #[validate(length(max = 20000))]
     pub my_thoughts: Option<String>,
-    pub links: Option<Vec<Option<serde_json::Value>>>,
+    #[validate(custom(function = "validation::links"))]
+    pub links: Option<Links>,
+    #[validate(url)]
     pub cover_image: Option<String>,
     pub explicit: Option<bool>,
+    #[validate(custom(function = "validation::hex_color"))]
     pub color: Option<String>,
+    #[validate(range(min = 0))]
+    pub current_page: Option<i32>,
+    #[validate(range(min = 1))]
+    pub total_pages: Option<i32>,
+    #[validate(range(min = 0))]
+    pub current_chapter: Option<i32>,
+    #[validate(range(min = 1))]
+    pub total_chapters: Option<i32>,
+    pub slug: Option<String>,
+    pub series_id: Option<i32>,
+    #[validate(range(min = 1))]
+    pub series_volume: Option<i32>,
+    pub extra: Option<serde_json::Value>,
+}
+
+#[derive(Queryable, Selectable, Serialize, Deserialize, Debug)]
+#[diesel(table_name = book_progress)]
+#[diesel(check_for_backend(Pg))]
+pub struct BookProgress {
+    pub id: i32,
+    pub book_id: i32,
+    pub page: Option<i32>,
+    pub chapter: Option<i32>,
+    pub note: Option<String>,
+    pub logged_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = book_progress)]
+#[diesel(check_for_backend(Pg))]
+pub struct NewBookProgress {
+    pub book_id: i32,
+    pub page: Option<i32>,
+    pub chapter: Option<i32>,
+    pub note: Option<String>,
+    pub logged_at: Option<NaiveDateTime>,
+}
+
+#[derive(Queryable, Selectable, Serialize, Deserialize, Debug)]
+#[diesel(table_name = series)]
+#[diesel(check_for_backend(Pg))]
+pub struct Series {
+    pub id: i32,
+    pub name: String,
+    pub description: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Deserialize, Serialize, Debug, Validate)]
+#[diesel(table_name = series)]
+#[diesel(check_for_backend(Pg))]
+pub struct NewSeries {
+    #[validate(custom(function = "validation::not_blank"), length(max = 200))]
+    pub name: String,
+    #[serde(default)]
+    #[validate(length(max = 20000))]
+    pub description: String,
+}
+
+#[derive(Deserialize, AsChangeset, Serialize, Debug, Validate)]
+#[diesel(table_name = series)]
+#[diesel(check_for_backend(Pg))]
+pub struct UpdateSeries {
+    #[validate(custom(function = "validation::not_blank"), length(max = 200))]
+    pub name: Option<String>,
+    #[validate(length(max = 20000))]
+    pub description: Option<String>,
 }
 
 #[derive(Queryable, Selectable, Serialize, Deserialize, Debug)]
@@ -162,53 +611,562 @@ pub struct Game {
     pub developer: String,
     pub genres: Vec<Option<String>>,
     pub tags: Vec<Option<String>>,
-    pub rating: i32,
+    pub rating: Rating,
     pub status: String,
     pub description: String,
     pub my_thoughts: String,
-    pub links: Option<serde_json::Value>,
+    pub links: Links,
     pub cover_image: String,
     pub explicit: bool,
     pub percent: i32,
     pub bad: bool,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub deleted_at: Option<NaiveDateTime>,
+    pub started_at: Option<NaiveDateTime>,
+    pub finished_at: Option<NaiveDateTime>,
+    pub uuid: Uuid,
+    pub slug: String,
+    pub extra: serde_json::Value,
+    pub owner_id: i32,
 }
 
-#[derive(Insertable, Deserialize, Serialize, Debug)]
+#[derive(Insertable, Deserialize, Serialize, Debug, Validate)]
 #[diesel(table_name = games)]
 #[diesel(check_for_backend(Pg))]
 pub struct NewGame<'a> {
+    #[validate(custom(function = "validation::not_blank"), length(max = 200))]
     pub title: &'a str,
+    #[validate(custom(function = "validation::not_blank"), length(max = 200))]
     pub developer: &'a str,
+    #[validate(custom(function = "validation::no_blank_entries"))]
     pub genres: Vec<&'a str>,
+    #[validate(custom(function = "validation::no_blank_entries"))]
     pub tags: Vec<&'a str>,
-    pub rating: i32,
+    #[validate(custom(function = "validation::non_negative"))]
+    pub rating: Rating,
+    #[validate(length(max = 200))]
     pub status: &'a str,
+    #[validate(length(max = 20000))]
     pub description: &'a str,
+    #[validate(length(max = 20000))]
     pub my_thoughts: &'a str,
-    pub links: Option<serde_json::Value>,
+    #[serde(default)]
+    #[validate(custom(function = "validation::links"))]
+    pub links: Links,
+    #[validate(url)]
     pub cover_image: &'a str,
     pub explicit: bool,
+    #[validate(range(min = 0, max = 100))]
     pub percent: i32,
     pub bad: bool,
+    pub slug: Option<String>,
+    pub extra: Option<serde_json::Value>,
 }
 
-#[derive(Deserialize, AsChangeset, Serialize, Debug)]
+#[derive(Deserialize, AsChangeset, Serialize, Debug, Validate)]
 #[diesel(table_name = games)]
 #[diesel(check_for_backend(Pg))]
 pub struct UpdateGame {
+    #[validate(custom(function = "validation::not_blank"), length(max = 200))]
     pub title: Option<String>,
+    #[validate(custom(function = "validation::not_blank"), length(max = 200))]
     pub developer: Option<String>,
+    #[validate(custom(function = "validation::no_blank_entries"))]
     pub genres: Option<Vec<String>>,
+    #[validate(custom(function = "validation::no_blank_entries"))]
     pub tags: Option<Vec<String>>,
-    pub rating: Option<i32>,
+    #[validate(custom(function = "validation::non_negative"))]
+    pub rating: Option<Rating>,
+    #[validate(length(max = 200))]
     pub status: Option<String>,
+    #[validate(length(max = 20000))]
     pub description: Option<String>,
+    #[validate(length(max = 20000))]
     pub my_thoughts: Option<String>,
-    pub links: Option<serde_json::Value>,
+    #[validate(custom(function = "validation::links"))]
+    pub links: Option<Links>,
+    #[validate(url)]
     pub cover_image: Option<String>,
     pub explicit: Option<bool>,
+    #[validate(range(min = 0, max = 100))]
     pub percent: Option<i32>,
     pub bad: Option<bool>,
+    pub slug: Option<String>,
+    pub extra: Option<serde_json::Value>,
+}
+
+#[derive(Queryable, Selectable, Serialize, Deserialize, Debug)]
+#[diesel(table_name = albums)]
+#[diesel(check_for_backend(Pg))]
+pub struct Album {
+    pub id: i32,
+    pub title: String,
+    pub artist: String,
+    pub release_year: Option<i32>,
+    pub genres: Vec<Option<String>>,
+    pub tags: Vec<Option<String>>,
+    pub rating: Option<Rating>,
+    pub description: String,
+    pub my_thoughts: String,
+    pub cover_image: String,
+    pub explicit: bool,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub deleted_at: Option<NaiveDateTime>,
+    pub uuid: Uuid,
+    pub slug: String,
+}
+
+#[derive(Insertable, Deserialize, Serialize, Debug, Validate)]
+#[diesel(table_name = albums)]
+#[diesel(check_for_backend(Pg))]
+pub struct NewAlbum<'a> {
+    #[validate(custom(function = "validation::not_blank"), length(max = 200))]
+    pub title: &'a str,
+    #[validate(custom(function = "validation::not_blank"), length(max = 200))]
+    pub artist: &'a str,
+    #[validate(range(min = 1, max = 9999))]
+    pub release_year: Option<i32>,
+    #[validate(custom(function = "validation::no_blank_entries"))]
+    pub genres: Vec<&'a str>,
+    #[validate(custom(function = "validation::no_blank_entries"))]
+    pub tags: Vec<&'a str>,
+    #[validate(custom(function = "validation::non_negative"))]
+    pub rating: Option<Rating>,
+    #[validate(length(max = 20000))]
+    pub description: &'a str,
+    #[validate(length(max = 20000))]
+    pub my_thoughts: &'a str,
+    #[validate(url)]
+    pub cover_image: &'a str,
+    pub explicit: bool,
+    pub slug: Option<String>,
+}
+
+#[derive(Deserialize, AsChangeset, Serialize, Debug, Default, Validate)]
+#[diesel(table_name = albums)]
+#[diesel(check_for_backend(Pg))]
+pub struct UpdateAlbum {
+    #[validate(custom(function = "validation::not_blank"), length(max = 200))]
+    pub title: Option<String>,
+    #[validate(custom(function = "validation::not_blank"), length(max = 200))]
+    pub artist: Option<String>,
+    #[validate(range(min = 1, max = 9999))]
+    pub release_year: Option<i32>,
+    #[validate(custom(function = "validation::no_blank_entries"))]
+    pub genres: Option<Vec<String>>,
+    #[validate(custom(function = "validation::no_blank_entries"))]
+    pub tags: Option<Vec<String>>,
+    #[validate(custom(function = "validation::non_negative"))]
+    pub rating: Option<Rating>,
+    #[validate(length(max = 20000))]
+    pub description: Option<String>,
+    #[validate(length(max = 20000))]
+    pub my_thoughts: Option<String>,
+    #[validate(url)]
+    pub cover_image: Option<String>,
+    pub explicit: Option<bool>,
+    pub slug: Option<String>,
+}
+
+#[derive(Queryable, Selectable, Serialize, Deserialize, Debug)]
+#[diesel(table_name = album_tracks)]
+#[diesel(check_for_backend(Pg))]
+pub struct AlbumTrack {
+    pub id: i32,
+    pub album_id: i32,
+    pub position: i32,
+    pub title: String,
+    pub duration_seconds: Option<i32>,
+    pub rating: Option<Rating>,
+    pub favorite: bool,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = album_tracks)]
+#[diesel(check_for_backend(Pg))]
+pub struct NewAlbumTrack {
+    pub album_id: i32,
+    pub position: i32,
+    pub title: String,
+    pub duration_seconds: Option<i32>,
+    pub rating: Option<Rating>,
+    pub favorite: bool,
+}
+
+#[derive(Deserialize, AsChangeset, Serialize, Debug, Validate)]
+#[diesel(table_name = album_tracks)]
+#[diesel(check_for_backend(Pg))]
+pub struct UpdateAlbumTrack {
+    #[validate(range(min = 1))]
+    pub position: Option<i32>,
+    #[validate(custom(function = "validation::not_blank"), length(max = 200))]
+    pub title: Option<String>,
+    #[validate(range(min = 0))]
+    pub duration_seconds: Option<i32>,
+    #[validate(custom(function = "validation::non_negative"))]
+    pub rating: Option<Rating>,
+    pub favorite: Option<bool>,
+}
+
+#[derive(Queryable, Selectable, Serialize, Deserialize, Debug)]
+#[diesel(table_name = movies)]
+#[diesel(check_for_backend(Pg))]
+pub struct Movie {
+    pub id: i32,
+    pub title: String,
+    pub director: Option<String>,
+    pub studio: Option<String>,
+    pub genres: Vec<Option<String>>,
+    pub tags: Vec<Option<String>>,
+    pub rating: Rating,
+    pub status: String,
+    pub description: String,
+    pub my_thoughts: String,
+    pub links: Option<serde_json::Value>,
+    pub cover_image: String,
+    pub explicit: bool,
+    pub runtime_minutes: Option<i32>,
+    pub release_year: Option<i32>,
+    pub first_watched_at: Option<NaiveDateTime>,
+    pub last_watched_at: Option<NaiveDateTime>,
+    pub rewatch_count: i32,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub deleted_at: Option<NaiveDateTime>,
+    pub started_at: Option<NaiveDateTime>,
+    pub finished_at: Option<NaiveDateTime>,
+    pub uuid: Uuid,
+    pub slug: String,
+}
+
+#[derive(Insertable, Deserialize, Serialize, Debug, Validate)]
+#[diesel(table_name = movies)]
+#[diesel(check_for_backend(Pg))]
+pub struct NewMovie<'a> {
+    #[validate(custom(function = "validation::not_blank"), length(max = 200))]
+    pub title: &'a str,
+    #[validate(length(max = 200))]
+    pub director: Option<String>,
+    #[validate(length(max = 200))]
+    pub studio: Option<String>,
+    #[validate(custom(function = "validation::no_blank_entries"))]
+    pub genres: Vec<&'a str>,
+    #[validate(custom(function = "validation::no_blank_entries"))]
+    pub tags: Vec<&'a str>,
+    #[validate(custom(function = "validation::non_negative"))]
+    pub rating: Rating,
+    #[validate(length(max = 200))]
+    pub status: &'a str,
+    #[validate(length(max = 20000))]
+    pub description: &'a str,
+    #[validate(length(max = 20000))]
+    pub my_thoughts: &'a str,
+    pub links: Option<serde_json::Value>,
+    #[validate(url)]
+    pub cover_image: &'a str,
+    pub explicit: bool,
+    #[validate(range(min = 1))]
+    pub runtime_minutes: Option<i32>,
+    #[validate(range(min = 1, max = 9999))]
+    pub release_year: Option<i32>,
+    pub first_watched_at: Option<NaiveDateTime>,
+    pub last_watched_at: Option<NaiveDateTime>,
+    #[validate(range(min = 0))]
+    pub rewatch_count: Option<i32>,
+    pub slug: Option<String>,
+}
+
+#[derive(Deserialize, AsChangeset, Serialize, Debug, Default, Validate)]
+#[diesel(table_name = movies)]
+#[diesel(check_for_backend(Pg))]
+pub struct UpdateMovie {
+    #[validate(custom(function = "validation::not_blank"), length(max = 200))]
+    pub title: Option<String>,
+    #[validate(length(max = 200))]
+    pub director: Option<String>,
+    #[validate(length(max = 200))]
+    pub studio: Option<String>,
+    #[validate(custom(function = "validation::no_blank_entries"))]
+    pub genres: Option<Vec<String>>,
+    #[validate(custom(function = "validation::no_blank_entries"))]
+    pub tags: Option<Vec<String>>,
+    #[validate(custom(function = "validation::non_negative"))]
+    pub rating: Option<Rating>,
+    #[validate(length(max = 200))]
+    pub status: Option<String>,
+    #[validate(length(max = 20000))]
+    pub description: Option<String>,
+    #[validate(length(max = 20000))]
+    pub my_thoughts: Option<String>,
+    pub links: Option<serde_json::Value>,
+    #[validate(url)]
+    pub cover_image: Option<String>,
+    pub explicit: Option<bool>,
+    #[validate(range(min = 1))]
+    pub runtime_minutes: Option<i32>,
+    #[validate(range(min = 1, max = 9999))]
+    pub release_year: Option<i32>,
+    pub first_watched_at: Option<NaiveDateTime>,
+    pub last_watched_at: Option<NaiveDateTime>,
+    #[validate(range(min = 0))]
+    pub rewatch_count: Option<i32>,
+    pub slug: Option<String>,
+}
+
+#[derive(Queryable, Selectable, Serialize, Deserialize, Debug)]
+#[diesel(table_name = shows)]
+#[diesel(check_for_backend(Pg))]
+pub struct Show {
+    pub id: i32,
+    pub title: String,
+    pub director: Option<String>,
+    pub studio: Option<String>,
+    pub genres: Vec<Option<String>>,
+    pub tags: Vec<Option<String>>,
+    pub rating: Rating,
+    pub status: String,
+    pub description: String,
+    pub my_thoughts: String,
+    pub links: Option<serde_json::Value>,
+    pub cover_image: String,
+    pub explicit: bool,
+    pub runtime_minutes: Option<i32>,
+    pub total_seasons: Option<i32>,
+    pub total_episodes: Option<i32>,
+    pub current_season: Option<i32>,
+    pub current_episode: Option<i32>,
+    pub episodes_watched: i32,
+    pub percent: Option<i32>,
+    pub first_watched_at: Option<NaiveDateTime>,
+    pub last_watched_at: Option<NaiveDateTime>,
+    pub rewatch_count: i32,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub deleted_at: Option<NaiveDateTime>,
+    pub started_at: Option<NaiveDateTime>,
+    pub finished_at: Option<NaiveDateTime>,
+    pub uuid: Uuid,
+    pub slug: String,
+}
+
+#[derive(Insertable, Deserialize, Serialize, Debug, Validate)]
+#[diesel(table_name = shows)]
+#[diesel(check_for_backend(Pg))]
+pub struct NewShow<'a> {
+    #[validate(custom(function = "validation::not_blank"), length(max = 200))]
+    pub title: &'a str,
+    #[validate(length(max = 200))]
+    pub director: Option<String>,
+    #[validate(length(max = 200))]
+    pub studio: Option<String>,
+    #[validate(custom(function = "validation::no_blank_entries"))]
+    pub genres: Vec<&'a str>,
+    #[validate(custom(function = "validation::no_blank_entries"))]
+    pub tags: Vec<&'a str>,
+    #[validate(custom(function = "validation::non_negative"))]
+    pub rating: Rating,
+    #[validate(length(max = 200))]
+    pub status: &'a str,
+    #[validate(length(max = 20000))]
+    pub description: &'a str,
+    #[validate(length(max = 20000))]
+    pub my_thoughts: &'a str,
+    pub links: Option<serde_json::Value>,
+    #[validate(url)]
+    pub cover_image: &'a str,
+    pub explicit: bool,
+    #[validate(range(min = 1))]
+    pub runtime_minutes: Option<i32>,
+    #[validate(range(min = 1))]
+    pub total_seasons: Option<i32>,
+    #[validate(range(min = 1))]
+    pub total_episodes: Option<i32>,
+    #[validate(range(min = 1))]
+    pub current_season: Option<i32>,
+    #[validate(range(min = 1))]
+    pub current_episode: Option<i32>,
+    #[validate(range(min = 0))]
+    pub episodes_watched: Option<i32>,
+    #[validate(range(min = 0))]
+    pub rewatch_count: Option<i32>,
+    pub slug: Option<String>,
+}
+
+#[derive(Deserialize, AsChangeset, Serialize, Debug, Default, Validate)]
+#[diesel(table_name = shows)]
+#[diesel(check_for_backend(Pg))]
+pub struct UpdateShow {
+    #[validate(custom(function = "validation::not_blank"), length(max = 200))]
+    pub title: Option<String>,
+    #[validate(length(max = 200))]
+    pub director: Option<String>,
+    #[validate(length(max = 200))]
+    pub studio: Option<String>,
+    #[validate(custom(function = "validation::no_blank_entries"))]
+    pub genres: Option<Vec<String>>,
+    #[validate(custom(function = "validation::no_blank_entries"))]
+    pub tags: Option<Vec<String>>,
+    #[validate(custom(function = "validation::non_negative"))]
+    pub rating: Option<Rating>,
+    #[validate(length(max = 200))]
+    pub status: Option<String>,
+    #[validate(length(max = 20000))]
+    pub description: Option<String>,
+    #[validate(length(max = 20000))]
+    pub my_thoughts: Option<String>,
+    pub links: Option<serde_json::Value>,
+    #[validate(url)]
+    pub cover_image: Option<String>,
+    pub explicit: Option<bool>,
+    #[validate(range(min = 1))]
+    pub runtime_minutes: Option<i32>,
+    #[validate(range(min = 1))]
+    pub total_seasons: Option<i32>,
+    #[validate(range(min = 1))]
+    pub total_episodes: Option<i32>,
+    #[validate(range(min = 1))]
+    pub current_season: Option<i32>,
+    #[validate(range(min = 1))]
+    pub current_episode: Option<i32>,
+    #[validate(range(min = 0))]
+    pub episodes_watched: Option<i32>,
+    pub first_watched_at: Option<NaiveDateTime>,
+    pub last_watched_at: Option<NaiveDateTime>,
+    #[validate(range(min = 0))]
+    pub rewatch_count: Option<i32>,
+    pub slug: Option<String>,
+}
+
+#[derive(Queryable, Selectable, Serialize, Deserialize, Debug)]
+#[diesel(table_name = show_episodes)]
+#[diesel(check_for_backend(Pg))]
+pub struct ShowEpisode {
+    pub id: i32,
+    pub show_id: i32,
+    pub season: i32,
+    pub episode: i32,
+    pub note: Option<String>,
+    pub watched_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = show_episodes)]
+#[diesel(check_for_backend(Pg))]
+pub struct NewShowEpisode {
+    pub show_id: i32,
+    pub season: i32,
+    pub episode: i32,
+    pub note: Option<String>,
+    pub watched_at: Option<NaiveDateTime>,
+}
+
+#[derive(Queryable, Selectable, Serialize, Deserialize, Debug, Clone)]
+#[diesel(table_name = tags)]
+#[diesel(check_for_backend(Pg))]
+pub struct Tag {
+    pub id: i32,
+    pub name: String,
+    pub slug: String,
+    pub kind: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = tags)]
+#[diesel(check_for_backend(Pg))]
+pub struct NewTag<'a> {
+    pub name: &'a str,
+    pub slug: &'a str,
+    pub kind: &'a str,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = book_tags)]
+#[diesel(check_for_backend(Pg))]
+pub struct NewBookTag {
+    pub book_id: i32,
+    pub tag_id: i32,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = game_tags)]
+#[diesel(check_for_backend(Pg))]
+pub struct NewGameTag {
+    pub game_id: i32,
+    pub tag_id: i32,
+}
+
+#[derive(Queryable, Selectable, Insertable, Serialize, Deserialize, Debug)]
+#[diesel(table_name = status_transitions)]
+#[diesel(check_for_backend(Pg))]
+pub struct StatusTransition {
+    pub item_type: String,
+    pub from_status: String,
+    pub to_status: String,
+}
+
+#[derive(Queryable, Selectable, Serialize, Deserialize, Debug)]
+#[diesel(table_name = status_changes)]
+#[diesel(check_for_backend(Pg))]
+pub struct StatusChange {
+    pub id: i32,
+    pub item_type: String,
+    pub item_id: i32,
+    pub from_status: Option<String>,
+    pub to_status: String,
+    pub changed_at: NaiveDateTime,
+}
+
+#[derive(Queryable, Selectable, Serialize, Deserialize, Debug)]
+#[diesel(table_name = revisions)]
+#[diesel(check_for_backend(Pg))]
+pub struct Revision {
+    pub id: i32,
+    pub item_type: String,
+    pub item_id: i32,
+    pub revision: i32,
+    pub data: serde_json::Value,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Queryable, Selectable, Serialize, Deserialize, Debug)]
+#[diesel(table_name = collections)]
+#[diesel(check_for_backend(Pg))]
+pub struct Collection {
+    pub id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    pub fields: serde_json::Value,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = collections)]
+pub struct NewCollection<'a> {
+    pub name: &'a str,
+    pub description: Option<&'a str>,
+    pub fields: serde_json::Value,
+}
+
+#[derive(Queryable, Selectable, Serialize, Deserialize, Debug)]
+#[diesel(table_name = collection_items)]
+#[diesel(check_for_backend(Pg))]
+pub struct CollectionItem {
+    pub id: i32,
+    pub collection_id: i32,
+    pub data: serde_json::Value,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = collection_items)]
+pub struct NewCollectionItem {
+    pub collection_id: i32,
+    pub data: serde_json::Value,
 }
 
 #[derive(Queryable, Selectable, Serialize, Deserialize, Debug, Clone)]
@@ -219,6 +1177,7 @@ pub struct ApiKey {
     pub is_admin: bool,
     pub created_at: NaiveDateTime,
     pub last_used_at: Option<NaiveDateTime>,
+    pub account_id: i32,
 }
 
 #[derive(Insertable, Deserialize, Serialize, Debug)]
@@ -226,4 +1185,240 @@ pub struct ApiKey {
 pub struct NewApiKey {
     pub key_hash: String,
     pub is_admin: bool,
+    pub account_id: i32,
+}
+
+#[derive(Queryable, Selectable, Serialize, Deserialize, Debug, Clone)]
+#[diesel(table_name = rating_scales)]
+#[diesel(check_for_backend(Pg))]
+pub struct RatingScale {
+    pub item_type: String,
+    pub max_rating: Rating,
+    pub step: Rating,
+    pub updated_at: NaiveDateTime,
+}
+
+impl RatingScale {
+    /// Maps a rating on this scale onto 0–1.
+    pub fn normalize(&self, rating: Rating) -> f64 {
+        (rating.0 / self.max_rating.0).clamp(0.0, 1.0)
+    }
+}
+
+#[derive(Deserialize, AsChangeset, Serialize, Debug, Validate)]
+#[diesel(table_name = rating_scales)]
+#[diesel(check_for_backend(Pg))]
+pub struct UpdateRatingScale {
+    #[validate(custom(function = "validation::non_negative"))]
+    pub max_rating: Option<Rating>,
+    #[validate(custom(function = "validation::non_negative"))]
+    pub step: Option<Rating>,
+}
+
+#[derive(Queryable, Selectable, Serialize, Deserialize, Debug, Clone)]
+#[diesel(table_name = relations)]
+#[diesel(check_for_backend(Pg))]
+pub struct Relation {
+    pub id: i32,
+    pub source_type: String,
+    pub source_id: i32,
+    pub kind: String,
+    pub target_type: String,
+    pub target_id: i32,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = relations)]
+#[diesel(check_for_backend(Pg))]
+pub struct NewRelation<'a> {
+    pub source_type: &'a str,
+    pub source_id: i32,
+    pub kind: &'a str,
+    pub target_type: &'a str,
+    pub target_id: i32,
+}
+
+#[derive(Queryable, Selectable, Serialize, Deserialize, Debug)]
+#[diesel(table_name = wplace_albums)]
+#[diesel(check_for_backend(Pg))]
+pub struct WplaceAlbum {
+    pub id: i32,
+    pub name: String,
+    pub description: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Deserialize, Serialize, Debug, Validate)]
+#[diesel(table_name = wplace_albums)]
+#[diesel(check_for_backend(Pg))]
+pub struct NewWplaceAlbum {
+    #[validate(custom(function = "validation::not_blank"), length(max = 200))]
+    pub name: String,
+    #[serde(default)]
+    #[validate(length(max = 20000))]
+    pub description: String,
+}
+
+#[derive(Deserialize, AsChangeset, Serialize, Debug, Validate)]
+#[diesel(table_name = wplace_albums)]
+#[diesel(check_for_backend(Pg))]
+pub struct UpdateWplaceAlbum {
+    #[validate(custom(function = "validation::not_blank"), length(max = 200))]
+    pub name: Option<String>,
+    #[validate(length(max = 20000))]
+    pub description: Option<String>,
+}
+
+#[derive(Queryable, Selectable, Serialize, Deserialize, Debug)]
+#[diesel(table_name = extra_schemas)]
+#[diesel(check_for_backend(Pg))]
+pub struct ExtraSchema {
+    pub item_type: String,
+    pub schema: serde_json::Value,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = extra_schemas)]
+#[diesel(check_for_backend(Pg))]
+pub struct NewExtraSchema<'a> {
+    pub item_type: &'a str,
+    pub schema: &'a serde_json::Value,
+}
+
+#[derive(Queryable, Selectable, Serialize, Deserialize, Debug, Clone)]
+#[diesel(table_name = accounts)]
+#[diesel(check_for_backend(Pg))]
+pub struct Account {
+    pub id: i32,
+    pub handle: String,
+    pub display_name: String,
+    pub bio: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Deserialize, Serialize, Debug, Validate)]
+#[diesel(table_name = accounts)]
+#[diesel(check_for_backend(Pg))]
+pub struct NewAccount {
+    #[validate(custom(function = "validation::handle"))]
+    pub handle: String,
+    #[serde(default)]
+    #[validate(length(max = 200))]
+    pub display_name: String,
+    #[serde(default)]
+    #[validate(length(max = 20000))]
+    pub bio: String,
+}
+
+#[derive(Deserialize, AsChangeset, Serialize, Debug, Validate)]
+#[diesel(table_name = accounts)]
+#[diesel(check_for_backend(Pg))]
+pub struct UpdateAccount {
+    #[validate(custom(function = "validation::handle"))]
+    pub handle: Option<String>,
+    #[validate(length(max = 200))]
+    pub display_name: Option<String>,
+    #[validate(length(max = 20000))]
+    pub bio: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Rating;
+    use diesel::data_types::PgNumeric;
+
+    fn round_trip(value: f64) -> f64 {
+        let numeric = PgNumeric::try_from(Rating(value)).unwrap();
+        Rating::try_from(numeric).unwrap().0
+    }
+
+    fn positive(weight: i16, digits: &[i16]) -> PgNumeric {
+        PgNumeric::Positive {
+            weight,
+            scale: 2,
+            digits: digits.to_vec(),
+        }
+    }
+
+    #[test]
+    fn zero_has_no_digits() {
+        assert_eq!(PgNumeric::try_from(Rating(0.0)), Ok(positive(-1, &[])));
+        assert_eq!(round_trip(0.0), 0.0);
+    }
+
+    #[test]
+    fn whole_ratings_round_trip() {
+        assert_eq!(PgNumeric::try_from(Rating(5.0)), Ok(positive(0, &[5])));
+        for value in [1.0, 3.0, 7.0, 42.0, 9_999.0] {
+            assert_eq!(round_trip(value), value);
+        }
+    }
+
+    #[test]
+    fn fractional_ratings_round_trip() {
+        assert_eq!(
+            PgNumeric::try_from(Rating(4.5)),
+            Ok(positive(0, &[4, 5000]))
+        );
+        assert_eq!(PgNumeric::try_from(Rating(0.25)), Ok(positive(-1, &[2500])));
+        for value in [0.01, 0.5, 2.25, 3.75, 8.99, 1_234.56] {
+            assert_eq!(round_trip(value), value);
+        }
+    }
+
+    #[test]
+    fn values_are_rounded_to_hundredths() {
+        assert_eq!(round_trip(1.23456), 1.23);
+        assert_eq!(round_trip(2.675_000_1), 2.68);
+    }
+
+    #[test]
+    fn scale_bounds_round_trip() {
+        // `rating_scales` allows maxima up to 1000 and steps down to 0.01;
+        // NUMERIC(6, 2) itself tops out at 9999.99.
+        assert_eq!(
+            PgNumeric::try_from(Rating(1_000.0)),
+            Ok(positive(0, &[1000]))
+        );
+        assert_eq!(
+            PgNumeric::try_from(Rating(9_999.99)),
+            Ok(positive(0, &[9999, 9900]))
+        );
+        for value in [0.01, 10.0, 100.0, 1_000.0, 999.99, 9_999.99] {
+            assert_eq!(round_trip(value), value);
+        }
+    }
+
+    #[test]
+    fn ratings_past_one_base_10000_digit_round_trip() {
+        assert_eq!(
+            PgNumeric::try_from(Rating(10_000.5)),
+            Ok(positive(1, &[1, 0, 5000]))
+        );
+        assert_eq!(round_trip(10_000.5), 10_000.5);
+    }
+
+    #[test]
+    fn negative_ratings_keep_their_sign() {
+        assert_eq!(
+            PgNumeric::try_from(Rating(-2.5)),
+            Ok(PgNumeric::Negative {
+                weight: 0,
+                scale: 2,
+                digits: vec![2, 5000],
+            })
+        );
+        assert_eq!(round_trip(-2.5), -2.5);
+    }
+
+    #[test]
+    fn non_finite_values_are_rejected() {
+        assert!(PgNumeric::try_from(Rating(f64::NAN)).is_err());
+        assert!(PgNumeric::try_from(Rating(f64::INFINITY)).is_err());
+        assert!(Rating::try_from(PgNumeric::NaN).is_err());
+    }
 }
@@ -1,5 +1,49 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    accounts (id) {
+        id -> Int4,
+        handle -> Text,
+        display_name -> Text,
+        bio -> Text,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    album_tracks (id) {
+        id -> Int4,
+        album_id -> Int4,
+        position -> Int4,
+        title -> Text,
+        duration_seconds -> Nullable<Int4>,
+        rating -> Nullable<Numeric>,
+        favorite -> Bool,
+    }
+}
+
+diesel::table! {
+    albums (id) {
+        id -> Int4,
+        title -> Text,
+        artist -> Text,
+        release_year -> Nullable<Int4>,
+        genres -> Array<Nullable<Text>>,
+        tags -> Array<Nullable<Text>>,
+        rating -> Nullable<Numeric>,
+        description -> Text,
+        my_thoughts -> Text,
+        cover_image -> Text,
+        explicit -> Bool,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        deleted_at -> Nullable<Timestamp>,
+        uuid -> Uuid,
+        slug -> Text,
+    }
+}
+
 diesel::table! {
     api_keys (id) {
         id -> Int4,
@@ -7,6 +51,25 @@ diesel::table! {
         is_admin -> Bool,
         created_at -> Timestamp,
         last_used_at -> Nullable<Timestamp>,
+        account_id -> Int4,
+    }
+}
+
+diesel::table! {
+    book_progress (id) {
+        id -> Int4,
+        book_id -> Int4,
+        page -> Nullable<Int4>,
+        chapter -> Nullable<Int4>,
+        note -> Nullable<Text>,
+        logged_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    book_tags (book_id, tag_id) {
+        book_id -> Int4,
+        tag_id -> Int4,
     }
 }
 
@@ -17,14 +80,66 @@ diesel::table! {
         author -> Text,
         genres -> Array<Nullable<Text>>,
         tags -> Array<Nullable<Text>>,
-        rating -> Int4,
+        rating -> Numeric,
         status -> Text,
         description -> Text,
         my_thoughts -> Text,
-        links -> Nullable<Array<Nullable<Jsonb>>>,
+        links -> Jsonb,
         cover_image -> Text,
         explicit -> Bool,
         color -> Nullable<Text>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        deleted_at -> Nullable<Timestamp>,
+        started_at -> Nullable<Timestamp>,
+        finished_at -> Nullable<Timestamp>,
+        current_page -> Nullable<Int4>,
+        total_pages -> Nullable<Int4>,
+        current_chapter -> Nullable<Int4>,
+        total_chapters -> Nullable<Int4>,
+        percent -> Nullable<Int4>,
+        uuid -> Uuid,
+        slug -> Text,
+        series_id -> Nullable<Int4>,
+        series_volume -> Nullable<Int4>,
+        extra -> Jsonb,
+        owner_id -> Int4,
+    }
+}
+
+diesel::table! {
+    collection_items (id) {
+        id -> Int4,
+        collection_id -> Int4,
+        data -> Jsonb,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    collections (id) {
+        id -> Int4,
+        name -> Text,
+        description -> Nullable<Text>,
+        fields -> Jsonb,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    extra_schemas (item_type) {
+        item_type -> Text,
+        schema -> Jsonb,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    game_tags (game_id, tag_id) {
+        game_id -> Int4,
+        tag_id -> Int4,
     }
 }
 
@@ -35,15 +150,54 @@ diesel::table! {
         developer -> Text,
         genres -> Array<Nullable<Text>>,
         tags -> Array<Nullable<Text>>,
-        rating -> Int4,
+        rating -> Numeric,
         status -> Text,
         description -> Text,
         my_thoughts -> Text,
-        links -> Nullable<Jsonb>,
+        links -> Jsonb,
         cover_image -> Text,
         explicit -> Bool,
         percent -> Int4,
         bad -> Bool,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        deleted_at -> Nullable<Timestamp>,
+        started_at -> Nullable<Timestamp>,
+        finished_at -> Nullable<Timestamp>,
+        uuid -> Uuid,
+        slug -> Text,
+        extra -> Jsonb,
+        owner_id -> Int4,
+    }
+}
+
+diesel::table! {
+    movies (id) {
+        id -> Int4,
+        title -> Text,
+        director -> Nullable<Text>,
+        studio -> Nullable<Text>,
+        genres -> Array<Nullable<Text>>,
+        tags -> Array<Nullable<Text>>,
+        rating -> Numeric,
+        status -> Text,
+        description -> Text,
+        my_thoughts -> Text,
+        links -> Nullable<Jsonb>,
+        cover_image -> Text,
+        explicit -> Bool,
+        runtime_minutes -> Nullable<Int4>,
+        release_year -> Nullable<Int4>,
+        first_watched_at -> Nullable<Timestamp>,
+        last_watched_at -> Nullable<Timestamp>,
+        rewatch_count -> Int4,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        deleted_at -> Nullable<Timestamp>,
+        started_at -> Nullable<Timestamp>,
+        finished_at -> Nullable<Timestamp>,
+        uuid -> Uuid,
+        slug -> Text,
     }
 }
 
@@ -55,7 +209,41 @@ diesel::table! {
         tags -> Nullable<Array<Nullable<Text>>>,
         source -> Text,
         cover_image -> Nullable<Text>,
-        install_command -> Nullable<Text>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        deleted_at -> Nullable<Timestamp>,
+        uuid -> Uuid,
+        slug -> Text,
+        primary_language -> Nullable<Text>,
+        secondary_languages -> Array<Nullable<Text>>,
+        status -> Text,
+        links -> Jsonb,
+        install_commands -> Jsonb,
+        featured -> Bool,
+        position -> Int4,
+        extra -> Jsonb,
+        owner_id -> Int4,
+    }
+}
+
+diesel::table! {
+    rating_scales (item_type) {
+        item_type -> Text,
+        max_rating -> Numeric,
+        step -> Numeric,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    relations (id) {
+        id -> Int4,
+        source_type -> Text,
+        source_id -> Int4,
+        kind -> Text,
+        target_type -> Text,
+        target_id -> Int4,
+        created_at -> Timestamp,
     }
 }
 
@@ -64,8 +252,121 @@ diesel::table! {
         id -> Int4,
         chapter -> Int4,
         description -> Text,
-        rating -> Int4,
+        rating -> Numeric,
         thoughts -> Text,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        deleted_at -> Nullable<Timestamp>,
+        book_id -> Nullable<Int4>,
+        game_id -> Nullable<Int4>,
+        uuid -> Uuid,
+        slug -> Text,
+        owner_id -> Int4,
+    }
+}
+
+diesel::table! {
+    revisions (id) {
+        id -> Int4,
+        item_type -> Text,
+        item_id -> Int4,
+        revision -> Int4,
+        data -> Jsonb,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    series (id) {
+        id -> Int4,
+        name -> Text,
+        description -> Text,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    show_episodes (id) {
+        id -> Int4,
+        show_id -> Int4,
+        season -> Int4,
+        episode -> Int4,
+        note -> Nullable<Text>,
+        watched_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    shows (id) {
+        id -> Int4,
+        title -> Text,
+        director -> Nullable<Text>,
+        studio -> Nullable<Text>,
+        genres -> Array<Nullable<Text>>,
+        tags -> Array<Nullable<Text>>,
+        rating -> Numeric,
+        status -> Text,
+        description -> Text,
+        my_thoughts -> Text,
+        links -> Nullable<Jsonb>,
+        cover_image -> Text,
+        explicit -> Bool,
+        runtime_minutes -> Nullable<Int4>,
+        total_seasons -> Nullable<Int4>,
+        total_episodes -> Nullable<Int4>,
+        current_season -> Nullable<Int4>,
+        current_episode -> Nullable<Int4>,
+        episodes_watched -> Int4,
+        percent -> Nullable<Int4>,
+        first_watched_at -> Nullable<Timestamp>,
+        last_watched_at -> Nullable<Timestamp>,
+        rewatch_count -> Int4,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        deleted_at -> Nullable<Timestamp>,
+        started_at -> Nullable<Timestamp>,
+        finished_at -> Nullable<Timestamp>,
+        uuid -> Uuid,
+        slug -> Text,
+    }
+}
+
+diesel::table! {
+    slug_history (item_type, slug) {
+        item_type -> Text,
+        slug -> Text,
+        item_id -> Int4,
+        changed_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    status_changes (id) {
+        id -> Int4,
+        item_type -> Text,
+        item_id -> Int4,
+        from_status -> Nullable<Text>,
+        to_status -> Text,
+        changed_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    status_transitions (item_type, from_status, to_status) {
+        item_type -> Text,
+        from_status -> Text,
+        to_status -> Text,
+    }
+}
+
+diesel::table! {
+    tags (id) {
+        id -> Int4,
+        name -> Text,
+        slug -> Text,
+        kind -> Text,
+        created_at -> Timestamp,
     }
 }
 
@@ -74,14 +375,74 @@ diesel::table! {
         id -> Int4,
         alt -> Text,
         coverimage -> Text,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        deleted_at -> Nullable<Timestamp>,
+        uuid -> Uuid,
+        slug -> Text,
+        tile_x -> Nullable<Int4>,
+        tile_y -> Nullable<Int4>,
+        pixel_x -> Nullable<Int4>,
+        pixel_y -> Nullable<Int4>,
+        width -> Nullable<Int4>,
+        height -> Nullable<Int4>,
+        captured_at -> Timestamp,
+        artwork -> Nullable<Text>,
+        album_id -> Nullable<Int4>,
+        owner_id -> Int4,
     }
 }
 
+diesel::table! {
+    wplace_albums (id) {
+        id -> Int4,
+        name -> Text,
+        description -> Text,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::joinable!(album_tracks -> albums (album_id));
+diesel::joinable!(api_keys -> accounts (account_id));
+diesel::joinable!(book_progress -> books (book_id));
+diesel::joinable!(book_tags -> books (book_id));
+diesel::joinable!(books -> series (series_id));
+diesel::joinable!(book_tags -> tags (tag_id));
+diesel::joinable!(collection_items -> collections (collection_id));
+diesel::joinable!(game_tags -> games (game_id));
+diesel::joinable!(game_tags -> tags (tag_id));
+diesel::joinable!(reviews -> books (book_id));
+diesel::joinable!(reviews -> games (game_id));
+diesel::joinable!(show_episodes -> shows (show_id));
+diesel::joinable!(wplace -> wplace_albums (album_id));
+
 diesel::allow_tables_to_appear_in_same_query!(
+    accounts,
+    album_tracks,
+    albums,
     api_keys,
+    book_progress,
+    book_tags,
     books,
+    collection_items,
+    collections,
+    extra_schemas,
+    game_tags,
     games,
+    movies,
     projects,
+    rating_scales,
+    relations,
     reviews,
+    revisions,
+    series,
+    show_episodes,
+    shows,
+    slug_history,
+    status_changes,
+    status_transitions,
+    tags,
     wplace,
+    wplace_albums,
 );
@@ -0,0 +1,102 @@
+//! Field checks used through `#[validate(custom(function = ...))]` on the
+//! `New*` and `Update*` models. Lengths, ranges and URLs use validator's
+//! built-in checks instead.
+
+use crate::models::{InstallCommands, Links, Rating};
+use std::borrow::Cow;
+use validator::ValidationError;
+
+fn invalid(code: &'static str, message: &'static str) -> Result<(), ValidationError> {
+    Err(ValidationError::new(code).with_message(Cow::Borrowed(message)))
+}
+
+pub fn not_blank(value: &str) -> Result<(), ValidationError> {
+    if value.trim().is_empty() {
+        return invalid("blank", "can't be blank");
+    }
+
+    Ok(())
+}
+
+/// Rejects blank entries. The list itself may be empty.
+pub fn no_blank_entries<S: AsRef<str>>(values: &[S]) -> Result<(), ValidationError> {
+    if values.iter().any(|value| value.as_ref().trim().is_empty()) {
+        return invalid("blank_entry", "entries can't be blank");
+    }
+
+    Ok(())
+}
+
+/// Like [`no_blank_entries`], for array columns that allow nulls in SQL but
+/// shouldn't be given any.
+pub fn no_missing_entries<S: AsRef<str>>(values: &[Option<S>]) -> Result<(), ValidationError> {
+    if values.iter().any(|value| {
+        value
+            .as_ref()
+            .is_none_or(|value| value.as_ref().trim().is_empty())
+    }) {
+        return invalid("blank_entry", "entries can't be null or blank");
+    }
+
+    Ok(())
+}
+
+/// Accepts `#rgb` and `#rrggbb`.
+pub fn hex_color(value: &str) -> Result<(), ValidationError> {
+    let valid = value.strip_prefix('#').is_some_and(|digits| {
+        matches!(digits.len(), 3 | 6) && digits.chars().all(|c| c.is_ascii_hexdigit())
+    });
+
+    if !valid {
+        return invalid("hex_color", "must be a hex color like #1a2b3c");
+    }
+
+    Ok(())
+}
+
+pub fn non_negative(rating: &Rating) -> Result<(), ValidationError> {
+    if !rating.0.is_finite() || rating.0 < 0.0 {
+        return invalid("negative", "can't be negative");
+    }
+
+    Ok(())
+}
+
+pub fn links(links: &Links) -> Result<(), ValidationError> {
+    if !links.is_valid() {
+        return invalid(
+            "link",
+            "each link needs a label and an absolute http(s) URL",
+        );
+    }
+
+    Ok(())
+}
+
+pub fn install_commands(commands: &InstallCommands) -> Result<(), ValidationError> {
+    if !commands.is_valid() {
+        return invalid("install_command", "platforms and commands can't be blank");
+    }
+
+    Ok(())
+}
+
+/// Matches the `accounts.handle` check: 2-32 lowercase letters, digits, `_`
+/// or `-`, starting with a letter or digit.
+pub fn handle(value: &str) -> Result<(), ValidationError> {
+    let mut chars = value.chars();
+    let valid = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+        && (2..=32).contains(&value.len())
+        && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '_' | '-'));
+
+    if !valid {
+        return invalid(
+            "handle",
+            "must be 2-32 lowercase letters, digits, _ or -, starting with a letter or digit",
+        );
+    }
+
+    Ok(())
+}